@@ -0,0 +1,5 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| rbx_mesh::fuzz::roundtrip::<rbx_mesh::union_physics::CSGPHS8>(data));
@@ -0,0 +1,76 @@
+use binrw::BinReaderExt;
+
+use crate::detect::{Format, detect_at};
+use crate::error::{Error, ErrorKind};
+use crate::limits::ReadLimits;
+
+/// Any file which this crate can read.
+#[cfg_attr(
+	all(
+		feature = "mesh",
+		any(feature = "union-graphics", feature = "union-physics")
+	),
+	expect(clippy::large_enum_variant)
+)]
+#[derive(Debug, Clone)]
+pub enum AnyMesh {
+	#[cfg(feature = "mesh")]
+	Mesh(crate::mesh::Mesh),
+	#[cfg(feature = "union-graphics")]
+	UnionGraphics(crate::union_graphics::UnionGraphics),
+	#[cfg(feature = "union-physics")]
+	UnionPhysics(crate::union_physics::UnionPhysics),
+	/// CSGK may stand in for either union graphics or union physics data
+	#[cfg(any(feature = "union-graphics", feature = "union-physics"))]
+	CSGK(crate::csgk::CSGK),
+}
+
+/// Detect the format from the header and read it with the matching read_versioned.
+/// Formats whose feature is compiled out fail with ErrorKind::FeatureNotEnabled.
+pub fn read_any<R: BinReaderExt>(read: R) -> Result<AnyMesh, Error> {
+	read_any_with_limits(read, ReadLimits::DEFAULT)
+}
+
+/// read_any with limits on the counts in the file, for untrusted input.
+pub fn read_any_with_limits<R: BinReaderExt>(
+	mut read: R,
+	limits: ReadLimits,
+) -> Result<AnyMesh, Error> {
+	let start = read.stream_position()?;
+	let Some(format) = detect_at(&mut read, start)? else {
+		return Err(Error::new(ErrorKind::UnknownFormat, Some(start)));
+	};
+	if format.features().is_empty() {
+		return Err(Error::new(
+			ErrorKind::UnsupportedVersion(format),
+			Some(start),
+		));
+	}
+	if !format.is_supported() {
+		return Err(Error::new(
+			ErrorKind::FeatureNotEnabled(format),
+			Some(start),
+		));
+	}
+	Ok(match format {
+		#[cfg(feature = "mesh")]
+		Format::Mesh { .. } => AnyMesh::Mesh(crate::mesh::read_versioned_with_limits(read, limits)?),
+		#[cfg(feature = "union-graphics")]
+		Format::UnionGraphics(_) => AnyMesh::UnionGraphics(
+			crate::union_graphics::read_versioned_with_limits(read, limits)?,
+		),
+		#[cfg(feature = "union-physics")]
+		Format::UnionPhysics(_) | Format::Block => AnyMesh::UnionPhysics(
+			crate::union_physics::read_versioned_with_limits(read, limits)?,
+		),
+		#[cfg(any(feature = "union-graphics", feature = "union-physics"))]
+		Format::CSGK => AnyMesh::CSGK(crate::error::read_with(read, |read| read.read_le())?),
+		#[allow(unreachable_patterns)]
+		_ => {
+			return Err(Error::new(
+				ErrorKind::FeatureNotEnabled(format),
+				Some(start),
+			));
+		}
+	})
+}
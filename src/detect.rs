@@ -0,0 +1,152 @@
+/// The family and version of a file, read from its header alone.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Format {
+	/// `version major.minor`, for example 4.01 is major 4 and minor 1
+	Mesh { major: u8, minor: u8 },
+	/// Obfuscated CSGMDL with its version
+	UnionGraphics(u32),
+	/// CSGPHS with its version, excluding BLOCK
+	UnionPhysics(u32),
+	/// CSGPHS version 0 followed by BLOCK
+	Block,
+	/// A placeholder which may be found in place of union graphics or union physics data
+	CSGK,
+}
+
+impl Format {
+	/// The cargo features which can decode this format.  Any one of them is enough.
+	/// Empty when no version of this crate decodes it.
+	pub const fn features(&self) -> &'static [&'static str] {
+		match *self {
+			Self::Mesh {
+				major: 1,
+				minor: 0 | 1,
+			} => &["mesh-v1"],
+			Self::Mesh {
+				major: 2 | 5 | 6 | 7,
+				minor: 0,
+			}
+			| Self::Mesh {
+				major: 3 | 4,
+				minor: 0 | 1,
+			} => &["mesh"],
+			Self::UnionGraphics(2 | 4 | 5) => &["union-graphics"],
+			Self::UnionPhysics(3 | 5 | 6 | 7) | Self::Block => &["union-physics"],
+			Self::UnionPhysics(8) => &["csgphs-v8-zstd", "csgphs-v8-ruzstd"],
+			Self::CSGK => &["union-graphics", "union-physics"],
+			_ => &[],
+		}
+	}
+	/// Whether this build of the crate can decode this format.
+	pub const fn is_supported(&self) -> bool {
+		let enabled = match *self {
+			Self::Mesh { major: 1, .. } => cfg!(feature = "mesh-v1"),
+			Self::Mesh { .. } => cfg!(feature = "mesh"),
+			Self::UnionGraphics(_) => cfg!(feature = "union-graphics"),
+			Self::UnionPhysics(8) => {
+				cfg!(any(
+					feature = "csgphs-v8-zstd",
+					feature = "csgphs-v8-ruzstd"
+				))
+			}
+			Self::UnionPhysics(_) | Self::Block => cfg!(feature = "union-physics"),
+			Self::CSGK => cfg!(any(feature = "union-graphics", feature = "union-physics")),
+		};
+		enabled && !self.features().is_empty()
+	}
+}
+
+// "CSGMDL" xor the obfuscation noise
+const CSGMDL_OBFUSCATED: &[u8] = b"\x15\x7d\x29\x15\x75\x6c";
+// the obfuscation noise over the version which follows
+const CSGMDL_VERSION_NOISE: [u8; 4] = [48, 4, 52, 105];
+
+fn mesh_version(header: &[u8]) -> Option<Format> {
+	let line = header.strip_prefix(b"version ")?;
+	let end = line.iter().position(|&b| b == b'\n')?;
+	let line = &line[..end];
+	let line = line.strip_suffix(b"\r").unwrap_or(line);
+	let (major, minor) = core::str::from_utf8(line).ok()?.split_once('.')?;
+	if minor.len() != 2 {
+		return None;
+	}
+	Some(Format::Mesh {
+		major: major.parse().ok()?,
+		minor: minor.parse().ok()?,
+	})
+}
+
+fn u32_le(bytes: &[u8]) -> Option<u32> {
+	Some(u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?))
+}
+
+/// Identify the format of a file from its header without parsing the body.
+/// Returns None if the header is not recognized.
+pub fn detect_format(data: &[u8]) -> Option<Format> {
+	if data.starts_with(b"version ") {
+		return mesh_version(data);
+	}
+	if data.starts_with(b"CSGK") {
+		return Some(Format::CSGK);
+	}
+	if let Some(rest) = data.strip_prefix(b"CSGPHS") {
+		return match u32_le(rest)? {
+			0 if rest[4..].starts_with(b"BLOCK") => Some(Format::Block),
+			0 => None,
+			version => Some(Format::UnionPhysics(version)),
+		};
+	}
+	if let Some(rest) = data.strip_prefix(CSGMDL_OBFUSCATED) {
+		return Some(Format::UnionGraphics(
+			u32_le(rest)? ^ u32::from_le_bytes(CSGMDL_VERSION_NOISE),
+		));
+	}
+	None
+}
+
+/// Long enough for every header which detect_format inspects.
+#[cfg(any(
+	feature = "mesh",
+	feature = "union-graphics",
+	feature = "union-physics"
+))]
+const HEADER_LEN: u64 = 32;
+
+/// Detect the format of the header at start and seek back to it.
+#[cfg(any(
+	feature = "mesh",
+	feature = "union-graphics",
+	feature = "union-physics"
+))]
+pub(crate) fn detect_at<R: std::io::Read + std::io::Seek>(
+	read: &mut R,
+	start: u64,
+) -> std::io::Result<Option<Format>> {
+	use std::io::Read;
+	read.seek(std::io::SeekFrom::Start(start))?;
+	let mut header = Vec::new();
+	read.take(HEADER_LEN).read_to_end(&mut header)?;
+	read.seek(std::io::SeekFrom::Start(start))?;
+	Ok(detect_format(&header))
+}
+
+#[test]
+fn headers() {
+	assert_eq!(
+		detect_format(b"version 1.00\r\n"),
+		Some(Format::Mesh { major: 1, minor: 0 })
+	);
+	assert_eq!(
+		detect_format(b"version 4.01\n"),
+		Some(Format::Mesh { major: 4, minor: 1 })
+	);
+	assert_eq!(detect_format(b"version 4.01"), None);
+	assert_eq!(detect_format(b"CSGPHS\0\0\0\0BLOCK"), Some(Format::Block));
+	assert_eq!(
+		detect_format(b"CSGPHS\x09\0\0\0"),
+		Some(Format::UnionPhysics(9))
+	);
+	assert!(Format::UnionPhysics(9).features().is_empty());
+	assert_eq!(Format::Mesh { major: 3, minor: 1 }.features(), &["mesh"]);
+	assert_eq!(detect_format(b"CSGPHS\x08\0"), None);
+}
@@ -0,0 +1,287 @@
+use std::borrow::Cow;
+
+/// What went wrong, with the typed cause of the failure when this crate raised it.
+#[derive(Debug)]
+pub enum ErrorKind {
+	/// The stream ended early
+	UnexpectedEof,
+	Io(std::io::Error),
+	/// A magic or version header did not match, found is the debug representation of the bytes
+	BadMagic {
+		found: String,
+	},
+	/// An assertion in the format description failed
+	Assert(String),
+	/// No variant of an enum matched and no variant got past its magic
+	NoVariantMatch,
+	/// detect_format did not recognize the header
+	UnknownFormat,
+	/// The format is recognized but this build was compiled without any of Format::features
+	FeatureNotEnabled(crate::Format),
+	/// The family is recognized but no version of this crate decodes this version of it
+	UnsupportedVersion(crate::Format),
+	/// A count in the file exceeded the ReadLimits
+	Limit(crate::limits::LimitError),
+	#[cfg(feature = "mesh-v1")]
+	Mesh1(crate::mesh::Error1),
+	#[cfg(feature = "mesh")]
+	ChunkSize(crate::mesh::ChunkSizeError),
+	#[cfg(feature = "mesh")]
+	Draco(crate::mesh::DracoError),
+	#[cfg(feature = "union-graphics")]
+	FacesStateMachine(crate::union_graphics::FacesStateMachineError),
+	#[cfg(feature = "union-graphics")]
+	RangeMarker(crate::union_graphics::RangeMarkerError),
+	#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+	BitCounter(crate::union_physics::BitCounterError),
+	#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+	HullDecoder(crate::union_physics::HullDecoderError),
+	/// A custom error which has no variant of its own
+	Other(Box<dyn binrw::error::CustomError>),
+}
+
+/// A read or write failure with the stream offset, the format and the fields where it happened.
+///
+/// Returned by the read_*_versioned functions and read_any.  The binrw::Error returned by
+/// reading a single version directly converts with `rbx_mesh::Error::from`.
+#[derive(Debug)]
+pub struct Error {
+	pub kind: ErrorKind,
+	/// The stream offset of the failure, if known
+	pub pos: Option<u64>,
+	/// The format detected from the header, when the failure happened after it was recognized
+	pub format: Option<crate::Format>,
+	/// The binrw backtrace frames leading to the failure, outermost first.
+	/// Struct fields read like "While parsing field 'vertices' in Mesh5".
+	pub context: Vec<Cow<'static, str>>,
+}
+impl core::fmt::Display for Error {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+impl core::error::Error for Error {}
+impl Error {
+	pub(crate) const fn new(kind: ErrorKind, pos: Option<u64>) -> Self {
+		Self {
+			kind,
+			pos,
+			format: None,
+			context: Vec::new(),
+		}
+	}
+	/// The innermost context, which names the field that failed to parse.
+	pub fn field(&self) -> Option<&str> {
+		self.context.last().map(|context| context.as_ref())
+	}
+}
+impl From<std::io::Error> for Error {
+	fn from(error: std::io::Error) -> Self {
+		binrw::Error::Io(error).into()
+	}
+}
+
+/// A reader which remembers the deepest offset where a read came up short,
+/// so io errors can report an offset.
+pub(crate) struct TrackedReader<R> {
+	inner: R,
+	pos: u64,
+	failed_pos: Option<u64>,
+}
+impl<R: std::io::Read> std::io::Read for TrackedReader<R> {
+	fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+		let result = self.inner.read(buf);
+		match result {
+			Ok(len) if len != 0 || buf.is_empty() => self.pos += len as u64,
+			// enum variants are retried after a failure, so keep the deepest one
+			_ => self.failed_pos = self.failed_pos.max(Some(self.pos)),
+		}
+		result
+	}
+}
+impl<R: std::io::Seek> std::io::Seek for TrackedReader<R> {
+	fn seek(&mut self, from: std::io::SeekFrom) -> std::io::Result<u64> {
+		self.pos = self.inner.seek(from)?;
+		Ok(self.pos)
+	}
+}
+
+/// Run a binrw read and convert its error.  Failures without an offset of their own,
+/// such as io errors, take the offset of the read which failed, and the format is
+/// detected from the header at the start of the read.
+pub(crate) fn read_with<R, T>(
+	read: R,
+	f: impl FnOnce(&mut TrackedReader<R>) -> binrw::BinResult<T>,
+) -> Result<T, Error>
+where
+	R: std::io::Read + std::io::Seek,
+{
+	let mut read = TrackedReader {
+		inner: read,
+		pos: 0,
+		failed_pos: None,
+	};
+	let start = std::io::Seek::stream_position(&mut read)?;
+	f(&mut read).map_err(|error| {
+		let mut error = Error::from(error);
+		error.pos = error.pos.or(read.failed_pos);
+		error.format = crate::detect::detect_at(&mut read.inner, start)
+			.ok()
+			.flatten();
+		error
+	})
+}
+
+#[cfg(feature = "mesh")]
+fn draco(err: crate::mesh::DracoError) -> ErrorKind {
+	match err {
+		crate::mesh::DracoError::Limit(err) => ErrorKind::Limit(err),
+		err => ErrorKind::Draco(err),
+	}
+}
+
+#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+fn hull_decoder(err: crate::union_physics::HullDecoderError) -> ErrorKind {
+	match err {
+		crate::union_physics::HullDecoderError::BitCounter(err) => ErrorKind::BitCounter(err),
+		err => ErrorKind::HullDecoder(err),
+	}
+}
+
+fn downcast(err: Box<dyn binrw::error::CustomError>) -> ErrorKind {
+	macro_rules! downcast {
+		($err:ident, $($(#[$cfg:meta])* $ty:ty => $variant:path),* $(,)?) => {
+			$(
+				$(#[$cfg])*
+				let $err = match $err.downcast::<$ty>() {
+					Ok(err) => return $variant(*err),
+					Err(err) => err,
+				};
+			)*
+		};
+	}
+	downcast!(err,
+		std::io::Error => ErrorKind::Io,
+		crate::limits::LimitError => ErrorKind::Limit,
+		#[cfg(feature = "mesh-v1")]
+		crate::mesh::Error1 => ErrorKind::Mesh1,
+		#[cfg(feature = "mesh")]
+		crate::mesh::ChunkSizeError => ErrorKind::ChunkSize,
+		#[cfg(feature = "mesh")]
+		crate::mesh::DracoError => draco,
+		#[cfg(feature = "union-graphics")]
+		crate::union_graphics::FacesStateMachineError => ErrorKind::FacesStateMachine,
+		#[cfg(feature = "union-graphics")]
+		crate::union_graphics::RangeMarkerError => ErrorKind::RangeMarker,
+		#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+		crate::union_physics::BitCounterError => ErrorKind::BitCounter,
+		#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+		crate::union_physics::HullDecoderError => hull_decoder,
+	);
+	ErrorKind::Other(err)
+}
+
+/// Whether the variant failed on its magic or version header instead of somewhere in its body.
+fn is_magic_mismatch(error: &binrw::Error, enum_pos: u64) -> bool {
+	match error {
+		binrw::Error::Backtrace(backtrace) => is_magic_mismatch(&backtrace.error, enum_pos),
+		binrw::Error::BadMagic { pos, .. } | binrw::Error::NoVariantMatch { pos } => {
+			*pos == enum_pos
+		}
+		// Mesh1 has no magic, its version line is parsed as text
+		#[cfg(feature = "mesh-v1")]
+		binrw::Error::Custom { pos, err } => {
+			*pos == enum_pos
+				&& matches!(
+					err.downcast_ref::<crate::mesh::Error1>(),
+					Some(crate::mesh::Error1::Header)
+				)
+		}
+		binrw::Error::EnumErrors { variant_errors, .. } => variant_errors
+			.iter()
+			.all(|(_, error)| is_magic_mismatch(error, enum_pos)),
+		_ => false,
+	}
+}
+
+/// The deepest stream offset which an error knows about.
+fn error_pos(error: &binrw::Error) -> Option<u64> {
+	match error {
+		binrw::Error::BadMagic { pos, .. }
+		| binrw::Error::AssertFail { pos, .. }
+		| binrw::Error::Custom { pos, .. }
+		| binrw::Error::NoVariantMatch { pos } => Some(*pos),
+		binrw::Error::EnumErrors {
+			pos,
+			variant_errors,
+		} => variant_errors
+			.iter()
+			.filter_map(|(_, error)| error_pos(error))
+			.max()
+			.or(Some(*pos)),
+		binrw::Error::Backtrace(backtrace) => error_pos(&backtrace.error),
+		_ => None,
+	}
+}
+
+fn frame_message(frame: binrw::error::BacktraceFrame) -> Cow<'static, str> {
+	use binrw::error::BacktraceFrame;
+	match frame {
+		BacktraceFrame::Full { message, .. } | BacktraceFrame::Message(message) => message,
+		BacktraceFrame::Custom(context) => context.to_string().into(),
+	}
+}
+
+impl From<binrw::Error> for Error {
+	fn from(mut error: binrw::Error) -> Self {
+		let mut context = Vec::new();
+		loop {
+			let (kind, pos) = match error {
+				binrw::Error::Backtrace(backtrace) => {
+					// the frames of one backtrace are innermost first, and
+					// backtraces of enum variants are met after the enclosing one
+					context.extend(backtrace.frames.into_iter().rev().map(frame_message));
+					error = *backtrace.error;
+					continue;
+				}
+				binrw::Error::EnumErrors {
+					pos,
+					variant_errors,
+				} => {
+					// the variant which got past its magic is the real failure
+					let variant_error = variant_errors
+						.into_iter()
+						.filter(|(_, error)| !is_magic_mismatch(error, pos))
+						.max_by_key(|(_, error)| error_pos(error));
+					match variant_error {
+						Some((_, variant_error)) => {
+							error = variant_error;
+							continue;
+						}
+						None => (ErrorKind::NoVariantMatch, Some(pos)),
+					}
+				}
+				binrw::Error::BadMagic { pos, found } => (
+					ErrorKind::BadMagic {
+						found: format!("{found:?}"),
+					},
+					Some(pos),
+				),
+				binrw::Error::AssertFail { pos, message } => {
+					(ErrorKind::Assert(message), Some(pos))
+				}
+				binrw::Error::Io(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => {
+					(ErrorKind::UnexpectedEof, None)
+				}
+				binrw::Error::Io(error) => (ErrorKind::Io(error), None),
+				binrw::Error::Custom { pos, err } => (downcast(err), Some(pos)),
+				binrw::Error::NoVariantMatch { pos } => (ErrorKind::NoVariantMatch, Some(pos)),
+				_ => (ErrorKind::Other(Box::new(error.to_string())), None),
+			};
+			return Self {
+				context,
+				..Self::new(kind, pos)
+			};
+		}
+	}
+}
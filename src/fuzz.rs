@@ -0,0 +1,188 @@
+use binrw::{BinRead, BinReaderExt, BinWrite, BinWriterExt};
+use std::io::Cursor;
+
+/// Read M from the start of data with the default args, and the number of bytes read.
+pub fn read<M>(data: &[u8]) -> binrw::BinResult<(M, u64)>
+where
+	M: for<'a> BinRead<Args<'a>: Default>,
+{
+	let mut rbuf = Cursor::new(data);
+	let mesh = rbuf.read_le()?;
+	Ok((mesh, rbuf.position()))
+}
+
+/// Write M with the default args.
+pub fn write<M>(mesh: &M) -> binrw::BinResult<Vec<u8>>
+where
+	M: for<'a> BinWrite<Args<'a>: Default>,
+{
+	let mut wbuf = Cursor::new(Vec::new());
+	wbuf.write_le(mesh)?;
+	Ok(wbuf.into_inner())
+}
+
+/// CSGPHS8 reads hulls which are not closed, manifold and genus 0, but can only write those which are.
+#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+fn is_unencodable_hull(error: &binrw::Error) -> bool {
+	match error {
+		binrw::Error::Backtrace(backtrace) => is_unencodable_hull(&backtrace.error),
+		binrw::Error::Custom { err, .. } => err
+			.downcast_ref::<crate::union_physics::HullEncoderError>()
+			.is_some(),
+		_ => false,
+	}
+}
+#[cfg(not(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd")))]
+fn is_unencodable_hull(_error: &binrw::Error) -> bool {
+	false
+}
+
+/// Read arbitrary bytes as M with the default ReadLimits.
+/// Anything which reads and writes must read back and write the same bytes again.
+/// Anything which reads must write, except CSGPHS8 hulls which the encoder rejects.
+///
+/// The input itself is not compared since it may have trailing data
+/// or fields which are recalculated on write.
+pub fn roundtrip<M>(data: &[u8])
+where
+	M: std::fmt::Debug,
+	M: for<'a> BinRead<Args<'a>: Default>,
+	M: for<'a> BinWrite<Args<'a>: Default>,
+{
+	let Ok((mesh, _)) = read::<M>(data) else {
+		return;
+	};
+	let written = match write(&mesh) {
+		Ok(written) => written,
+		Err(e) if is_unencodable_hull(&e) => return,
+		Err(e) => panic!("Mesh which read failed to write: {e}\n{mesh:?}"),
+	};
+	let (reread, _) = read::<M>(&written)
+		.unwrap_or_else(|e| panic!("Written mesh failed to read: {e}\n{mesh:?}"));
+	let rewritten = write(&reread).expect("Reread mesh failed to write");
+	assert_eq!(written, rewritten, "Round trip failed");
+}
+
+/// Read arbitrary bytes as a GLB or glTF file.
+/// Anything which reads must write a GLB which reads back with the same parts and corner positions,
+/// and must either convert into a Mesh5 which writes or fail to convert with an error.
+///
+/// Vertex indices are not compared since the reader gives each part its own vertices.
+#[cfg(feature = "mesh")]
+pub fn glb(data: &[u8]) {
+	use crate::gltf::Glb;
+	let Ok(glb) = Glb::read(data) else {
+		return;
+	};
+	let mut written = Vec::new();
+	glb.write(&mut written)
+		.unwrap_or_else(|e| panic!("Glb which read failed to write: {e}\n{glb:?}"));
+	let reread =
+		Glb::read(&written).unwrap_or_else(|e| panic!("Written glb failed to read: {e}\n{glb:?}"));
+	assert_eq!(reread.mesh.parts, glb.mesh.parts, "Parts changed");
+	let corners = |glb: &Glb| -> Vec<[u32; 3]> {
+		glb.mesh
+			.indices
+			.as_flattened()
+			.iter()
+			.map(|&vertex| glb.mesh.positions[vertex as usize].map(f32::to_bits))
+			.collect()
+	};
+	assert_eq!(corners(&reread), corners(&glb), "Corner positions changed");
+	if let Ok(converted) = glb.to_mesh5() {
+		write(&converted.mesh).expect("Converted mesh failed to write");
+	}
+}
+
+#[cfg(any(
+	feature = "mesh",
+	feature = "union-graphics",
+	feature = "csgphs-v8-zstd",
+	feature = "csgphs-v8-ruzstd"
+))]
+#[test]
+fn mutated_samples() {
+	// run each target over a sample and some deterministic mutations of it.
+	// this only checks the harness, cargo fuzz does the real work
+	let mut state = 0x9E37_79B9u32;
+	let mut next = move || {
+		state ^= state << 13;
+		state ^= state >> 17;
+		state ^= state << 5;
+		state as usize
+	};
+	let mut check = |bytes: &[u8], target: fn(&[u8])| {
+		target(bytes);
+		for _ in 0..24 {
+			let mut mutated = bytes.to_vec();
+			match next() % 3 {
+				0 => mutated.truncate(next() % bytes.len()),
+				1 => {
+					for _ in 0..1 + next() % 4 {
+						let i = next() % mutated.len();
+						mutated[i] = next() as u8;
+					}
+				}
+				_ => {
+					// counts live near the start of the headers
+					let i = next() % mutated.len().min(64);
+					mutated[i] ^= 1 << (next() % 8);
+				}
+			}
+			target(&mutated);
+		}
+	};
+	let read = |path: &str| std::fs::read(path).unwrap();
+
+	#[cfg(feature = "mesh-v1")]
+	check(
+		b"version 1.00\r\n1\r\n[1,2,3][0,1,0][0.5,0.5,0][4,5,6][0,1,0][1,0,0][7,8,9][0,1,0][0,1,0]",
+		roundtrip::<crate::mesh::Mesh1>,
+	);
+	#[cfg(feature = "mesh")]
+	{
+		check(&read("meshes/torso.mesh"), roundtrip::<crate::mesh::Mesh2>);
+		check(&read("meshes/5648093777"), roundtrip::<crate::mesh::Mesh3>);
+		check(&read("meshes/sphere.mesh"), roundtrip::<crate::mesh::Mesh4>);
+		check(&read("meshes/13674780763"), roundtrip::<crate::mesh::Mesh5>);
+		check(
+			&read("meshes/mesh6_from_13674780763.bin"),
+			roundtrip::<crate::mesh::Mesh6>,
+		);
+		check(
+			&read("meshes/mesh7_127279296594138.bin"),
+			roundtrip::<crate::mesh::Mesh7>,
+		);
+		check(
+			&read("meshes/mesh7_quantized_cube.bin"),
+			roundtrip::<crate::mesh::Mesh7>,
+		);
+		check(
+			&read("meshes/mesh7_from_5648093777.bin"),
+			roundtrip::<crate::mesh::Mesh7>,
+		);
+		check(&read("meshes/mesh7_127279296594138.glb"), glb);
+		// a skinned sample, without the morph targets which the reader ignores
+		let (mesh, _) =
+			crate::fuzz::read::<crate::mesh::Mesh5>(&read("meshes/13674780763")).unwrap();
+		let skinned = crate::gltf::Glb {
+			morph_targets: Vec::new(),
+			..crate::gltf::Glb::from(&mesh)
+		};
+		let mut sample = Vec::new();
+		skinned.write(&mut sample).unwrap();
+		check(&sample, glb);
+	}
+	#[cfg(feature = "union-graphics")]
+	{
+		use crate::union_graphics::{CSGMDL2, CSGMDL4, CSGMDL5};
+		check(&read("meshes/5692112940_2.meshdata"), roundtrip::<CSGMDL2>);
+		check(&read("meshes/4500696697_4.meshdata"), roundtrip::<CSGMDL4>);
+		check(&read("meshes/14846974687_5.meshdata"), roundtrip::<CSGMDL5>);
+	}
+	#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+	check(
+		&read("meshes/CSGPHS_8_00.data"),
+		roundtrip::<crate::union_physics::CSGPHS8>,
+	);
+}
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use super::{Glb, GlbSkin};
+use crate::mesh::{
+	Bone4, BoneId4, Envelope4, Face2, Lod3, Mesh4, Mesh5, Subset4, Vertex2, VertexId2,
+};
+use crate::triangle_mesh::{ConvertError, Converted, DroppedAttribute};
+
+/// Roblox limits each subset to this many bones.
+const SUBSET_BONE_COUNT: usize = 26;
+
+/// Quantize weights so they sum to 255, pairing each with its joint.
+fn quantize_weights(joints: [u16; 4], weights: [f32; 4]) -> [(u16, u8); 4] {
+	let mut quantized =
+		weights.map(|weight| (weight.clamp(0.0, 1.0) * u8::MAX as f32).round() as i32);
+	let total: i32 = quantized.iter().sum();
+	// the rounding error goes to the heaviest weight
+	let (heaviest, _) = quantized
+		.iter()
+		.enumerate()
+		.max_by_key(|&(_, weight)| *weight)
+		.unwrap();
+	quantized[heaviest] += u8::MAX as i32 - total;
+	core::array::from_fn(|i| (joints[i], quantized[i].clamp(0, u8::MAX as i32) as u8))
+}
+
+#[derive(Default)]
+struct SubsetBuilder {
+	bones: Vec<u16>,
+	faces: Vec<[u32; 3]>,
+}
+
+/// Vertices, envelopes and faces grouped into subsets of at most 26 bones.
+/// Vertices which are shared between subsets are duplicated, and vertices
+/// which are not used by any face are dropped.
+#[derive(Default)]
+struct Skinning {
+	vertices: Vec<Vertex2>,
+	envelopes: Vec<Envelope4>,
+	faces: Vec<Face2>,
+	bones: Vec<Bone4>,
+	bone_names: Vec<u8>,
+	subsets: Vec<Subset4>,
+}
+impl Skinning {
+	fn new(
+		vertices: &[Vertex2],
+		faces: &[Face2],
+		lods: &[Lod3],
+		skin: &GlbSkin,
+	) -> Result<Self, ConvertError> {
+		if skin.vertex_joints.len() != vertices.len() {
+			return Err(ConvertError::AttributeLength("vertex_joints"));
+		}
+		if skin.vertex_weights.len() != vertices.len() {
+			return Err(ConvertError::AttributeLength("vertex_weights"));
+		}
+		if u16::MAX as usize <= skin.joints.len() {
+			return Err(ConvertError::TooManyBones(skin.joints.len()));
+		}
+		let in_range = |joint: usize| {
+			if joint < skin.joints.len() {
+				Ok(joint as u16)
+			} else {
+				Err(ConvertError::JointOutOfRange(joint as u16))
+			}
+		};
+
+		let mut skinning = Self::default();
+		for joint in &skin.joints {
+			let parent = joint.parent.map(in_range).transpose()?;
+			skinning.bones.push(Bone4 {
+				bone_name_pos: skinning.bone_names.len() as u32,
+				parent: BoneId4::new(parent),
+				lod_parent: BoneId4::new(parent),
+				cull_distance: 0.0,
+				cframe: joint.cframe.clone(),
+			});
+			skinning.bone_names.extend_from_slice(joint.name.as_bytes());
+			skinning.bone_names.push(0);
+		}
+		let influences: Vec<[(u16, u8); 4]> = skin
+			.vertex_joints
+			.iter()
+			.zip(&skin.vertex_weights)
+			.map(|(&joints, &weights)| {
+				for joint in joints {
+					in_range(joint as usize)?;
+				}
+				Ok(quantize_weights(joints, weights))
+			})
+			.collect::<Result<_, _>>()?;
+
+		// subsets never span levels of detail, so the lods stay valid
+		let bounds: Vec<usize> = lods.iter().map(|lod| lod.0 as usize).collect();
+		for lod in bounds.windows(2) {
+			let mut subset = SubsetBuilder::default();
+			for Face2(face) in faces.get(lod[0]..lod[1]).unwrap_or_default() {
+				let face = face.clone().map(|vertex| vertex.0);
+				let mut bones = subset.bones.clone();
+				for &(joint, weight) in face.iter().flat_map(|&vertex| &influences[vertex as usize])
+				{
+					if weight != 0 && !bones.contains(&joint) {
+						bones.push(joint);
+					}
+				}
+				if SUBSET_BONE_COUNT < bones.len() {
+					skinning.push_subset(vertices, &influences, core::mem::take(&mut subset));
+					bones.retain(|bone| {
+						face.iter()
+							.flat_map(|&vertex| &influences[vertex as usize])
+							.any(|&(joint, weight)| weight != 0 && joint == *bone)
+					});
+				}
+				subset.bones = bones;
+				subset.faces.push(face);
+			}
+			skinning.push_subset(vertices, &influences, subset);
+		}
+		Ok(skinning)
+	}
+	fn push_subset(
+		&mut self,
+		vertices: &[Vertex2],
+		influences: &[[(u16, u8); 4]],
+		subset: SubsetBuilder,
+	) {
+		if subset.faces.is_empty() {
+			return;
+		}
+		let faces_offset = self.faces.len() as u32;
+		let vertices_offset = self.vertices.len() as u32;
+		let mut remap = HashMap::new();
+		for face in subset.faces {
+			let face = face.map(|vertex| {
+				let index = *remap.entry(vertex).or_insert_with(|| {
+					let index = self.vertices.len() as u32;
+					self.vertices.push(vertices[vertex as usize].clone());
+					// envelopes index into the bones of the subset
+					let influence = &influences[vertex as usize];
+					self.envelopes.push(Envelope4 {
+						bones: influence.map(|(joint, weight)| {
+							let local = subset.bones.iter().position(|&bone| bone == joint);
+							local.filter(|_| weight != 0).unwrap_or(0) as u8
+						}),
+						weights: influence.map(|(_, weight)| weight),
+					});
+					index
+				});
+				VertexId2(index)
+			});
+			self.faces.push(Face2(face));
+		}
+		let mut bones = core::array::from_fn(|_| BoneId4::new(None));
+		for (bone, &joint) in bones.iter_mut().zip(&subset.bones) {
+			*bone = BoneId4::new(Some(joint));
+		}
+		self.subsets.push(Subset4 {
+			faces_offset,
+			faces_len: self.faces.len() as u32 - faces_offset,
+			vertices_offset,
+			vertices_len: self.vertices.len() as u32 - vertices_offset,
+			bone_count: subset.bones.len() as u32,
+			bones,
+		});
+	}
+}
+
+impl Glb {
+	fn dropped(&self, mut dropped: Vec<DroppedAttribute>) -> Vec<DroppedAttribute> {
+		if !self.morph_targets.is_empty() {
+			dropped.push(DroppedAttribute::MorphTargets);
+		}
+		dropped
+	}
+	/// Parts become lods, and the skin becomes bones, envelopes and subsets.
+	pub fn to_mesh4(&self) -> Result<Converted<Mesh4>, ConvertError> {
+		let Converted { mut mesh, dropped } = self.mesh.to_mesh4()?;
+		if let Some(skin) = &self.skin {
+			let skinning = Skinning::new(&mesh.vertices, &mesh.faces, &mesh.lods, skin)?;
+			mesh.vertices = skinning.vertices;
+			mesh.envelopes = skinning.envelopes;
+			mesh.faces = skinning.faces;
+			mesh.bones = skinning.bones;
+			mesh.bone_names = skinning.bone_names;
+			mesh.subsets = skinning.subsets;
+		}
+		Ok(Converted {
+			mesh,
+			dropped: self.dropped(dropped),
+		})
+	}
+	/// Parts become lods, and the skin becomes bones, envelopes and subsets.
+	/// The mesh has no FACS data.
+	pub fn to_mesh5(&self) -> Result<Converted<Mesh5>, ConvertError> {
+		let Converted { mut mesh, dropped } = self.mesh.to_mesh5()?;
+		if let Some(skin) = &self.skin {
+			let skinning = Skinning::new(&mesh.vertices, &mesh.faces, &mesh.lods, skin)?;
+			mesh.vertices = skinning.vertices;
+			mesh.envelopes = skinning.envelopes;
+			mesh.faces = skinning.faces;
+			mesh.bones = skinning.bones;
+			mesh.bone_names = skinning.bone_names;
+			mesh.subsets = skinning.subsets;
+		}
+		Ok(Converted {
+			mesh,
+			dropped: self.dropped(dropped),
+		})
+	}
+}
+
+#[test]
+fn quantize() {
+	assert_eq!(
+		quantize_weights([1, 2, 3, 0], [0.5, 0.25, 0.25, 0.0]),
+		[(1, 127), (2, 64), (3, 64), (0, 0)]
+	);
+	assert_eq!(
+		quantize_weights([1, 2, 3, 4], [0.25; 4]),
+		[(1, 64), (2, 64), (3, 64), (4, 63)]
+	);
+}
+
+#[test]
+fn split_subsets() {
+	use super::GlbJoint;
+	use crate::TriangleMesh;
+	use crate::mesh::CFrame4;
+	// one triangle per bone
+	let bone_count: u16 = 30;
+	let mut mesh = TriangleMesh::default();
+	let mut vertex_joints = Vec::new();
+	for bone in 0..bone_count {
+		let z = bone as f32;
+		mesh.push_part(
+			&[[0.0, 0.0, z], [1.0, 0.0, z], [0.0, 1.0, z]],
+			[[0, 1, 2]].into_iter(),
+		);
+		vertex_joints.extend([[bone, 0, 0, 0]; 3]);
+	}
+	// a single lod
+	mesh.parts.clear();
+	let glb = Glb {
+		skin: Some(GlbSkin {
+			joints: (0..bone_count)
+				.map(|bone| GlbJoint {
+					name: format!("Bone{bone}"),
+					parent: bone.checked_sub(1).map(usize::from),
+					cframe: CFrame4::IDENTITY,
+				})
+				.collect(),
+			vertex_weights: vec![[1.0, 0.0, 0.0, 0.0]; vertex_joints.len()],
+			vertex_joints,
+		}),
+		mesh,
+		morph_targets: Vec::new(),
+	};
+	let mesh = glb.to_mesh4().unwrap().mesh;
+	let bone_counts: Vec<u32> = mesh
+		.subsets
+		.iter()
+		.map(|subset| subset.bone_count)
+		.collect();
+	assert_eq!(bone_counts, [26, 4]);
+	assert_eq!(mesh.subsets[1].faces_offset, 26);
+	assert_eq!(mesh.subsets[1].vertices_offset, 26 * 3);
+	assert_eq!(mesh.envelopes[26 * 3].bones, [0; 4]);
+	assert_eq!(mesh.subsets[1].bones[0].get(), Some(26));
+}
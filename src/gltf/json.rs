@@ -0,0 +1,251 @@
+//! A minimal JSON reader, just enough for glTF documents.
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum JsonError {
+	UnexpectedEof,
+	UnexpectedByte(usize),
+	InvalidEscape(usize),
+	InvalidNumber(usize),
+	TooDeep,
+	TrailingData(usize),
+}
+impl core::fmt::Display for JsonError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+impl core::error::Error for JsonError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+	Null,
+	Bool(bool),
+	Number(f64),
+	String(String),
+	Array(Vec<Value>),
+	/// Keys in document order
+	Object(Vec<(String, Value)>),
+}
+impl Value {
+	pub fn get(&self, key: &str) -> Option<&Value> {
+		match self {
+			Value::Object(entries) => entries
+				.iter()
+				.find_map(|(k, value)| (k == key).then_some(value)),
+			_ => None,
+		}
+	}
+	pub fn as_f64(&self) -> Option<f64> {
+		match *self {
+			Value::Number(number) => Some(number),
+			_ => None,
+		}
+	}
+	pub fn as_usize(&self) -> Option<usize> {
+		self.as_f64()
+			.filter(|number| number.fract() == 0.0 && 0.0 <= *number && *number <= u32::MAX as f64)
+			.map(|number| number as usize)
+	}
+	pub fn as_str(&self) -> Option<&str> {
+		match self {
+			Value::String(string) => Some(string),
+			_ => None,
+		}
+	}
+	pub fn as_bool(&self) -> Option<bool> {
+		match *self {
+			Value::Bool(value) => Some(value),
+			_ => None,
+		}
+	}
+	pub fn as_array(&self) -> Option<&[Value]> {
+		match self {
+			Value::Array(values) => Some(values),
+			_ => None,
+		}
+	}
+}
+
+// deeply nested documents would otherwise overflow the stack
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+	bytes: &'a [u8],
+	pos: usize,
+}
+impl Parser<'_> {
+	fn skip_whitespace(&mut self) {
+		while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.pos) {
+			self.pos += 1;
+		}
+	}
+	fn peek(&mut self) -> Result<u8, JsonError> {
+		self.skip_whitespace();
+		self.bytes
+			.get(self.pos)
+			.copied()
+			.ok_or(JsonError::UnexpectedEof)
+	}
+	fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+		if self.peek()? != byte {
+			return Err(JsonError::UnexpectedByte(self.pos));
+		}
+		self.pos += 1;
+		Ok(())
+	}
+	fn literal(&mut self, literal: &[u8], value: Value) -> Result<Value, JsonError> {
+		if !self.bytes[self.pos..].starts_with(literal) {
+			return Err(JsonError::UnexpectedByte(self.pos));
+		}
+		self.pos += literal.len();
+		Ok(value)
+	}
+	fn value(&mut self, depth: usize) -> Result<Value, JsonError> {
+		if MAX_DEPTH < depth {
+			return Err(JsonError::TooDeep);
+		}
+		match self.peek()? {
+			b'{' => {
+				self.pos += 1;
+				let mut entries = Vec::new();
+				if self.peek()? == b'}' {
+					self.pos += 1;
+					return Ok(Value::Object(entries));
+				}
+				loop {
+					if self.peek()? != b'"' {
+						return Err(JsonError::UnexpectedByte(self.pos));
+					}
+					let key = self.string()?;
+					self.expect(b':')?;
+					entries.push((key, self.value(depth + 1)?));
+					match self.peek()? {
+						b',' => self.pos += 1,
+						b'}' => {
+							self.pos += 1;
+							return Ok(Value::Object(entries));
+						}
+						_ => return Err(JsonError::UnexpectedByte(self.pos)),
+					}
+				}
+			}
+			b'[' => {
+				self.pos += 1;
+				let mut values = Vec::new();
+				if self.peek()? == b']' {
+					self.pos += 1;
+					return Ok(Value::Array(values));
+				}
+				loop {
+					values.push(self.value(depth + 1)?);
+					match self.peek()? {
+						b',' => self.pos += 1,
+						b']' => {
+							self.pos += 1;
+							return Ok(Value::Array(values));
+						}
+						_ => return Err(JsonError::UnexpectedByte(self.pos)),
+					}
+				}
+			}
+			b'"' => Ok(Value::String(self.string()?)),
+			b't' => self.literal(b"true", Value::Bool(true)),
+			b'f' => self.literal(b"false", Value::Bool(false)),
+			b'n' => self.literal(b"null", Value::Null),
+			b'-' | b'0'..=b'9' => self.number(),
+			_ => Err(JsonError::UnexpectedByte(self.pos)),
+		}
+	}
+	fn number(&mut self) -> Result<Value, JsonError> {
+		let start = self.pos;
+		while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.bytes.get(self.pos) {
+			self.pos += 1;
+		}
+		core::str::from_utf8(&self.bytes[start..self.pos])
+			.ok()
+			.and_then(|number| number.parse().ok())
+			.map(Value::Number)
+			.ok_or(JsonError::InvalidNumber(start))
+	}
+	fn hex4(&mut self) -> Result<u32, JsonError> {
+		let hex = self
+			.bytes
+			.get(self.pos..self.pos + 4)
+			.ok_or(JsonError::UnexpectedEof)?;
+		let value = core::str::from_utf8(hex)
+			.ok()
+			.and_then(|hex| u32::from_str_radix(hex, 16).ok())
+			.ok_or(JsonError::InvalidEscape(self.pos))?;
+		self.pos += 4;
+		Ok(value)
+	}
+	fn string(&mut self) -> Result<String, JsonError> {
+		// opening quote
+		self.pos += 1;
+		let mut bytes = Vec::new();
+		loop {
+			let byte = *self.bytes.get(self.pos).ok_or(JsonError::UnexpectedEof)?;
+			self.pos += 1;
+			match byte {
+				b'"' => break,
+				b'\\' => {
+					let escape = *self.bytes.get(self.pos).ok_or(JsonError::UnexpectedEof)?;
+					self.pos += 1;
+					let c = match escape {
+						b'"' => '"',
+						b'\\' => '\\',
+						b'/' => '/',
+						b'b' => '\u{8}',
+						b'f' => '\u{c}',
+						b'n' => '\n',
+						b'r' => '\r',
+						b't' => '\t',
+						b'u' => {
+							let mut code = self.hex4()?;
+							// surrogate pair
+							if (0xD800..0xDC00).contains(&code)
+								&& self.bytes[self.pos..].starts_with(b"\\u")
+							{
+								self.pos += 2;
+								let low = self.hex4()?;
+								code = 0x10000
+									+ ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00)
+									& 0x3FF);
+							}
+							char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+						}
+						_ => return Err(JsonError::InvalidEscape(self.pos - 1)),
+					};
+					bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+				}
+				byte => bytes.push(byte),
+			}
+		}
+		Ok(String::from_utf8_lossy(&bytes).into_owned())
+	}
+}
+
+pub fn parse(bytes: &[u8]) -> Result<Value, JsonError> {
+	let mut parser = Parser { bytes, pos: 0 };
+	let value = parser.value(0)?;
+	parser.skip_whitespace();
+	if parser.pos != bytes.len() {
+		return Err(JsonError::TrailingData(parser.pos));
+	}
+	Ok(value)
+}
+
+#[test]
+fn parse_document() {
+	let value =
+		parse(r#" {"a": [1, -2.5e1, true, null], "b": {"c": "d\"\u00e9"}} "#.as_bytes()).unwrap();
+	let a = value.get("a").unwrap().as_array().unwrap();
+	assert_eq!(a[0].as_usize(), Some(1));
+	assert_eq!(a[1].as_f64(), Some(-25.0));
+	assert_eq!(a[2].as_bool(), Some(true));
+	assert_eq!(a[3], Value::Null);
+	let c = value.get("b").unwrap().get("c").unwrap().as_str();
+	assert_eq!(c, Some("d\"é"));
+	assert_eq!(parse(b"[1] x"), Err(JsonError::TrailingData(4)));
+	assert_eq!(parse(&[b'['; 1000]), Err(JsonError::TooDeep));
+}
@@ -0,0 +1,591 @@
+//! glTF 2.0 binary (GLB) export.
+//!
+//! Meshes with bones are written as a skinned mesh with one joint per bone,
+//! and FACS poses are baked into morph targets by posing the face bones.
+//! Each part of the TriangleMesh is written as its own node.
+//!
+//! GLB and glTF files can also be read back, and converted into Mesh4 or Mesh5.
+
+mod convert;
+mod json;
+mod read;
+
+pub use json::JsonError;
+pub use read::GltfError;
+
+use std::io::Write;
+
+use crate::TriangleMesh;
+use crate::mesh::{CFrame4, Facs5, Skeleton, VertexWeights};
+
+/// A bone in its bind pose.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlbJoint {
+	pub name: String,
+	/// Index into GlbSkin.joints
+	pub parent: Option<usize>,
+	/// Relative to the mesh, not the parent
+	pub cframe: CFrame4,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GlbSkin {
+	pub joints: Vec<GlbJoint>,
+	/// Indices into joints, one entry per vertex
+	pub vertex_joints: Vec<[u16; 4]>,
+	/// Normalized weights, one entry per vertex
+	pub vertex_weights: Vec<[f32; 4]>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlbMorphTarget {
+	pub name: String,
+	/// One entry per vertex
+	pub position_deltas: Vec<[f32; 3]>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Glb {
+	pub mesh: TriangleMesh,
+	pub skin: Option<GlbSkin>,
+	pub morph_targets: Vec<GlbMorphTarget>,
+}
+
+impl GlbSkin {
+	fn new(vertex_count: usize, skeleton: &Skeleton) -> Option<Self> {
+		let vertex_weights = skeleton.vertex_weights();
+		if skeleton.is_empty() || vertex_weights.len() != vertex_count {
+			return None;
+		}
+		let joints = skeleton
+			.bones()
+			.map(|bone| GlbJoint {
+				name: bone
+					.name
+					.map_or_else(|| format!("Bone{}", bone.id), str::to_owned),
+				parent: bone.parent.map(usize::from),
+				cframe: bone.bone.cframe.clone(),
+			})
+			.collect();
+		// vertices without weights follow the first bone
+		let (vertex_joints, vertex_weights) = vertex_weights
+			.into_iter()
+			.map(|weights| match weights {
+				Some(VertexWeights { bones, weights }) => (bones, weights),
+				None => ([0; 4], [1.0, 0.0, 0.0, 0.0]),
+			})
+			.unzip();
+		Some(Self {
+			joints,
+			vertex_joints,
+			vertex_weights,
+		})
+	}
+}
+
+/// Each FACS pose becomes a morph target, posed through the skeleton
+/// with the offsets of that pose alone.
+fn morph_targets(positions: &[[f32; 3]], skeleton: &Skeleton, facs: &Facs5) -> Vec<GlbMorphTarget> {
+	let Ok(rig) = facs.decode() else {
+		return Vec::new();
+	};
+	rig.poses
+		.iter()
+		.map(|pose| {
+			let posed = skeleton.deform_points(
+				positions.iter().map(|&position| (position, [0.0; 3])),
+				&rig.pose_offsets(skeleton, &pose.offsets),
+			);
+			GlbMorphTarget {
+				name: pose.name.clone(),
+				position_deltas: posed
+					.positions
+					.iter()
+					.zip(positions)
+					.map(|(posed, position)| {
+						[
+							posed[0] - position[0],
+							posed[1] - position[1],
+							posed[2] - position[2],
+						]
+					})
+					.collect(),
+			}
+		})
+		.collect()
+}
+
+impl Glb {
+	fn skinned(mesh: TriangleMesh, skeleton: &Skeleton, facs: Option<&Facs5>) -> Self {
+		let skin = GlbSkin::new(mesh.vertex_count(), skeleton);
+		let morph_targets = match (&skin, facs) {
+			(Some(_), Some(facs)) => morph_targets(&mesh.positions, skeleton, facs),
+			_ => Vec::new(),
+		};
+		Self {
+			mesh,
+			skin,
+			morph_targets,
+		}
+	}
+	/// Write a GLB file.
+	pub fn write<W: Write>(&self, mut write: W) -> std::io::Result<()> {
+		let (json, bin) = GltfBuilder::build(self);
+		let mut json = json.into_bytes();
+		while !json.len().is_multiple_of(4) {
+			json.push(b' ');
+		}
+		let too_large = || std::io::Error::other("GLB is larger than 4GiB");
+		let json_len = u32::try_from(json.len()).map_err(|_| too_large())?;
+		let bin_len = u32::try_from(bin.len()).map_err(|_| too_large())?;
+		// header, json chunk header, bin chunk header
+		let total_len = 12u32
+			.checked_add(8 + json_len)
+			.and_then(|len| len.checked_add(8 + bin_len))
+			.ok_or_else(too_large)?;
+		write.write_all(b"glTF")?;
+		write.write_all(&2u32.to_le_bytes())?;
+		write.write_all(&total_len.to_le_bytes())?;
+		write.write_all(&json_len.to_le_bytes())?;
+		write.write_all(b"JSON")?;
+		write.write_all(&json)?;
+		write.write_all(&bin_len.to_le_bytes())?;
+		write.write_all(b"BIN\0")?;
+		write.write_all(&bin)?;
+		Ok(())
+	}
+}
+
+fn json_string(value: &str) -> String {
+	let mut escaped = String::with_capacity(value.len() + 2);
+	escaped.push('"');
+	for c in value.chars() {
+		match c {
+			'"' => escaped.push_str("\\\""),
+			'\\' => escaped.push_str("\\\\"),
+			c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+			c => escaped.push(c),
+		}
+	}
+	escaped.push('"');
+	escaped
+}
+
+/// JSON has no NaN or infinity, so NaN is written as 0 and infinities as the largest finite f32.
+fn json_floats(values: &[f32]) -> String {
+	let values: Vec<String> = values
+		.iter()
+		.map(|&value| match value {
+			value if value.is_nan() => 0.0,
+			value => value.clamp(f32::MIN, f32::MAX),
+		})
+		.map(|value| value.to_string())
+		.collect();
+	format!("[{}]", values.join(","))
+}
+
+fn column_major(cframe: &CFrame4) -> [f32; 16] {
+	#[rustfmt::skip]
+	let matrix = [
+		cframe.r00, cframe.r10, cframe.r20, 0.0,
+		cframe.r01, cframe.r11, cframe.r21, 0.0,
+		cframe.r02, cframe.r12, cframe.r22, 0.0,
+		cframe.x, cframe.y, cframe.z, 1.0,
+	];
+	matrix
+}
+
+const UNSIGNED_BYTE: u32 = 5121;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+#[derive(Default)]
+struct GltfBuilder {
+	bin: Vec<u8>,
+	buffer_views: Vec<String>,
+	accessors: Vec<String>,
+}
+impl GltfBuilder {
+	fn push_view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+		while !self.bin.len().is_multiple_of(4) {
+			self.bin.push(0);
+		}
+		let target = target.map_or(String::new(), |target| format!(",\"target\":{target}"));
+		self.buffer_views.push(format!(
+			"{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{}{target}}}",
+			self.bin.len(),
+			bytes.len()
+		));
+		self.bin.extend_from_slice(bytes);
+		self.buffer_views.len() - 1
+	}
+	fn push_accessor(
+		&mut self,
+		bytes: &[u8],
+		target: Option<u32>,
+		component_type: u32,
+		count: usize,
+		ty: &str,
+		extra: &str,
+	) -> usize {
+		let view = self.push_view(bytes, target);
+		self.accessors.push(format!(
+			"{{\"bufferView\":{view},\"componentType\":{component_type},\"count\":{count},\"type\":\"{ty}\"{extra}}}"
+		));
+		self.accessors.len() - 1
+	}
+	fn push_vec3(&mut self, values: &[[f32; 3]]) -> usize {
+		let mut min = [f32::INFINITY; 3];
+		let mut max = [f32::NEG_INFINITY; 3];
+		for value in values {
+			for i in 0..3 {
+				min[i] = min[i].min(value[i]);
+				max[i] = max[i].max(value[i]);
+			}
+		}
+		// components which are NaN for every value
+		for i in 0..3 {
+			if min[i] > max[i] {
+				(min[i], max[i]) = (0.0, 0.0);
+			}
+		}
+		let bounds = if values.is_empty() {
+			String::new()
+		} else {
+			format!(
+				",\"min\":{},\"max\":{}",
+				json_floats(&min),
+				json_floats(&max)
+			)
+		};
+		let bytes: Vec<u8> = values
+			.as_flattened()
+			.iter()
+			.flat_map(|v| v.to_le_bytes())
+			.collect();
+		self.push_accessor(
+			&bytes,
+			Some(ARRAY_BUFFER),
+			FLOAT,
+			values.len(),
+			"VEC3",
+			&bounds,
+		)
+	}
+	fn build(glb: &Glb) -> (String, Vec<u8>) {
+		let mut builder = Self::default();
+		let mesh = &glb.mesh;
+		let vertex_count = mesh.vertex_count();
+
+		// vertex attributes shared by every part
+		let mut attributes = vec![format!(
+			"\"POSITION\":{}",
+			builder.push_vec3(&mesh.positions)
+		)];
+		if mesh.normals.len() == vertex_count && vertex_count != 0 {
+			let accessor = builder.push_vec3(&mesh.normals);
+			attributes.push(format!("\"NORMAL\":{accessor}"));
+		}
+		if mesh.uvs.len() == vertex_count && vertex_count != 0 {
+			let bytes: Vec<u8> = mesh
+				.uvs
+				.as_flattened()
+				.iter()
+				.flat_map(|v| v.to_le_bytes())
+				.collect();
+			let accessor =
+				builder.push_accessor(&bytes, Some(ARRAY_BUFFER), FLOAT, vertex_count, "VEC2", "");
+			attributes.push(format!("\"TEXCOORD_0\":{accessor}"));
+		}
+		// written as stored, not renormalized, so that they convert back exactly
+		if mesh.tangents.len() == vertex_count && vertex_count != 0 {
+			let bytes: Vec<u8> = mesh
+				.tangents
+				.as_flattened()
+				.iter()
+				.flat_map(|v| v.to_le_bytes())
+				.collect();
+			let accessor =
+				builder.push_accessor(&bytes, Some(ARRAY_BUFFER), FLOAT, vertex_count, "VEC4", "");
+			attributes.push(format!("\"TANGENT\":{accessor}"));
+		}
+		if mesh.colors.len() == vertex_count && vertex_count != 0 {
+			let accessor = builder.push_accessor(
+				mesh.colors.as_flattened(),
+				Some(ARRAY_BUFFER),
+				UNSIGNED_BYTE,
+				vertex_count,
+				"VEC4",
+				",\"normalized\":true",
+			);
+			attributes.push(format!("\"COLOR_0\":{accessor}"));
+		}
+		let skin = glb.skin.as_ref().filter(|skin| {
+			skin.vertex_joints.len() == vertex_count && skin.vertex_weights.len() == vertex_count
+		});
+		if let Some(skin) = skin {
+			let bytes: Vec<u8> = skin
+				.vertex_joints
+				.as_flattened()
+				.iter()
+				.flat_map(|v| v.to_le_bytes())
+				.collect();
+			let accessor = builder.push_accessor(
+				&bytes,
+				Some(ARRAY_BUFFER),
+				UNSIGNED_SHORT,
+				vertex_count,
+				"VEC4",
+				"",
+			);
+			attributes.push(format!("\"JOINTS_0\":{accessor}"));
+			let bytes: Vec<u8> = skin
+				.vertex_weights
+				.as_flattened()
+				.iter()
+				.flat_map(|v| v.to_le_bytes())
+				.collect();
+			let accessor =
+				builder.push_accessor(&bytes, Some(ARRAY_BUFFER), FLOAT, vertex_count, "VEC4", "");
+			attributes.push(format!("\"WEIGHTS_0\":{accessor}"));
+		}
+		let attributes = attributes.join(",");
+
+		let morph_targets: Vec<&GlbMorphTarget> = glb
+			.morph_targets
+			.iter()
+			.filter(|target| target.position_deltas.len() == vertex_count)
+			.collect();
+		let targets: Vec<String> = morph_targets
+			.iter()
+			.map(|target| {
+				format!(
+					"{{\"POSITION\":{}}}",
+					builder.push_vec3(&target.position_deltas)
+				)
+			})
+			.collect();
+		let morph = if targets.is_empty() {
+			String::new()
+		} else {
+			let weights = vec!["0"; targets.len()].join(",");
+			let names: Vec<String> = morph_targets
+				.iter()
+				.map(|target| json_string(&target.name))
+				.collect();
+			format!(
+				",\"weights\":[{weights}],\"extras\":{{\"targetNames\":[{}]}}",
+				names.join(",")
+			)
+		};
+		let targets = if targets.is_empty() {
+			String::new()
+		} else {
+			format!(",\"targets\":[{}]", targets.join(","))
+		};
+
+		// one mesh and node per part
+		let whole = 0..mesh.indices.len();
+		let parts = match (mesh.parts.is_empty(), whole.is_empty()) {
+			(false, _) => &mesh.parts[..],
+			(true, false) => core::slice::from_ref(&whole),
+			(true, true) => &[],
+		};
+		let mut meshes = Vec::new();
+		let mut nodes = Vec::new();
+		for (i, part) in parts.iter().enumerate() {
+			let indices = &mesh.indices[part.clone()];
+			let bytes: Vec<u8> = indices
+				.as_flattened()
+				.iter()
+				.flat_map(|v| v.to_le_bytes())
+				.collect();
+			let accessor = builder.push_accessor(
+				&bytes,
+				Some(ELEMENT_ARRAY_BUFFER),
+				UNSIGNED_INT,
+				indices.len() * 3,
+				"SCALAR",
+				"",
+			);
+			meshes.push(format!(
+				"{{\"name\":\"part{i}\",\"primitives\":[{{\"attributes\":{{{attributes}}},\"indices\":{accessor}{targets}}}]{morph}}}"
+			));
+			let skin = if skin.is_some() { ",\"skin\":0" } else { "" };
+			nodes.push(format!("{{\"name\":\"part{i}\",\"mesh\":{i}{skin}}}"));
+		}
+		let mut scene_nodes: Vec<usize> = (0..nodes.len()).collect();
+
+		// joints follow the part nodes
+		let mut skins = String::new();
+		if let Some(skin) = skin {
+			let first_joint = nodes.len();
+			for (index, joint) in skin.joints.iter().enumerate() {
+				let local = match joint.parent {
+					Some(parent) => &skin.joints[parent].cframe.inverse() * &joint.cframe,
+					None => joint.cframe.clone(),
+				};
+				let children: Vec<String> = skin
+					.joints
+					.iter()
+					.enumerate()
+					.filter(|(_, child)| child.parent == Some(index))
+					.map(|(child, _)| (first_joint + child).to_string())
+					.collect();
+				let children = if children.is_empty() {
+					String::new()
+				} else {
+					format!(",\"children\":[{}]", children.join(","))
+				};
+				nodes.push(format!(
+					"{{\"name\":{},\"matrix\":{}{children}}}",
+					json_string(&joint.name),
+					json_floats(&column_major(&local))
+				));
+				if joint.parent.is_none() {
+					scene_nodes.push(first_joint + index);
+				}
+			}
+			let bytes: Vec<u8> = skin
+				.joints
+				.iter()
+				.flat_map(|joint| column_major(&joint.cframe.inverse()))
+				.flat_map(f32::to_le_bytes)
+				.collect();
+			let accessor =
+				builder.push_accessor(&bytes, None, FLOAT, skin.joints.len(), "MAT4", "");
+			let joints: Vec<String> = (first_joint..first_joint + skin.joints.len())
+				.map(|joint| joint.to_string())
+				.collect();
+			skins = format!(
+				",\"skins\":[{{\"inverseBindMatrices\":{accessor},\"joints\":[{}]}}]",
+				joints.join(",")
+			);
+		}
+
+		let scene_nodes: Vec<String> = scene_nodes.iter().map(usize::to_string).collect();
+		let json = format!(
+			"{{\"asset\":{{\"version\":\"2.0\",\"generator\":\"rbx_mesh\"}},\"scene\":0,\"scenes\":[{{\"nodes\":[{}]}}],\"nodes\":[{}],\"meshes\":[{}]{skins},\"accessors\":[{}],\"bufferViews\":[{}],\"buffers\":[{{\"byteLength\":{}}}]}}",
+			scene_nodes.join(","),
+			nodes.join(","),
+			meshes.join(","),
+			builder.accessors.join(","),
+			builder.buffer_views.join(","),
+			builder.bin.len()
+		);
+		let mut bin = builder.bin;
+		while !bin.len().is_multiple_of(4) {
+			bin.push(0);
+		}
+		(json, bin)
+	}
+}
+
+impl From<TriangleMesh> for Glb {
+	fn from(mesh: TriangleMesh) -> Self {
+		Self {
+			mesh,
+			..Default::default()
+		}
+	}
+}
+impl From<&crate::mesh::Mesh4> for Glb {
+	fn from(mesh: &crate::mesh::Mesh4) -> Self {
+		Self::skinned(mesh.into(), &mesh.skeleton(), None)
+	}
+}
+impl From<&crate::mesh::Mesh5> for Glb {
+	fn from(mesh: &crate::mesh::Mesh5) -> Self {
+		Self::skinned(mesh.into(), &mesh.skeleton(), Some(&mesh.facs))
+	}
+}
+impl From<&crate::mesh::Mesh6> for Glb {
+	fn from(mesh: &crate::mesh::Mesh6) -> Self {
+		match mesh.skinning() {
+			Some(skinning) => Self::skinned(mesh.into(), &skinning.skeleton(), mesh.facs()),
+			None => TriangleMesh::from(mesh).into(),
+		}
+	}
+}
+impl From<&crate::mesh::Mesh7> for Glb {
+	fn from(mesh: &crate::mesh::Mesh7) -> Self {
+		match mesh.skinning() {
+			Some(skinning) => Self::skinned(mesh.into(), &skinning.skeleton(), mesh.facs()),
+			None => TriangleMesh::from(mesh).into(),
+		}
+	}
+}
+impl From<&crate::mesh::Mesh> for Glb {
+	fn from(mesh: &crate::mesh::Mesh) -> Self {
+		use crate::mesh::Mesh;
+		match mesh {
+			Mesh::V4(mesh) => mesh.into(),
+			Mesh::V5(mesh) => mesh.into(),
+			Mesh::V6(mesh) => mesh.into(),
+			Mesh::V7(mesh) => mesh.into(),
+			other => TriangleMesh::from(other).into(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn non_finite_json() {
+		let mesh = TriangleMesh {
+			positions: vec![
+				[f32::NAN, 0.0, 0.0],
+				[1.0, f32::INFINITY, 0.0],
+				[0.0, 0.0, f32::NAN],
+			],
+			indices: vec![[0, 1, 2]],
+			..Default::default()
+		};
+		let cframe = CFrame4 {
+			x: f32::NEG_INFINITY,
+			y: f32::NAN,
+			..CFrame4::IDENTITY
+		};
+		let glb = Glb {
+			skin: Some(GlbSkin {
+				joints: vec![GlbJoint {
+					name: "Root".to_owned(),
+					parent: None,
+					cframe,
+				}],
+				vertex_joints: vec![[0; 4]; 3],
+				vertex_weights: vec![[1.0, 0.0, 0.0, 0.0]; 3],
+			}),
+			mesh,
+			..Default::default()
+		};
+		let (text, _) = GltfBuilder::build(&glb);
+		let document = json::parse(text.as_bytes()).unwrap();
+		let floats = |value: &json::Value| -> Vec<f32> {
+			value
+				.as_array()
+				.unwrap()
+				.iter()
+				.map(|value| value.as_f64().unwrap() as f32)
+				.collect()
+		};
+		let position = document.get("accessors").unwrap().as_array().unwrap()[0].clone();
+		assert_eq!(floats(position.get("min").unwrap()), [0.0, 0.0, 0.0]);
+		assert_eq!(floats(position.get("max").unwrap()), [1.0, f32::MAX, 0.0]);
+		let joint = &document.get("nodes").unwrap().as_array().unwrap()[1];
+		assert_eq!(
+			floats(joint.get("matrix").unwrap())[12..],
+			[f32::MIN, 0.0, 0.0, 1.0]
+		);
+
+		let mut bytes = Vec::new();
+		glb.write(&mut bytes).unwrap();
+		let imported = Glb::read(&bytes).unwrap();
+		assert!(imported.mesh.positions[0][0].is_nan());
+	}
+}
@@ -0,0 +1,597 @@
+use std::borrow::Cow;
+
+use super::json::{self, JsonError, Value};
+use super::{Glb, GlbJoint, GlbSkin};
+use crate::TriangleMesh;
+use crate::mesh::CFrame4;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GltfError {
+	Json(JsonError),
+	InvalidGlb,
+	/// Only data URIs and the GLB binary chunk can be resolved.
+	UnsupportedUri(usize),
+	InvalidBuffer(usize),
+	InvalidBufferView(usize),
+	InvalidAccessor(usize),
+	/// Sparse accessors are not supported.
+	SparseAccessor(usize),
+	InvalidPrimitive(usize),
+	InvalidSkin,
+	InvalidNode(usize),
+}
+impl core::fmt::Display for GltfError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+impl core::error::Error for GltfError {}
+impl From<JsonError> for GltfError {
+	fn from(value: JsonError) -> Self {
+		Self::Json(value)
+	}
+}
+
+fn decode_base64(data: &str) -> Option<Vec<u8>> {
+	let sextet = |c: u8| match c {
+		b'A'..=b'Z' => Some(c - b'A'),
+		b'a'..=b'z' => Some(c - b'a' + 26),
+		b'0'..=b'9' => Some(c - b'0' + 52),
+		b'+' => Some(62),
+		b'/' => Some(63),
+		_ => None,
+	};
+	let data = data.trim_end_matches('=').as_bytes();
+	let mut bytes = Vec::with_capacity(data.len() / 4 * 3 + 2);
+	for chunk in data.chunks(4) {
+		let mut word = 0u32;
+		for &c in chunk {
+			word = word << 6 | sextet(c)? as u32;
+		}
+		// a lone trailing sextet does not make a whole byte
+		let byte_count = match chunk.len() {
+			4 => 3,
+			3 => 2,
+			2 => 1,
+			_ => return None,
+		};
+		word <<= 6 * (4 - chunk.len());
+		bytes.extend_from_slice(&word.to_be_bytes()[1..1 + byte_count]);
+	}
+	Some(bytes)
+}
+
+/// Split a GLB file into its JSON and BIN chunks.
+fn split_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), GltfError> {
+	let u32_at = |offset: usize| -> Result<usize, GltfError> {
+		let word = bytes.get(offset..offset + 4).ok_or(GltfError::InvalidGlb)?;
+		Ok(u32::from_le_bytes(word.try_into().unwrap()) as usize)
+	};
+	if u32_at(4)? != 2 {
+		return Err(GltfError::InvalidGlb);
+	}
+	let total_len = u32_at(8)?.min(bytes.len());
+	let mut offset = 12;
+	let mut json = None;
+	let mut bin = None;
+	while offset + 8 <= total_len {
+		let chunk_len = u32_at(offset)?;
+		let chunk_type = &bytes[offset + 4..offset + 8];
+		let chunk = bytes
+			.get(offset + 8..)
+			.and_then(|chunk| chunk.get(..chunk_len))
+			.ok_or(GltfError::InvalidGlb)?;
+		match chunk_type {
+			b"JSON" if json.is_none() => json = Some(chunk),
+			b"BIN\0" if bin.is_none() => bin = Some(chunk),
+			_ => (),
+		}
+		offset += 8 + chunk_len;
+	}
+	Ok((json.ok_or(GltfError::InvalidGlb)?, bin))
+}
+
+struct Document<'a> {
+	root: Value,
+	buffers: Vec<Cow<'a, [u8]>>,
+}
+
+fn array<'v>(value: &'v Value, key: &str) -> &'v [Value] {
+	value.get(key).and_then(Value::as_array).unwrap_or_default()
+}
+
+impl<'a> Document<'a> {
+	fn new(json: &[u8], bin: Option<&'a [u8]>) -> Result<Self, GltfError> {
+		let root = json::parse(json)?;
+		let buffers = array(&root, "buffers")
+			.iter()
+			.enumerate()
+			.map(|(index, buffer)| {
+				let data: Cow<[u8]> = match buffer.get("uri") {
+					None => Cow::Borrowed(
+						bin.filter(|_| index == 0)
+							.ok_or(GltfError::InvalidBuffer(index))?,
+					),
+					Some(uri) => {
+						let uri = uri.as_str().ok_or(GltfError::InvalidBuffer(index))?;
+						let (_, data) = uri
+							.strip_prefix("data:")
+							.and_then(|uri| uri.split_once(";base64,"))
+							.ok_or(GltfError::UnsupportedUri(index))?;
+						Cow::Owned(decode_base64(data).ok_or(GltfError::InvalidBuffer(index))?)
+					}
+				};
+				let byte_length = buffer.get("byteLength").and_then(Value::as_usize);
+				match byte_length {
+					Some(byte_length) if byte_length <= data.len() => Ok(match data {
+						Cow::Borrowed(data) => Cow::Borrowed(&data[..byte_length]),
+						Cow::Owned(mut data) => {
+							data.truncate(byte_length);
+							Cow::Owned(data)
+						}
+					}),
+					_ => Err(GltfError::InvalidBuffer(index)),
+				}
+			})
+			.collect::<Result<_, _>>()?;
+		Ok(Self { root, buffers })
+	}
+	/// Read the elements of an accessor, returning the values and the number of components.
+	/// Normalized integers are converted into the 0.0..=1.0 or -1.0..=1.0 range.
+	fn accessor(&self, index: usize) -> Result<(Vec<f64>, usize), GltfError> {
+		let error = GltfError::InvalidAccessor(index);
+		let accessor = array(&self.root, "accessors")
+			.get(index)
+			.ok_or(error.clone())?;
+		if accessor.get("sparse").is_some() {
+			return Err(GltfError::SparseAccessor(index));
+		}
+		let count = accessor
+			.get("count")
+			.and_then(Value::as_usize)
+			.ok_or(error.clone())?;
+		let components = match accessor.get("type").and_then(Value::as_str) {
+			Some("SCALAR") => 1,
+			Some("VEC2") => 2,
+			Some("VEC3") => 3,
+			Some("VEC4") => 4,
+			Some("MAT4") => 16,
+			_ => return Err(error),
+		};
+		let component_type = accessor.get("componentType").and_then(Value::as_usize);
+		let normalized = accessor
+			.get("normalized")
+			.and_then(Value::as_bool)
+			.unwrap_or(false);
+		let (size, read): (usize, fn(&[u8]) -> f64) = match component_type {
+			Some(5120) => (1, |b| b[0] as i8 as f64),
+			Some(5121) => (1, |b| b[0] as f64),
+			Some(5122) => (2, |b| i16::from_le_bytes([b[0], b[1]]) as f64),
+			Some(5123) => (2, |b| u16::from_le_bytes([b[0], b[1]]) as f64),
+			Some(5125) => (4, |b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64),
+			Some(5126) => (4, |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64),
+			_ => return Err(error),
+		};
+		let scale = match (normalized, component_type) {
+			(true, Some(5120)) => i8::MAX as f64,
+			(true, Some(5121)) => u8::MAX as f64,
+			(true, Some(5122)) => i16::MAX as f64,
+			(true, Some(5123)) => u16::MAX as f64,
+			_ => 1.0,
+		};
+
+		// accessors without a buffer view are only useful with sparse values
+		let view_index = accessor
+			.get("bufferView")
+			.and_then(Value::as_usize)
+			.ok_or(error.clone())?;
+		let view_error = GltfError::InvalidBufferView(view_index);
+		let view = array(&self.root, "bufferViews")
+			.get(view_index)
+			.ok_or(view_error.clone())?;
+		let buffer = view
+			.get("buffer")
+			.and_then(Value::as_usize)
+			.and_then(|buffer| self.buffers.get(buffer))
+			.ok_or(view_error.clone())?;
+		let view_offset = view.get("byteOffset").map_or(Some(0), Value::as_usize);
+		let view_length = view.get("byteLength").and_then(Value::as_usize);
+		let view_bytes = view_offset
+			.zip(view_length)
+			.and_then(|(offset, length)| buffer.get(offset..offset.checked_add(length)?))
+			.ok_or(view_error)?;
+		let element_size = size * components;
+		let stride = view
+			.get("byteStride")
+			.map_or(Some(element_size), Value::as_usize)
+			.ok_or(error.clone())?;
+		let offset = accessor
+			.get("byteOffset")
+			.map_or(Some(0), Value::as_usize)
+			.ok_or(error.clone())?;
+		// the last element does not need the whole stride
+		let required = match count {
+			0 => 0,
+			count => (count - 1)
+				.checked_mul(stride)
+				.and_then(|len| len.checked_add(offset + element_size))
+				.ok_or(error.clone())?,
+		};
+		if stride < element_size || view_bytes.len() < required {
+			return Err(error);
+		}
+		let mut values = Vec::with_capacity(count * components);
+		for element in 0..count {
+			let start = offset + element * stride;
+			for component in 0..components {
+				let at = start + component * size;
+				let value = read(&view_bytes[at..at + size]);
+				values.push(if normalized {
+					(value / scale).max(-1.0)
+				} else {
+					value
+				});
+			}
+		}
+		Ok((values, components))
+	}
+	fn accessor_with(&self, index: usize, components: &[usize]) -> Result<Vec<f64>, GltfError> {
+		let (values, count) = self.accessor(index)?;
+		if !components.contains(&count) {
+			return Err(GltfError::InvalidAccessor(index));
+		}
+		Ok(values)
+	}
+}
+
+fn cframe_from_column_major(m: &[f64]) -> CFrame4 {
+	let m: Vec<f32> = m.iter().map(|&value| value as f32).collect();
+	CFrame4 {
+		r00: m[0],
+		r10: m[1],
+		r20: m[2],
+		r01: m[4],
+		r11: m[5],
+		r21: m[6],
+		r02: m[8],
+		r12: m[9],
+		r22: m[10],
+		x: m[12],
+		y: m[13],
+		z: m[14],
+	}
+}
+
+fn chunks<const N: usize>(values: &[f64]) -> impl Iterator<Item = [f32; N]> + '_ {
+	values
+		.chunks_exact(N)
+		.map(|chunk| core::array::from_fn(|i| chunk[i] as f32))
+}
+
+/// Vertex data of one primitive, before the attributes of all primitives are unified.
+#[derive(Default)]
+struct Primitive {
+	positions: Vec<[f32; 3]>,
+	normals: Option<Vec<[f32; 3]>>,
+	uvs: Option<Vec<[f32; 2]>>,
+	colors: Option<Vec<[u8; 4]>>,
+	tangents: Option<Vec<[f32; 4]>>,
+	joints: Option<Vec<[u16; 4]>>,
+	weights: Option<Vec<[f32; 4]>>,
+	indices: Vec<[u32; 3]>,
+}
+
+impl Document<'_> {
+	fn primitive(&self, index: usize, primitive: &Value) -> Result<Option<Primitive>, GltfError> {
+		let error = GltfError::InvalidPrimitive(index);
+		// only triangle lists are imported
+		if primitive.get("mode").map_or(Some(4), Value::as_usize) != Some(4) {
+			return Ok(None);
+		}
+		let attributes = primitive.get("attributes").ok_or(error.clone())?;
+		let attribute = |name: &str, components: &[usize]| {
+			attributes
+				.get(name)
+				.map(|accessor| {
+					let accessor = accessor.as_usize().ok_or(error.clone())?;
+					self.accessor_with(accessor, components)
+				})
+				.transpose()
+		};
+		let positions: Vec<[f32; 3]> =
+			chunks(&attribute("POSITION", &[3])?.ok_or(error.clone())?).collect();
+		let vertex_count = positions.len();
+		let check = |len: usize| {
+			if len == vertex_count {
+				Ok(())
+			} else {
+				Err(error.clone())
+			}
+		};
+
+		let normals: Option<Vec<[f32; 3]>> =
+			attribute("NORMAL", &[3])?.map(|values| chunks(&values).collect());
+		let uvs: Option<Vec<[f32; 2]>> =
+			attribute("TEXCOORD_0", &[2])?.map(|values| chunks(&values).collect());
+		let tangents: Option<Vec<[f32; 4]>> =
+			attribute("TANGENT", &[4])?.map(|values| chunks(&values).collect());
+		let colors: Option<Vec<[u8; 4]>> = match attributes.get("COLOR_0") {
+			Some(accessor) => {
+				let accessor = accessor.as_usize().ok_or(error.clone())?;
+				let (values, components) = self.accessor(accessor)?;
+				if !matches!(components, 3 | 4) {
+					return Err(GltfError::InvalidAccessor(accessor));
+				}
+				Some(
+					values
+						.chunks_exact(components)
+						.map(|color| {
+							core::array::from_fn(|i| {
+								let value = color.get(i).copied().unwrap_or(1.0);
+								(value.clamp(0.0, 1.0) * u8::MAX as f64).round() as u8
+							})
+						})
+						.collect(),
+				)
+			}
+			None => None,
+		};
+		let joints: Option<Vec<[u16; 4]>> = attribute("JOINTS_0", &[4])?.map(|values| {
+			values
+				.chunks_exact(4)
+				.map(|joints| core::array::from_fn(|i| joints[i] as u16))
+				.collect()
+		});
+		let weights: Option<Vec<[f32; 4]>> =
+			attribute("WEIGHTS_0", &[4])?.map(|values| chunks(&values).collect());
+		for len in [
+			normals.as_ref().map(Vec::len),
+			uvs.as_ref().map(Vec::len),
+			tangents.as_ref().map(Vec::len),
+			colors.as_ref().map(Vec::len),
+			joints.as_ref().map(Vec::len),
+			weights.as_ref().map(Vec::len),
+		]
+		.into_iter()
+		.flatten()
+		{
+			check(len)?;
+		}
+
+		let indices: Vec<u32> = match primitive.get("indices") {
+			Some(accessor) => {
+				let accessor = accessor.as_usize().ok_or(error.clone())?;
+				self.accessor_with(accessor, &[1])?
+					.into_iter()
+					.map(|index| index as u32)
+					.collect()
+			}
+			None => (0..vertex_count as u32).collect(),
+		};
+		if indices.iter().any(|&index| vertex_count <= index as usize) {
+			return Err(error);
+		}
+		let (indices, _) = indices.as_chunks();
+		Ok(Some(Primitive {
+			positions,
+			normals,
+			uvs,
+			colors,
+			tangents,
+			joints,
+			weights,
+			indices: indices.to_vec(),
+		}))
+	}
+	fn skin(&self) -> Result<Option<Vec<GlbJoint>>, GltfError> {
+		let Some(skin) = array(&self.root, "skins").first() else {
+			return Ok(None);
+		};
+		let nodes = array(&self.root, "nodes");
+		let joint_nodes: Vec<usize> = array(skin, "joints")
+			.iter()
+			.map(|joint| joint.as_usize().filter(|&joint| joint < nodes.len()))
+			.collect::<Option<_>>()
+			.ok_or(GltfError::InvalidSkin)?;
+		let mut node_parents = vec![None; nodes.len()];
+		for (parent, node) in nodes.iter().enumerate() {
+			for child in array(node, "children") {
+				let child = child
+					.as_usize()
+					.filter(|&child| child < nodes.len())
+					.ok_or(GltfError::InvalidNode(parent))?;
+				node_parents[child] = Some(parent);
+			}
+		}
+		let inverse_binds = match skin.get("inverseBindMatrices") {
+			Some(accessor) => {
+				let accessor = accessor.as_usize().ok_or(GltfError::InvalidSkin)?;
+				let values = self.accessor_with(accessor, &[16])?;
+				if values.len() != joint_nodes.len() * 16 {
+					return Err(GltfError::InvalidSkin);
+				}
+				values
+					.chunks_exact(16)
+					.map(cframe_from_column_major)
+					.collect()
+			}
+			None => vec![CFrame4::IDENTITY; joint_nodes.len()],
+		};
+		let joints = joint_nodes
+			.iter()
+			.zip(inverse_binds)
+			.enumerate()
+			.map(|(index, (&node, inverse_bind))| {
+				// the closest ancestor which is also a joint
+				let mut parent = node_parents[node];
+				let mut steps = 0;
+				while let Some(ancestor) = parent {
+					if joint_nodes.contains(&ancestor) || nodes.len() < steps {
+						break;
+					}
+					parent = node_parents[ancestor];
+					steps += 1;
+				}
+				let name = nodes[node].get("name").and_then(Value::as_str);
+				GlbJoint {
+					name: name.map_or_else(|| format!("Bone{index}"), str::to_owned),
+					parent: parent
+						.and_then(|parent| joint_nodes.iter().position(|&joint| joint == parent)),
+					cframe: inverse_bind.inverse(),
+				}
+			})
+			.collect();
+		Ok(Some(joints))
+	}
+}
+
+impl Glb {
+	/// Read a GLB file, or a glTF file whose buffers are embedded as data URIs.
+	///
+	/// Each glTF mesh becomes a part of the TriangleMesh, and its triangle list
+	/// primitives are merged.  Node transforms and morph targets are not read.
+	pub fn read(bytes: &[u8]) -> Result<Self, GltfError> {
+		let (json, bin) = if bytes.starts_with(b"glTF") {
+			split_glb(bytes)?
+		} else {
+			(bytes, None)
+		};
+		let document = Document::new(json, bin)?;
+
+		let mut primitives = Vec::new();
+		for mesh in array(&document.root, "meshes") {
+			let mut part = Vec::new();
+			for primitive in array(mesh, "primitives") {
+				if let Some(primitive) =
+					document.primitive(primitives.len() + part.len(), primitive)?
+				{
+					part.push(primitive);
+				}
+			}
+			primitives.push(part);
+		}
+		let joints = document.skin()?;
+		let all = || primitives.iter().flatten();
+
+		let mut mesh = TriangleMesh::default();
+		let mut vertex_joints = Vec::new();
+		let mut vertex_weights = Vec::new();
+		let has_normals = all().any(|primitive| primitive.normals.is_some());
+		let has_uvs = all().any(|primitive| primitive.uvs.is_some());
+		let has_colors = all().any(|primitive| primitive.colors.is_some());
+		let has_tangents = all().any(|primitive| primitive.tangents.is_some());
+		for part in &primitives {
+			let start = mesh.indices.len();
+			for primitive in part {
+				let vertex_offset = mesh.positions.len() as u32;
+				let vertex_count = primitive.positions.len();
+				mesh.positions.extend_from_slice(&primitive.positions);
+				fn extend<T: Clone>(
+					to: &mut Vec<T>,
+					from: &Option<Vec<T>>,
+					enabled: bool,
+					count: usize,
+					default: T,
+				) {
+					match from {
+						Some(from) => to.extend_from_slice(from),
+						None if enabled => to.extend(core::iter::repeat_n(default, count)),
+						None => (),
+					}
+				}
+				extend(
+					&mut mesh.normals,
+					&primitive.normals,
+					has_normals,
+					vertex_count,
+					[0.0; 3],
+				);
+				extend(
+					&mut mesh.uvs,
+					&primitive.uvs,
+					has_uvs,
+					vertex_count,
+					[0.0; 2],
+				);
+				extend(
+					&mut mesh.colors,
+					&primitive.colors,
+					has_colors,
+					vertex_count,
+					[u8::MAX; 4],
+				);
+				extend(
+					&mut mesh.tangents,
+					&primitive.tangents,
+					has_tangents,
+					vertex_count,
+					[0.0, 0.0, -1.0, 1.0],
+				);
+				extend(
+					&mut vertex_joints,
+					&primitive.joints,
+					true,
+					vertex_count,
+					[0; 4],
+				);
+				extend(
+					&mut vertex_weights,
+					&primitive
+						.weights
+						.clone()
+						.filter(|_| primitive.joints.is_some()),
+					true,
+					vertex_count,
+					[1.0, 0.0, 0.0, 0.0],
+				);
+				mesh.indices.extend(
+					primitive
+						.indices
+						.iter()
+						.map(|face| face.map(|index| index + vertex_offset)),
+				);
+			}
+			mesh.parts.push(start..mesh.indices.len());
+		}
+
+		let skin = match joints {
+			Some(joints) if all().any(|primitive| primitive.joints.is_some()) => {
+				if vertex_joints
+					.as_flattened()
+					.iter()
+					.any(|&joint| joints.len() <= joint as usize)
+				{
+					return Err(GltfError::InvalidSkin);
+				}
+				// weights are renormalized, as exporters are not always exact
+				for weights in &mut vertex_weights {
+					let total: f32 = weights.iter().map(|weight| weight.max(0.0)).sum();
+					*weights = if 0.0 < total {
+						weights.map(|weight| weight.max(0.0) / total)
+					} else {
+						[1.0, 0.0, 0.0, 0.0]
+					};
+				}
+				Some(GlbSkin {
+					joints,
+					vertex_joints,
+					vertex_weights,
+				})
+			}
+			_ => None,
+		};
+		Ok(Self {
+			mesh,
+			skin,
+			morph_targets: Vec::new(),
+		})
+	}
+}
+
+#[test]
+fn base64() {
+	assert_eq!(decode_base64("TWFu").unwrap(), b"Man");
+	assert_eq!(decode_base64("TWE=").unwrap(), b"Ma");
+	assert_eq!(decode_base64("TQ==").unwrap(), b"M");
+	assert_eq!(decode_base64("T"), None);
+}
@@ -1,7 +1,37 @@
+/// Gates each item on at least one format being enabled.
+macro_rules! any_format {
+	($($item:item)*) => {$(
+		#[cfg(any(
+			feature = "mesh",
+			feature = "union-graphics",
+			feature = "union-physics"
+		))]
+		$item
+	)*};
+}
+
+any_format! {
+	pub mod error;
+	pub use error::Error;
+	pub mod limits;
+	pub use limits::ReadLimits;
+	mod any;
+	pub use any::{AnyMesh, read_any, read_any_with_limits};
+}
+
+pub mod detect;
+pub use detect::{Format, detect_format};
+
 #[cfg(feature = "mesh")]
 pub mod mesh;
 #[cfg(feature = "mesh")]
+pub use mesh::read_header as read_mesh_header;
+#[cfg(feature = "mesh")]
 pub use mesh::read_versioned as read_mesh_versioned;
+#[cfg(feature = "mesh")]
+pub use mesh::read_versioned_with_limits as read_mesh_versioned_with_limits;
+#[cfg(feature = "mesh")]
+pub use mesh::write_versioned as write_mesh_versioned;
 
 // shared code between union formats
 #[cfg(any(feature = "union-graphics", feature = "union-physics"))]
@@ -11,11 +41,33 @@ mod csgk;
 pub mod union_graphics;
 #[cfg(feature = "union-graphics")]
 pub use union_graphics::read_versioned as read_union_graphics_versioned;
+#[cfg(feature = "union-graphics")]
+pub use union_graphics::read_versioned_with_limits as read_union_graphics_versioned_with_limits;
+#[cfg(feature = "union-graphics")]
+pub use union_graphics::write_versioned as write_union_graphics_versioned;
 
 #[cfg(feature = "union-physics")]
 pub mod union_physics;
 #[cfg(feature = "union-physics")]
 pub use union_physics::read_versioned as read_union_physics_versioned;
+#[cfg(feature = "union-physics")]
+pub use union_physics::read_versioned_with_limits as read_union_physics_versioned_with_limits;
+#[cfg(feature = "union-physics")]
+pub use union_physics::write_versioned as write_union_physics_versioned;
+
+any_format! {
+	pub mod triangle_mesh;
+	pub use triangle_mesh::{ToTriangleMesh, TriangleMesh};
+	pub mod obj;
+	pub mod validate;
+}
+#[cfg(feature = "mesh")]
+pub mod gltf;
+
+// entry points for the targets in fuzz/, enabled by cargo fuzz
+#[cfg(any(fuzzing, test))]
+#[doc(hidden)]
+pub mod fuzz;
 
 #[cfg(test)]
 mod test;
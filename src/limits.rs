@@ -0,0 +1,118 @@
+/// Upper bounds for reading untrusted files.
+/// Counts read from a file are checked against these before anything is allocated for them.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ReadLimits {
+	pub max_vertices: u64,
+	/// Triangles, not indices
+	pub max_faces: u64,
+	pub max_bones: u64,
+	/// The size of zstd compressed data after decompression
+	pub max_decompressed_bytes: u64,
+	/// The total size of the arrays which the counts of one header or chunk ask for.
+	/// The meshes of CSGPHS3, CSGPHS5 and CSGPHS7 share it
+	pub max_alloc_bytes: u64,
+}
+impl ReadLimits {
+	/// Far beyond any mesh Roblox accepts, but small enough that a hostile count cannot exhaust memory.
+	pub const DEFAULT: Self = Self {
+		max_vertices: 1 << 24,
+		max_faces: 1 << 24,
+		max_bones: u16::MAX as u64,
+		max_decompressed_bytes: 1 << 28,
+		max_alloc_bytes: 1 << 30,
+	};
+	pub const UNLIMITED: Self = Self {
+		max_vertices: u64::MAX,
+		max_faces: u64::MAX,
+		max_bones: u64::MAX,
+		max_decompressed_bytes: u64::MAX,
+		max_alloc_bytes: u64::MAX,
+	};
+	/// Start checking the counts of a header or chunk.
+	pub(crate) const fn charge(self) -> Charge {
+		Charge(Ok(self))
+	}
+}
+impl Default for ReadLimits {
+	fn default() -> Self {
+		Self::DEFAULT
+	}
+}
+
+/// A count in the file exceeded a limit.  Each variant holds the count.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum LimitError {
+	Vertices(u64),
+	Faces(u64),
+	Bones(u64),
+	DecompressedBytes(u64),
+	AllocBytes(u64),
+}
+impl core::fmt::Display for LimitError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+impl core::error::Error for LimitError {}
+
+/// Checks counts against the limits and deducts the arrays they ask for from max_alloc_bytes.
+/// The first failure is kept and the remaining checks are skipped.
+pub(crate) struct Charge(Result<ReadLimits, LimitError>);
+impl Charge {
+	fn check(
+		self,
+		count: u64,
+		max: impl FnOnce(&ReadLimits) -> u64,
+		err: impl FnOnce(u64) -> LimitError,
+	) -> Self {
+		Self(self.0.and_then(|limits| {
+			if max(&limits) < count {
+				return Err(err(count));
+			}
+			Ok(limits)
+		}))
+	}
+	pub(crate) fn vertices(self, count: impl Into<u64>) -> Self {
+		self.check(count.into(), |l| l.max_vertices, LimitError::Vertices)
+	}
+	pub(crate) fn faces(self, count: impl Into<u64>) -> Self {
+		self.check(count.into(), |l| l.max_faces, LimitError::Faces)
+	}
+	#[cfg(feature = "mesh")]
+	pub(crate) fn bones(self, count: impl Into<u64>) -> Self {
+		self.check(count.into(), |l| l.max_bones, LimitError::Bones)
+	}
+	pub(crate) fn bytes(self, bytes: impl Into<u64>) -> Self {
+		let bytes = bytes.into();
+		Self(self.0.and_then(|mut limits| {
+			limits.max_alloc_bytes = limits
+				.max_alloc_bytes
+				.checked_sub(bytes)
+				.ok_or(LimitError::AllocBytes(bytes))?;
+			Ok(limits)
+		}))
+	}
+	/// Deduct an array of count elements of T.
+	pub(crate) fn alloc<T>(self, count: impl Into<u64>) -> Self {
+		self.bytes(count.into().saturating_mul(size_of::<T>() as u64))
+	}
+	/// The limits with the allocations deducted.
+	pub(crate) fn finish(self) -> Result<ReadLimits, LimitError> {
+		self.0
+	}
+}
+
+#[test]
+fn charge() {
+	let limits = ReadLimits {
+		max_vertices: 4,
+		max_alloc_bytes: 16,
+		..ReadLimits::UNLIMITED
+	};
+	let charged = limits.charge().vertices(4u32).alloc::<u32>(3u32).finish();
+	assert_eq!(charged.map(|l| l.max_alloc_bytes), Ok(4));
+	let charged = limits.charge().vertices(5u32).alloc::<u32>(5u32).finish();
+	assert_eq!(charged, Err(LimitError::Vertices(5)));
+	let charged = limits.charge().alloc::<u32>(5u32).finish();
+	assert_eq!(charged, Err(LimitError::AllocBytes(20)));
+}
@@ -0,0 +1,265 @@
+use binrw::BinReaderExt;
+
+use super::header::{Counts4, Header4, Header5};
+use super::{
+	Bone4, BoneId4, CFrame4, Envelope4, Error, Face2, Facs5, FacsFormat5, Lod3, LodType4,
+	Revision4, Revision5, Subset4, Vertex2, VertexId2,
+};
+
+/// A fixed size little endian record which can be decoded straight from its bytes.
+pub trait Record: Sized + 'static {
+	const SIZE: usize;
+	/// bytes is exactly SIZE long
+	fn decode(bytes: &[u8]) -> Self;
+}
+
+fn array<const N: usize>(bytes: &[u8], offset: usize) -> [u8; N] {
+	bytes[offset..offset + N].try_into().unwrap()
+}
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+	u16::from_le_bytes(array(bytes, offset))
+}
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+	u32::from_le_bytes(array(bytes, offset))
+}
+fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+	f32::from_le_bytes(array(bytes, offset))
+}
+fn f32s_at<const N: usize>(bytes: &[u8], offset: usize) -> [f32; N] {
+	core::array::from_fn(|i| f32_at(bytes, offset + 4 * i))
+}
+
+impl Record for Vertex2 {
+	const SIZE: usize = 40;
+	fn decode(bytes: &[u8]) -> Self {
+		Self {
+			pos: f32s_at(bytes, 0),
+			norm: f32s_at(bytes, 12),
+			tex: f32s_at(bytes, 24),
+			tangent: array(bytes, 32).map(|b: u8| b as i8),
+			color: array(bytes, 36),
+		}
+	}
+}
+impl Record for Envelope4 {
+	const SIZE: usize = 8;
+	fn decode(bytes: &[u8]) -> Self {
+		Self {
+			bones: array(bytes, 0),
+			weights: array(bytes, 4),
+		}
+	}
+}
+impl Record for Face2 {
+	const SIZE: usize = 12;
+	fn decode(bytes: &[u8]) -> Self {
+		Self(core::array::from_fn(|i| VertexId2(u32_at(bytes, 4 * i))))
+	}
+}
+impl Record for Lod3 {
+	const SIZE: usize = 4;
+	fn decode(bytes: &[u8]) -> Self {
+		Self(u32_at(bytes, 0))
+	}
+}
+impl Record for Bone4 {
+	const SIZE: usize = 60;
+	fn decode(bytes: &[u8]) -> Self {
+		let [r00, r01, r02, r10, r11, r12, r20, r21, r22, x, y, z] = f32s_at(bytes, 12);
+		Self {
+			bone_name_pos: u32_at(bytes, 0),
+			parent: BoneId4::new(Some(u16_at(bytes, 4))),
+			lod_parent: BoneId4::new(Some(u16_at(bytes, 6))),
+			cull_distance: f32_at(bytes, 8),
+			cframe: CFrame4 {
+				r00,
+				r01,
+				r02,
+				r10,
+				r11,
+				r12,
+				r20,
+				r21,
+				r22,
+				x,
+				y,
+				z,
+			},
+		}
+	}
+}
+impl Record for Subset4 {
+	const SIZE: usize = 72;
+	fn decode(bytes: &[u8]) -> Self {
+		Self {
+			faces_offset: u32_at(bytes, 0),
+			faces_len: u32_at(bytes, 4),
+			vertices_offset: u32_at(bytes, 8),
+			vertices_len: u32_at(bytes, 12),
+			bone_count: u32_at(bytes, 16),
+			bones: core::array::from_fn(|i| BoneId4::new(Some(u16_at(bytes, 20 + 2 * i)))),
+		}
+	}
+}
+
+/// An array of records borrowed from the file.  Elements are decoded on access.
+#[derive(Debug)]
+pub struct Records<'a, T> {
+	bytes: &'a [u8],
+	_record: core::marker::PhantomData<fn() -> T>,
+}
+impl<T> Clone for Records<'_, T> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+impl<T> Copy for Records<'_, T> {}
+impl<'a, T: Record> Records<'a, T> {
+	const fn new(bytes: &'a [u8]) -> Self {
+		Self {
+			bytes,
+			_record: core::marker::PhantomData,
+		}
+	}
+	pub const fn len(&self) -> usize {
+		self.bytes.len() / T::SIZE
+	}
+	pub const fn is_empty(&self) -> bool {
+		self.bytes.is_empty()
+	}
+	/// The raw little endian bytes of the array
+	pub const fn as_bytes(&self) -> &'a [u8] {
+		self.bytes
+	}
+	pub fn get(&self, index: usize) -> Option<T> {
+		let start = index.checked_mul(T::SIZE)?;
+		let bytes = self.bytes.get(start..start + T::SIZE)?;
+		Some(T::decode(bytes))
+	}
+	pub fn iter(&self) -> impl ExactSizeIterator<Item = T> + DoubleEndedIterator + 'a {
+		self.bytes.chunks_exact(T::SIZE).map(T::decode)
+	}
+}
+impl<'a> Records<'a, Vertex2> {
+	/// Decode only the positions, for bounds and statistics.
+	pub fn positions(&self) -> impl ExactSizeIterator<Item = [f32; 3]> + DoubleEndedIterator + 'a {
+		self.bytes
+			.chunks_exact(Vertex2::SIZE)
+			.map(|bytes| f32s_at(bytes, 0))
+	}
+}
+
+/// Splits the file into borrowed arrays.
+struct Slicer<'a> {
+	data: &'a [u8],
+	pos: usize,
+}
+impl<'a> Slicer<'a> {
+	fn take(&mut self, len: u64) -> Result<&'a [u8], Error> {
+		let end = usize::try_from(len)
+			.ok()
+			.and_then(|len| self.pos.checked_add(len))
+			.filter(|&end| end <= self.data.len())
+			.ok_or_else(|| Error::Io(std::io::ErrorKind::UnexpectedEof.into()))?;
+		let bytes = &self.data[self.pos..end];
+		self.pos = end;
+		Ok(bytes)
+	}
+	fn records<T: Record>(&mut self, count: u32) -> Result<Records<'a, T>, Error> {
+		let len = count as u64 * T::SIZE as u64;
+		Ok(Records::new(self.take(len)?))
+	}
+}
+
+/// The arrays which Mesh4 and Mesh5 have in common.
+#[derive(Debug, Clone, Copy)]
+pub struct Arrays4<'a> {
+	pub vertices: Records<'a, Vertex2>,
+	/// Empty when there are no bones
+	pub envelopes: Records<'a, Envelope4>,
+	pub faces: Records<'a, Face2>,
+	pub lods: Records<'a, Lod3>,
+	pub bones: Records<'a, Bone4>,
+	pub bone_names: &'a [u8],
+	pub subsets: Records<'a, Subset4>,
+}
+impl<'a> Arrays4<'a> {
+	fn read(slicer: &mut Slicer<'a>, counts: &Counts4) -> Result<Self, Error> {
+		let &Counts4 {
+			vertex_count,
+			face_count,
+			lod_count,
+			bone_count,
+			bone_names_len,
+			subset_count,
+		} = counts;
+		Ok(Self {
+			vertices: slicer.records(vertex_count)?,
+			envelopes: slicer.records(if bone_count == 0 { 0 } else { vertex_count })?,
+			faces: slicer.records(face_count)?,
+			lods: slicer.records(lod_count.into())?,
+			bones: slicer.records(bone_count.into())?,
+			bone_names: slicer.take(bone_names_len.into())?,
+			subsets: slicer.records(subset_count.into())?,
+		})
+	}
+}
+
+/// Mesh4 borrowed from the file bytes.  Nothing is allocated, the arrays are decoded on access.
+#[derive(Debug, Clone)]
+pub struct Mesh4Ref<'a> {
+	pub revision: Revision4,
+	pub lod_type: LodType4,
+	pub lod_hq_count: u8,
+	pub arrays: Arrays4<'a>,
+}
+impl<'a> Mesh4Ref<'a> {
+	pub fn parse(data: &'a [u8]) -> Result<Self, Error> {
+		let mut cursor = std::io::Cursor::new(data);
+		let header: Header4 = cursor.read_le()?;
+		let mut slicer = Slicer {
+			data,
+			pos: cursor.position() as usize,
+		};
+		Ok(Self {
+			revision: header.revision,
+			lod_type: header.lod_type,
+			lod_hq_count: header.lod_hq_count,
+			arrays: Arrays4::read(&mut slicer, &header.counts)?,
+		})
+	}
+}
+
+/// Mesh5 borrowed from the file bytes.  Nothing is allocated, the arrays are decoded on access.
+#[derive(Debug, Clone)]
+pub struct Mesh5Ref<'a> {
+	pub revision: Revision5,
+	pub lod_type: LodType4,
+	pub lod_hq_count: u8,
+	pub facs_format: FacsFormat5,
+	pub arrays: Arrays4<'a>,
+	/// The encoded Facs5, see Mesh5Ref::facs
+	pub facs: &'a [u8],
+}
+impl<'a> Mesh5Ref<'a> {
+	pub fn parse(data: &'a [u8]) -> Result<Self, Error> {
+		let mut cursor = std::io::Cursor::new(data);
+		let header: Header5 = cursor.read_le()?;
+		let mut slicer = Slicer {
+			data,
+			pos: cursor.position() as usize,
+		};
+		Ok(Self {
+			revision: header.revision,
+			lod_type: header.lod_type,
+			lod_hq_count: header.lod_hq_count,
+			facs_format: header.facs_format,
+			arrays: Arrays4::read(&mut slicer, &header.counts)?,
+			facs: slicer.take(header.sizeof_facs.into())?,
+		})
+	}
+	/// Decode the facs, which allocates.
+	pub fn facs(&self) -> Result<Facs5, Error> {
+		std::io::Cursor::new(self.facs).read_le()
+	}
+}
@@ -0,0 +1,208 @@
+use super::skeleton::{Pose, Skeleton};
+use super::v4::CFrame4;
+use super::v5::{ControlId5, Facs5, QuantizedMatrix5};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FacsError {
+	/// A matrix is not face bones by poses in size
+	MatrixSize,
+	ControlOutOfRange(u16),
+}
+impl core::fmt::Display for FacsError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+impl core::error::Error for FacsError {}
+
+/// The offset of a face bone relative to its bind pose.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FacsOffset {
+	/// In studs
+	pub position: [f32; 3],
+	/// XYZ euler angles in degrees
+	pub rotation: [f32; 3],
+}
+impl FacsOffset {
+	pub fn to_cframe(&self) -> CFrame4 {
+		let [rx, ry, rz] = self.rotation.map(f32::to_radians);
+		let mut cframe = CFrame4::from_euler_angles_xyz(rx, ry, rz);
+		[cframe.x, cframe.y, cframe.z] = self.position;
+		cframe
+	}
+}
+
+/// A control or a corrective, with an offset for every face bone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FacsPose {
+	/// Corrective names are the names of their controls joined with '_'
+	pub name: String,
+	/// Indices into FacsRig.controls, one for a control and two or three for a corrective
+	pub controls: Vec<usize>,
+	/// One entry per face bone
+	pub offsets: Vec<FacsOffset>,
+}
+impl FacsPose {
+	/// A corrective is applied with the product of the weights of its controls.
+	pub fn weight(&self, control_weights: &[f32]) -> f32 {
+		self.controls
+			.iter()
+			.map(|&control| control_weights.get(control).copied().unwrap_or(0.0))
+			.product()
+	}
+}
+
+/// Decoded FACS data.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FacsRig {
+	pub face_bones: Vec<String>,
+	pub controls: Vec<String>,
+	/// The controls, followed by the two pose correctives and then the three pose correctives
+	pub poses: Vec<FacsPose>,
+}
+
+fn split_names(names: &[u8]) -> Vec<String> {
+	let names = names.strip_suffix(&[0]).unwrap_or(names);
+	if names.is_empty() {
+		return Vec::new();
+	}
+	names
+		.split(|&b| b == 0)
+		.map(|name| String::from_utf8_lossy(name).into_owned())
+		.collect()
+}
+
+/// Values are stored as matrix[face_bone * poses + pose].
+fn dequantize(
+	matrix: &QuantizedMatrix5,
+	face_bones: usize,
+	poses: usize,
+) -> Result<Vec<f32>, FacsError> {
+	let (x, y, values) = match matrix {
+		QuantizedMatrix5::Raw { x, y, matrix } => (*x, *y, matrix.clone()),
+		QuantizedMatrix5::Quantized {
+			x,
+			y,
+			lerp0,
+			lerp1,
+			matrix,
+		} => (
+			*x,
+			*y,
+			matrix
+				.iter()
+				.map(|&value| lerp0 + (lerp1 - lerp0) * (value as f32 / u16::MAX as f32))
+				.collect(),
+		),
+	};
+	if (x as usize, y as usize) != (face_bones, poses) || values.len() != face_bones * poses {
+		return Err(FacsError::MatrixSize);
+	}
+	Ok(values)
+}
+
+impl Facs5 {
+	/// Split the names and dequantize the transforms into one pose per control and corrective.
+	pub fn decode(&self) -> Result<FacsRig, FacsError> {
+		let face_bones = split_names(&self.face_bone_names);
+		let controls = split_names(&self.face_control_names);
+
+		let control = |&ControlId5(id): &ControlId5| {
+			if (id as usize) < controls.len() {
+				Ok(id as usize)
+			} else {
+				Err(FacsError::ControlOutOfRange(id))
+			}
+		};
+		let mut pose_controls: Vec<Vec<usize>> =
+			(0..controls.len()).map(|control| vec![control]).collect();
+		for corrective in &self.two_pose_correctives {
+			pose_controls.push(corrective.0.iter().map(control).collect::<Result<_, _>>()?);
+		}
+		for corrective in &self.three_pose_correctives {
+			pose_controls.push(corrective.0.iter().map(control).collect::<Result<_, _>>()?);
+		}
+
+		let pose_count = pose_controls.len();
+		let transforms = &self.quantized_transforms;
+		let mut matrices = Vec::with_capacity(6);
+		for matrix in [
+			&transforms.px,
+			&transforms.py,
+			&transforms.pz,
+			&transforms.rx,
+			&transforms.ry,
+			&transforms.rz,
+		] {
+			matrices.push(dequantize(matrix, face_bones.len(), pose_count)?);
+		}
+		let value = |axis: usize, face_bone: usize, pose: usize| -> f32 {
+			matrices[axis][face_bone * pose_count + pose]
+		};
+
+		let poses = pose_controls
+			.into_iter()
+			.enumerate()
+			.map(|(pose, pose_controls)| FacsPose {
+				name: pose_controls
+					.iter()
+					.map(|&control| controls[control].as_str())
+					.collect::<Vec<_>>()
+					.join("_"),
+				controls: pose_controls,
+				offsets: (0..face_bones.len())
+					.map(|face_bone| FacsOffset {
+						position: [0, 1, 2].map(|axis| value(axis, face_bone, pose)),
+						rotation: [3, 4, 5].map(|axis| value(axis, face_bone, pose)),
+					})
+					.collect(),
+			})
+			.collect();
+		Ok(FacsRig {
+			face_bones,
+			controls,
+			poses,
+		})
+	}
+}
+
+impl FacsRig {
+	pub fn find_control(&self, name: &str) -> Option<usize> {
+		self.controls.iter().position(|control| control == name)
+	}
+	/// Sum the offsets of every pose, with one weight per control.
+	/// Missing weights are 0.0, and correctives are weighted by the product of their controls.
+	pub fn evaluate(&self, control_weights: &[f32]) -> Vec<FacsOffset> {
+		let mut offsets = vec![FacsOffset::default(); self.face_bones.len()];
+		for pose in &self.poses {
+			let weight = pose.weight(control_weights);
+			if weight == 0.0 {
+				continue;
+			}
+			for (offset, pose_offset) in offsets.iter_mut().zip(&pose.offsets) {
+				for axis in 0..3 {
+					offset.position[axis] += weight * pose_offset.position[axis];
+					offset.rotation[axis] += weight * pose_offset.rotation[axis];
+				}
+			}
+		}
+		offsets
+	}
+	/// Evaluate the controls into a pose of the skeleton.
+	/// Face bones which are not in the skeleton are skipped.
+	pub fn pose(&self, skeleton: &Skeleton, control_weights: &[f32]) -> Pose {
+		self.pose_offsets(skeleton, &self.evaluate(control_weights))
+	}
+	/// Apply one offset per face bone to its bind pose, for example the offsets of a single FacsPose.
+	/// Face bones which are not in the skeleton are skipped.
+	pub fn pose_offsets(&self, skeleton: &Skeleton, offsets: &[FacsOffset]) -> Pose {
+		self.face_bones
+			.iter()
+			.zip(offsets)
+			.filter_map(|(face_bone, offset)| {
+				let bind = skeleton.local_cframe(skeleton.find(face_bone)?)?;
+				Some((face_bone.clone(), &bind * &offset.to_cframe()))
+			})
+			.collect()
+	}
+}
@@ -0,0 +1,173 @@
+use binrw::BinReaderExt;
+
+use super::{
+	Error, FacsFormat5, LodType4, Revision2, Revision3, Revision4, Revision5, SizeOfVertex2,
+};
+use crate::detect::{Format, detect_at};
+
+/// The counts in the fixed size header of a mesh, read without the vertex data.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MeshHeader {
+	/// `version major.minor`
+	pub major: u8,
+	pub minor: u8,
+	pub vertex_count: u32,
+	pub face_count: u32,
+	/// 0 before mesh v3
+	pub lod_count: u16,
+	/// 0 before mesh v4
+	pub bone_count: u16,
+	/// Only mesh v5 has FACS
+	pub has_facs: bool,
+}
+
+#[binrw::binread]
+#[br(little)]
+struct Header2 {
+	#[br(temp)]
+	_revision: Revision2,
+	#[br(temp, magic = 12u16)]
+	_sizeof_header: (),
+	#[br(temp)]
+	_sizeof_vertex: SizeOfVertex2,
+	#[br(temp, magic = 12u8)]
+	_sizeof_face: (),
+	vertex_count: u32,
+	face_count: u32,
+}
+
+#[binrw::binread]
+#[br(little)]
+struct Header3 {
+	#[br(temp)]
+	_revision: Revision3,
+	#[br(temp, magic = 16u16)]
+	_sizeof_header: (),
+	#[br(temp)]
+	_sizeof_vertex: SizeOfVertex2,
+	#[br(temp, magic = 12u8)]
+	_sizeof_face: (),
+	#[br(temp, magic = 4u16)]
+	_sizeof_lod: (),
+	lod_count: u16,
+	vertex_count: u32,
+	face_count: u32,
+}
+
+/// The counts which Mesh4 and Mesh5 have in common
+#[binrw::binread]
+#[br(little)]
+pub(super) struct Counts4 {
+	pub vertex_count: u32,
+	pub face_count: u32,
+	pub lod_count: u16,
+	pub bone_count: u16,
+	pub bone_names_len: u32,
+	pub subset_count: u16,
+}
+
+#[binrw::binread]
+#[br(little)]
+pub(super) struct Header4 {
+	pub revision: Revision4,
+	#[br(temp, magic = 24u16)]
+	_sizeof_header: (),
+	pub lod_type: LodType4,
+	pub counts: Counts4,
+	pub lod_hq_count: u8,
+	#[br(temp)]
+	_padding: u8,
+}
+
+#[binrw::binread]
+#[br(little)]
+pub(super) struct Header5 {
+	pub revision: Revision5,
+	#[br(temp, magic = 32u16)]
+	_sizeof_header: (),
+	pub lod_type: LodType4,
+	pub counts: Counts4,
+	pub lod_hq_count: u8,
+	#[br(temp, magic = 0u8)]
+	_padding: (),
+	pub facs_format: FacsFormat5,
+	pub sizeof_facs: u32,
+}
+
+/// Read the header of a mesh v1 to v5, which is enough to enforce budgets on the counts.
+/// Other versions fail with NoVariantMatch like read_versioned.
+pub fn read_header<R: BinReaderExt>(read: R) -> Result<MeshHeader, crate::Error> {
+	crate::error::read_with(read, |read| read_header_binrw(read))
+}
+
+fn read_header_binrw<R: BinReaderExt>(mut read: R) -> Result<MeshHeader, Error> {
+	let pos = read.stream_position()?;
+	let empty = MeshHeader {
+		major: 0,
+		minor: 0,
+		vertex_count: 0,
+		face_count: 0,
+		lod_count: 0,
+		bone_count: 0,
+		has_facs: false,
+	};
+	Ok(match detect_at(&mut read, pos)? {
+		#[cfg(feature = "mesh-v1")]
+		Some(Format::Mesh { major: 1, minor }) => {
+			let (_, face_count) = super::v1::read_header1(&mut read)?;
+			MeshHeader {
+				major: 1,
+				minor,
+				vertex_count: face_count.saturating_mul(3),
+				face_count,
+				..empty
+			}
+		}
+		Some(Format::Mesh { major: 2, minor }) => {
+			let header2: Header2 = read.read_le()?;
+			MeshHeader {
+				major: 2,
+				minor,
+				vertex_count: header2.vertex_count,
+				face_count: header2.face_count,
+				..empty
+			}
+		}
+		Some(Format::Mesh { major: 3, minor }) => {
+			let header3: Header3 = read.read_le()?;
+			MeshHeader {
+				major: 3,
+				minor,
+				vertex_count: header3.vertex_count,
+				face_count: header3.face_count,
+				lod_count: header3.lod_count,
+				..empty
+			}
+		}
+		Some(Format::Mesh { major: 4, minor }) => {
+			let header4: Header4 = read.read_le()?;
+			MeshHeader {
+				major: 4,
+				minor,
+				vertex_count: header4.counts.vertex_count,
+				face_count: header4.counts.face_count,
+				lod_count: header4.counts.lod_count,
+				bone_count: header4.counts.bone_count,
+				..empty
+			}
+		}
+		Some(Format::Mesh { major: 5, minor }) => {
+			let header5: Header5 = read.read_le()?;
+			MeshHeader {
+				major: 5,
+				minor,
+				vertex_count: header5.counts.vertex_count,
+				face_count: header5.counts.face_count,
+				lod_count: header5.counts.lod_count,
+				bone_count: header5.counts.bone_count,
+				has_facs: header5.sizeof_facs != 0,
+			}
+		}
+		_ => return Err(Error::NoVariantMatch { pos }),
+	})
+}
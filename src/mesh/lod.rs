@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use super::v2::{Face2, Vertex2, VertexId2, Vertices2};
+use super::v3::{Lod3, Mesh3};
+use super::v4::Mesh4;
+use super::v5::Mesh5;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum LodError {
+	/// The lod at this index starts before the previous one
+	NotMonotonic(usize),
+	/// The lod at this index starts after the last face
+	OutOfBounds(usize),
+	VertexOutOfRange(u32),
+}
+impl core::fmt::Display for LodError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+impl core::error::Error for LodError {}
+
+/// A single level of detail with its own vertex buffer.
+#[derive(Debug, Clone)]
+pub struct LodLevel<'a, V> {
+	/// 0 is the most detailed
+	pub level: usize,
+	/// The first lod_hq_count levels are high quality
+	pub high_quality: bool,
+	/// The faces of this level, indexing the vertices of the mesh
+	pub faces: &'a [Face2],
+	/// The same faces, indexing the compacted vertices
+	pub compacted_faces: Vec<Face2>,
+	/// The vertices used by the faces, in order of first use
+	pub vertices: V,
+	/// The index of each compacted vertex in the vertices of the mesh
+	pub vertex_ids: Vec<VertexId2>,
+}
+
+/// The face range of each level.  With fewer than two lods every face is in a single level.
+pub(super) fn lod_ranges(lods: &[Lod3], face_count: usize) -> Result<Vec<Range<usize>>, LodError> {
+	if lods.len() < 2 {
+		return Ok(core::iter::once(0..face_count).collect());
+	}
+	for (i, &Lod3(offset)) in lods.iter().enumerate() {
+		if face_count < offset as usize {
+			return Err(LodError::OutOfBounds(i));
+		}
+	}
+	lods.windows(2)
+		.enumerate()
+		.map(|(i, lods)| {
+			let (start, end) = (lods[0].0 as usize, lods[1].0 as usize);
+			if end < start {
+				return Err(LodError::NotMonotonic(i + 1));
+			}
+			Ok(start..end)
+		})
+		.collect()
+}
+
+fn compact(faces: &[Face2], vertex_count: usize) -> Result<(Vec<Face2>, Vec<VertexId2>), LodError> {
+	let mut remap = HashMap::new();
+	let mut vertex_ids = Vec::new();
+	let compacted_faces = faces
+		.iter()
+		.map(|Face2(face)| {
+			let mut compacted = [VertexId2(0), VertexId2(0), VertexId2(0)];
+			for (compacted, &VertexId2(vertex)) in compacted.iter_mut().zip(face) {
+				if vertex_count <= vertex as usize {
+					return Err(LodError::VertexOutOfRange(vertex));
+				}
+				let index = *remap.entry(vertex).or_insert_with(|| {
+					vertex_ids.push(VertexId2(vertex));
+					vertex_ids.len() as u32 - 1
+				});
+				*compacted = VertexId2(index);
+			}
+			Ok(Face2(compacted))
+		})
+		.collect::<Result<_, _>>()?;
+	Ok((compacted_faces, vertex_ids))
+}
+
+fn lod_levels<'a, V>(
+	faces: &'a [Face2],
+	lods: &[Lod3],
+	lod_hq_count: usize,
+	vertex_count: usize,
+	gather: impl Fn(&[VertexId2]) -> V,
+) -> Result<Vec<LodLevel<'a, V>>, LodError> {
+	lod_ranges(lods, faces.len())?
+		.into_iter()
+		.enumerate()
+		.map(|(level, range)| {
+			let faces = &faces[range];
+			let (compacted_faces, vertex_ids) = compact(faces, vertex_count)?;
+			Ok(LodLevel {
+				level,
+				high_quality: level < lod_hq_count,
+				faces,
+				compacted_faces,
+				vertices: gather(&vertex_ids),
+				vertex_ids,
+			})
+		})
+		.collect()
+}
+
+fn gather<T: Clone>(vertices: &[T], vertex_ids: &[VertexId2]) -> Vec<T> {
+	vertex_ids
+		.iter()
+		.map(|&VertexId2(vertex)| vertices[vertex as usize].clone())
+		.collect()
+}
+
+impl Mesh3 {
+	/// Split the faces into levels of detail.
+	/// Mesh3 has no lod_hq_count, so only the first level is high quality.
+	pub fn lod_levels(&self) -> Result<Vec<LodLevel<'_, Vertices2>>, LodError> {
+		lod_levels(
+			&self.faces,
+			&self.lods,
+			1,
+			self.vertices.len(),
+			|vertex_ids| match &self.vertices {
+				Vertices2::Full(vertices) => Vertices2::Full(gather(vertices, vertex_ids)),
+				Vertices2::Truncated(vertices) => {
+					Vertices2::Truncated(gather(vertices, vertex_ids))
+				}
+			},
+		)
+	}
+}
+impl Mesh4 {
+	/// Split the faces into levels of detail.
+	pub fn lod_levels(&self) -> Result<Vec<LodLevel<'_, Vec<Vertex2>>>, LodError> {
+		lod_levels(
+			&self.faces,
+			&self.lods,
+			self.lod_hq_count as usize,
+			self.vertices.len(),
+			|vertex_ids| gather(&self.vertices, vertex_ids),
+		)
+	}
+}
+impl Mesh5 {
+	/// Split the faces into levels of detail.
+	pub fn lod_levels(&self) -> Result<Vec<LodLevel<'_, Vec<Vertex2>>>, LodError> {
+		lod_levels(
+			&self.faces,
+			&self.lods,
+			self.lod_hq_count as usize,
+			self.vertices.len(),
+			|vertex_ids| gather(&self.vertices, vertex_ids),
+		)
+	}
+}
+
+#[test]
+fn ranges() {
+	let lods = |lods: &[u32]| lods.iter().copied().map(Lod3).collect::<Vec<_>>();
+	let all = lod_ranges(&[], 4).unwrap();
+	assert_eq!(all, core::slice::from_ref(&(0..4)));
+	assert_eq!(lod_ranges(&lods(&[0, 3, 4]), 4), Ok(vec![0..3, 3..4]));
+	assert_eq!(
+		lod_ranges(&lods(&[0, 3, 2]), 4),
+		Err(LodError::NotMonotonic(2))
+	);
+	assert_eq!(lod_ranges(&lods(&[0, 5]), 4), Err(LodError::OutOfBounds(1)));
+}
@@ -12,43 +12,76 @@ mod v4;
 pub use v4::*;
 mod v5;
 pub use v5::*;
+mod v6;
+pub use v6::*;
+mod v7;
+pub use v7::*;
+mod skeleton;
+pub use skeleton::*;
+mod facs;
+pub use facs::*;
+mod lod;
+pub use lod::*;
+mod simplify;
+pub use simplify::*;
+mod header;
+pub use header::{MeshHeader, read_header};
+mod borrowed;
+pub use borrowed::*;
 
-use binrw::BinReaderExt;
+use binrw::{BinReaderExt, BinWriterExt};
+
+use crate::limits::ReadLimits;
 
 pub type Error = binrw::Error;
 
 #[cfg(feature = "mesh-v1")]
 #[expect(clippy::large_enum_variant)]
-#[binrw::binread]
-#[br(little)]
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub enum Mesh {
 	// TODO: use feature-gated enum variant when this issue is fixed
 	// https://github.com/jam1garner/binrw/issues/360
 	// #[cfg(feature = "mesh-v1")]
-	V1(Mesh1),
-	V2(Mesh2),
-	V3(Mesh3),
-	V4(Mesh4),
-	V5(Mesh5),
-	//Version6(Mesh6),
-	//Version7(Mesh7),
+	V1(#[br(args(limits))] Mesh1),
+	V2(#[br(args(limits))] Mesh2),
+	V3(#[br(args(limits))] Mesh3),
+	V4(#[br(args(limits))] Mesh4),
+	V5(#[br(args(limits))] Mesh5),
+	V6(#[br(args(limits))] Mesh6),
+	V7(#[br(args(limits))] Mesh7),
 }
 
 #[cfg(not(feature = "mesh-v1"))]
 #[expect(clippy::large_enum_variant)]
-#[binrw::binread]
-#[br(little)]
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub enum Mesh {
-	V2(Mesh2),
-	V3(Mesh3),
-	V4(Mesh4),
-	V5(Mesh5),
-	//Version6(Mesh6),
-	//Version7(Mesh7),
+	V2(#[br(args(limits))] Mesh2),
+	V3(#[br(args(limits))] Mesh3),
+	V4(#[br(args(limits))] Mesh4),
+	V5(#[br(args(limits))] Mesh5),
+	V6(#[br(args(limits))] Mesh6),
+	V7(#[br(args(limits))] Mesh7),
+}
+
+pub fn read_versioned<R: BinReaderExt>(read: R) -> Result<Mesh, crate::Error> {
+	crate::error::read_with(read, |read| read.read_le())
+}
+
+/// Read with limits on the counts in the file, for untrusted input.
+pub fn read_versioned_with_limits<R: BinReaderExt>(
+	read: R,
+	limits: ReadLimits,
+) -> Result<Mesh, crate::Error> {
+	crate::error::read_with(read, |read| read.read_le_args((limits,)))
 }
 
-pub fn read_versioned<R: BinReaderExt>(mut read: R) -> Result<Mesh, Error> {
-	read.read_le()
+/// Mesh1 is written with FloatFormat1::Shortest and Mesh7 with an uncompressed draco bitstream.
+pub fn write_versioned<W: BinWriterExt>(mut write: W, mesh: &Mesh) -> Result<(), Error> {
+	write.write_le(mesh)
 }
@@ -0,0 +1,585 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::ops::Range;
+
+use super::lod::{LodError, lod_ranges};
+use super::v2::{Face2, Vertex2, VertexId2, Vertices2};
+use super::v3::{Lod3, Mesh3};
+use super::v4::{Envelope4, LodType4, Mesh4, Subset4};
+use super::v5::Mesh5;
+
+// boundary edges are this much more expensive to move away from than faces
+const BOUNDARY_WEIGHT: f64 = 10.0;
+
+/// A symmetric 4x4 matrix, stored as its upper triangle.
+#[derive(Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+impl Quadric {
+	fn plane([a, b, c, d]: [f64; 4], weight: f64) -> Self {
+		Self(
+			[
+				a * a,
+				a * b,
+				a * c,
+				a * d,
+				b * b,
+				b * c,
+				b * d,
+				c * c,
+				c * d,
+				d * d,
+			]
+			.map(|q| q * weight),
+		)
+	}
+	fn add(&mut self, other: &Self) {
+		for (q, other) in self.0.iter_mut().zip(other.0) {
+			*q += other;
+		}
+	}
+	fn error(&self, [x, y, z]: [f64; 3]) -> f64 {
+		let q = &self.0;
+		q[0] * x * x
+			+ q[4] * y * y
+			+ q[7] * z * z
+			+ q[9] + 2.0 * (q[1] * x * y + q[2] * x * z + q[5] * y * z + q[3] * x + q[6] * y + q[8] * z)
+	}
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+	[a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+	[
+		a[1] * b[2] - a[2] * b[1],
+		a[2] * b[0] - a[0] * b[2],
+		a[0] * b[1] - a[1] * b[0],
+	]
+}
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+	a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+fn normal(positions: &[[f64; 3]], [a, b, c]: [u32; 3]) -> [f64; 3] {
+	let a = positions[a as usize];
+	cross(sub(positions[b as usize], a), sub(positions[c as usize], a))
+}
+
+/// A candidate collapse of `from` into `to`.
+struct Collapse {
+	cost: f64,
+	from: u32,
+	to: u32,
+	versions: [u32; 2],
+}
+impl PartialEq for Collapse {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+impl Eq for Collapse {}
+impl PartialOrd for Collapse {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+impl Ord for Collapse {
+	// the cheapest collapse is the greatest, so it is popped first
+	fn cmp(&self, other: &Self) -> Ordering {
+		other.cost.total_cmp(&self.cost)
+	}
+}
+
+struct Simplifier<'a> {
+	positions: Vec<[f64; 3]>,
+	faces: &'a mut [[u32; 3]],
+	face_alive: Vec<bool>,
+	vertex_faces: Vec<Vec<u32>>,
+	quadrics: Vec<Quadric>,
+	versions: Vec<u32>,
+	heap: BinaryHeap<Collapse>,
+}
+impl Simplifier<'_> {
+	fn neighbors(&self, vertex: u32) -> Vec<u32> {
+		let mut neighbors = Vec::new();
+		for &face in &self.vertex_faces[vertex as usize] {
+			if self.face_alive[face as usize] {
+				for other in self.faces[face as usize] {
+					if other != vertex && !neighbors.contains(&other) {
+						neighbors.push(other);
+					}
+				}
+			}
+		}
+		neighbors
+	}
+	fn push_collapses(&mut self, vertex: u32) {
+		for neighbor in self.neighbors(vertex) {
+			for (from, to) in [(vertex, neighbor), (neighbor, vertex)] {
+				let mut quadric = self.quadrics[from as usize];
+				quadric.add(&self.quadrics[to as usize]);
+				self.heap.push(Collapse {
+					cost: quadric.error(self.positions[to as usize]),
+					from,
+					to,
+					versions: [self.versions[from as usize], self.versions[to as usize]],
+				});
+			}
+		}
+	}
+	/// Collapsing must not fold any face over, or join two surfaces which only share the edge.
+	fn can_collapse(&self, from: u32, to: u32) -> bool {
+		let from_neighbors = self.neighbors(from);
+		let to_neighbors = self.neighbors(to);
+		let shared_neighbors = from_neighbors
+			.iter()
+			.filter(|neighbor| to_neighbors.contains(neighbor))
+			.count();
+		let mut shared_faces = 0;
+		for &face in &self.vertex_faces[from as usize] {
+			if !self.face_alive[face as usize] {
+				continue;
+			}
+			let vertices = self.faces[face as usize];
+			if vertices.contains(&to) {
+				shared_faces += 1;
+				continue;
+			}
+			let before = normal(&self.positions, vertices);
+			let after = normal(
+				&self.positions,
+				vertices.map(|v| if v == from { to } else { v }),
+			);
+			if dot(before, after) <= 0.0 {
+				return false;
+			}
+		}
+		shared_neighbors <= shared_faces
+	}
+	fn collapse(&mut self, from: u32, to: u32) -> usize {
+		let mut removed = 0;
+		for face in core::mem::take(&mut self.vertex_faces[from as usize]) {
+			if !self.face_alive[face as usize] {
+				continue;
+			}
+			let vertices = &mut self.faces[face as usize];
+			if vertices.contains(&to) {
+				self.face_alive[face as usize] = false;
+				removed += 1;
+			} else {
+				for vertex in vertices.iter_mut() {
+					if *vertex == from {
+						*vertex = to;
+					}
+				}
+				self.vertex_faces[to as usize].push(face);
+			}
+		}
+		let quadric = self.quadrics[from as usize];
+		self.quadrics[to as usize].add(&quadric);
+		self.versions[from as usize] += 1;
+		self.versions[to as usize] += 1;
+		self.push_collapses(to);
+		removed
+	}
+}
+
+/// Reduce faces towards target_faces by collapsing edges with the least quadric error.
+/// Vertices are never moved or created, so the result indexes the same vertices.
+pub fn simplify_faces(
+	positions: &[[f32; 3]],
+	faces: &[[u32; 3]],
+	target_faces: usize,
+) -> Vec<[u32; 3]> {
+	let mut faces = faces.to_vec();
+	let face_alive: Vec<bool> = faces
+		.iter()
+		.map(|&[a, b, c]| {
+			a != b && b != c && c != a && [a, b, c].iter().all(|&v| (v as usize) < positions.len())
+		})
+		.collect();
+	let mut simplifier = Simplifier {
+		positions: positions.iter().map(|p| p.map(f64::from)).collect(),
+		faces: &mut faces,
+		face_alive,
+		vertex_faces: vec![Vec::new(); positions.len()],
+		quadrics: vec![Quadric::default(); positions.len()],
+		versions: vec![0; positions.len()],
+		heap: BinaryHeap::new(),
+	};
+
+	let mut edges = HashMap::new();
+	for (face, &vertices) in simplifier.faces.iter().enumerate() {
+		if !simplifier.face_alive[face] {
+			continue;
+		}
+		let n = normal(&simplifier.positions, vertices);
+		let length = dot(n, n).sqrt();
+		for (i, &vertex) in vertices.iter().enumerate() {
+			simplifier.vertex_faces[vertex as usize].push(face as u32);
+			let next = vertices[(i + 1) % 3];
+			*edges
+				.entry((vertex.min(next), vertex.max(next)))
+				.or_insert(0u32) += 1;
+		}
+		if length == 0.0 {
+			continue;
+		}
+		let n = n.map(|n| n / length);
+		let d = -dot(n, simplifier.positions[vertices[0] as usize]);
+		// weighted by area
+		let quadric = Quadric::plane([n[0], n[1], n[2], d], length / 2.0);
+		for vertex in vertices {
+			simplifier.quadrics[vertex as usize].add(&quadric);
+		}
+	}
+	// boundary edges are held in place by a plane perpendicular to the face
+	for (face, &vertices) in simplifier.faces.iter().enumerate() {
+		if !simplifier.face_alive[face] {
+			continue;
+		}
+		let n = normal(&simplifier.positions, vertices);
+		for (i, &a) in vertices.iter().enumerate() {
+			let b = vertices[(i + 1) % 3];
+			if edges[&(a.min(b), a.max(b))] != 1 {
+				continue;
+			}
+			let edge = sub(
+				simplifier.positions[b as usize],
+				simplifier.positions[a as usize],
+			);
+			let m = cross(edge, n);
+			let length = dot(m, m).sqrt();
+			if length == 0.0 {
+				continue;
+			}
+			let m = m.map(|m| m / length);
+			let d = -dot(m, simplifier.positions[a as usize]);
+			let quadric = Quadric::plane([m[0], m[1], m[2], d], dot(edge, edge) * BOUNDARY_WEIGHT);
+			simplifier.quadrics[a as usize].add(&quadric);
+			simplifier.quadrics[b as usize].add(&quadric);
+		}
+	}
+	for vertex in 0..positions.len() as u32 {
+		simplifier.push_collapses(vertex);
+	}
+
+	let mut face_count = simplifier.face_alive.iter().filter(|&&alive| alive).count();
+	while target_faces < face_count {
+		let Some(Collapse {
+			from, to, versions, ..
+		}) = simplifier.heap.pop()
+		else {
+			break;
+		};
+		if versions
+			!= [
+				simplifier.versions[from as usize],
+				simplifier.versions[to as usize],
+			] {
+			continue;
+		}
+		if simplifier.can_collapse(from, to) {
+			face_count -= simplifier.collapse(from, to);
+		}
+	}
+	let face_alive = simplifier.face_alive;
+	faces
+		.into_iter()
+		.zip(face_alive)
+		.filter_map(|(face, alive)| alive.then_some(face))
+		.collect()
+}
+
+/// How to build a chain of levels of detail.
+#[derive(Debug, Clone)]
+pub struct LodOptions {
+	/// The number of levels, including the base mesh
+	pub level_count: usize,
+	/// The target face count of each level relative to the previous level
+	pub ratio: f32,
+	/// Levels are not simplified below this many faces
+	pub min_faces: usize,
+}
+impl Default for LodOptions {
+	fn default() -> Self {
+		Self {
+			level_count: 4,
+			ratio: 0.5,
+			min_faces: 16,
+		}
+	}
+}
+impl LodOptions {
+	fn target(&self, face_count: usize) -> usize {
+		(face_count as f32 * self.ratio).ceil() as usize
+	}
+}
+
+/// Index the faces relative to the start of their vertex range.
+/// A face which uses a vertex outside of the range fails with VertexOutOfRange.
+fn to_indices(faces: &[Face2], vertices: Range<usize>) -> Result<Vec<[u32; 3]>, LodError> {
+	faces
+		.iter()
+		.map(|Face2(face)| {
+			let mut indices = [0; 3];
+			for (index, &VertexId2(vertex)) in indices.iter_mut().zip(face) {
+				if !vertices.contains(&(vertex as usize)) {
+					return Err(LodError::VertexOutOfRange(vertex));
+				}
+				*index = vertex - vertices.start as u32;
+			}
+			Ok(indices)
+		})
+		.collect()
+}
+fn to_faces(indices: &[[u32; 3]]) -> impl Iterator<Item = Face2> + '_ {
+	indices.iter().map(|face| Face2(face.map(VertexId2)))
+}
+
+/// Every level shares the vertices of the base mesh.
+fn generate_shared(
+	positions: &[[f32; 3]],
+	faces: &mut Vec<Face2>,
+	lods: &mut Vec<Lod3>,
+	options: &LodOptions,
+) -> Result<(), LodError> {
+	let base = lod_ranges(lods, faces.len())?.swap_remove(0);
+	let mut level = to_indices(&faces[base], 0..positions.len())?;
+	faces.clear();
+	faces.extend(to_faces(&level));
+	lods.clear();
+	lods.extend([Lod3(0), Lod3(faces.len() as u32)]);
+	for _ in 1..options.level_count {
+		let target = options.target(level.len());
+		if target < options.min_faces {
+			break;
+		}
+		let simplified = simplify_faces(positions, &level, target);
+		if level.len() <= simplified.len() {
+			break;
+		}
+		level = simplified;
+		faces.extend(to_faces(&level));
+		lods.push(Lod3(faces.len() as u32));
+	}
+	Ok(())
+}
+
+/// Each subset is simplified on its own, and every level gets its own subsets
+/// and copies of their vertices, so the vertex and face ranges never overlap.
+fn generate_skinned(
+	vertices: &mut Vec<Vertex2>,
+	envelopes: &mut Vec<Envelope4>,
+	faces: &mut Vec<Face2>,
+	lods: &mut Vec<Lod3>,
+	subsets: &mut Vec<Subset4>,
+	options: &LodOptions,
+) -> Result<(), LodError> {
+	let base = lod_ranges(lods, faces.len())?.swap_remove(0);
+	// subsets of the other levels are rebuilt
+	let in_base = |subset: &Subset4| {
+		let start = subset.faces_offset as usize;
+		base.start <= start && start + subset.faces_len as usize <= base.end
+	};
+	// check the base subsets before anything is changed
+	for subset in subsets.iter().filter(|subset| in_base(subset)) {
+		let start = subset.vertices_offset as usize;
+		let end = start + subset.vertices_len as usize;
+		if vertices.len() < end {
+			return Err(LodError::VertexOutOfRange(end as u32));
+		}
+		let faces_start = subset.faces_offset as usize;
+		let faces_range = faces_start..faces_start + subset.faces_len as usize;
+		to_indices(&faces[faces_range], start..end)?;
+	}
+	subsets.retain(in_base);
+	faces.truncate(base.end);
+	faces.drain(..base.start);
+	for subset in subsets.iter_mut() {
+		subset.faces_offset -= base.start as u32;
+	}
+	// vertices after the base level belonged to the other levels
+	let skinned = envelopes.len() == vertices.len();
+	let vertices_end = subsets
+		.iter()
+		.map(|subset| (subset.vertices_offset + subset.vertices_len) as usize)
+		.chain(
+			faces
+				.iter()
+				.flat_map(|Face2(face)| face.iter().map(|vertex| vertex.0 as usize + 1)),
+		)
+		.max()
+		.unwrap_or(0);
+	if vertices_end < vertices.len() {
+		vertices.truncate(vertices_end);
+		if skinned {
+			envelopes.truncate(vertices_end);
+		}
+	}
+	lods.clear();
+	lods.extend([Lod3(0), Lod3(faces.len() as u32)]);
+
+	let mut level_subsets = subsets.clone();
+	for _ in 1..options.level_count {
+		let (faces_len, vertices_len) = (faces.len(), vertices.len());
+		let face_count = level_subsets
+			.iter()
+			.map(|subset| subset.faces_len as usize)
+			.sum();
+		if options.target(face_count) < options.min_faces {
+			break;
+		}
+		let mut next_subsets = Vec::new();
+		for subset in &level_subsets {
+			let start = subset.vertices_offset as usize;
+			let subset_vertices = start..start + subset.vertices_len as usize;
+			let faces_range =
+				subset.faces_offset as usize..(subset.faces_offset + subset.faces_len) as usize;
+			let positions: Vec<[f32; 3]> = vertices[subset_vertices.clone()]
+				.iter()
+				.map(|vertex| vertex.pos)
+				.collect();
+			let indices = to_indices(&faces[faces_range], subset_vertices.clone())?;
+			let simplified = simplify_faces(&positions, &indices, options.target(indices.len()));
+			if simplified.is_empty() {
+				continue;
+			}
+			// copy the vertices used by the simplified faces
+			let faces_offset = faces.len() as u32;
+			let vertices_offset = vertices.len() as u32;
+			let mut remap = HashMap::new();
+			for face in simplified {
+				let face = face.map(|vertex| {
+					remap
+						.entry(vertex)
+						.or_insert_with(|| {
+							let source = start + vertex as usize;
+							vertices.push(vertices[source].clone());
+							if skinned {
+								envelopes.push(envelopes[source].clone());
+							}
+							VertexId2(vertices.len() as u32 - 1)
+						})
+						.clone()
+				});
+				faces.push(Face2(face));
+			}
+			next_subsets.push(Subset4 {
+				faces_offset,
+				faces_len: faces.len() as u32 - faces_offset,
+				vertices_offset,
+				vertices_len: vertices.len() as u32 - vertices_offset,
+				bone_count: subset.bone_count,
+				bones: subset.bones.clone(),
+			});
+		}
+		let simplified_count: usize = next_subsets
+			.iter()
+			.map(|subset| subset.faces_len as usize)
+			.sum();
+		if simplified_count == 0 || face_count <= simplified_count {
+			// undo the level
+			faces.truncate(faces_len);
+			vertices.truncate(vertices_len);
+			if skinned {
+				envelopes.truncate(vertices_len);
+			}
+			break;
+		}
+		lods.push(Lod3(faces.len() as u32));
+		subsets.extend_from_slice(&next_subsets);
+		level_subsets = next_subsets;
+	}
+	Ok(())
+}
+
+impl Mesh3 {
+	/// Replace every level of detail after the first with a simplified chain.
+	pub fn generate_lods(&mut self, options: &LodOptions) -> Result<(), LodError> {
+		let positions: Vec<[f32; 3]> = match &self.vertices {
+			Vertices2::Full(vertices) => vertices.iter().map(|vertex| vertex.pos).collect(),
+			Vertices2::Truncated(vertices) => vertices.iter().map(|vertex| vertex.pos).collect(),
+		};
+		generate_shared(&positions, &mut self.faces, &mut self.lods, options)
+	}
+}
+fn generate_lods(
+	vertices: &mut Vec<Vertex2>,
+	envelopes: &mut Vec<Envelope4>,
+	faces: &mut Vec<Face2>,
+	lods: &mut Vec<Lod3>,
+	subsets: &mut Vec<Subset4>,
+	options: &LodOptions,
+) -> Result<LodType4, LodError> {
+	if subsets.is_empty() {
+		let positions: Vec<[f32; 3]> = vertices.iter().map(|vertex| vertex.pos).collect();
+		generate_shared(&positions, faces, lods, options)?;
+	} else {
+		generate_skinned(vertices, envelopes, faces, lods, subsets, options)?;
+	}
+	Ok(crate::TriangleMesh::lod_type4(lods))
+}
+
+impl Mesh4 {
+	/// Replace every level of detail after the first with a simplified chain.
+	/// Meshes with subsets get new subsets for each level, with their own copies of the vertices.
+	/// Only the first level is high quality afterwards.
+	pub fn generate_lods(&mut self, options: &LodOptions) -> Result<(), LodError> {
+		self.lod_type = generate_lods(
+			&mut self.vertices,
+			&mut self.envelopes,
+			&mut self.faces,
+			&mut self.lods,
+			&mut self.subsets,
+			options,
+		)?;
+		self.lod_hq_count = 1;
+		Ok(())
+	}
+}
+impl Mesh5 {
+	/// Replace every level of detail after the first with a simplified chain.
+	/// Meshes with subsets get new subsets for each level, with their own copies of the vertices.
+	/// Only the first level is high quality afterwards.
+	pub fn generate_lods(&mut self, options: &LodOptions) -> Result<(), LodError> {
+		self.lod_type = generate_lods(
+			&mut self.vertices,
+			&mut self.envelopes,
+			&mut self.faces,
+			&mut self.lods,
+			&mut self.subsets,
+			options,
+		)?;
+		self.lod_hq_count = 1;
+		Ok(())
+	}
+}
+
+#[test]
+fn simplify_grid() {
+	// a flat grid simplifies down to its corners
+	let n = 10;
+	let positions: Vec<[f32; 3]> = (0..=n)
+		.flat_map(|y| (0..=n).map(move |x| [x as f32, y as f32, 0.0]))
+		.collect();
+	let index = |x: u32, y: u32| y * (n + 1) + x;
+	let faces: Vec<[u32; 3]> = (0..n)
+		.flat_map(|y| (0..n).map(move |x| (x, y)))
+		.flat_map(|(x, y)| {
+			[
+				[index(x, y), index(x + 1, y), index(x + 1, y + 1)],
+				[index(x, y), index(x + 1, y + 1), index(x, y + 1)],
+			]
+		})
+		.collect();
+	let simplified = simplify_faces(&positions, &faces, 8);
+	assert!(simplified.len() <= 8);
+	for corner in [index(0, 0), index(n, 0), index(0, n), index(n, n)] {
+		assert!(simplified.iter().any(|face| face.contains(&corner)));
+	}
+	// every face still faces up
+	let positions: Vec<[f64; 3]> = positions.iter().map(|p| p.map(f64::from)).collect();
+	for &face in &simplified {
+		assert!(0.0 < normal(&positions, face)[2]);
+	}
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+
+use super::v2::Vertex2;
+use super::v4::{Bone4, CFrame4, Envelope4, Mesh4, Subset4};
+use super::v5::Mesh5;
+use super::v6::Skinning6;
+
+/// The bone influences of a vertex, resolved into indices of the skeleton bones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VertexWeights {
+	pub bones: [u16; 4],
+	/// Sums to 1.0, unused slots have a weight of 0.0 and bone 0
+	pub weights: [f32; 4],
+}
+
+/// Transforms by bone name which replace the bind pose of a bone relative to its parent.
+pub type Pose = HashMap<String, CFrame4>;
+
+/// Vertex positions and normals deformed by a pose.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PosedVertices {
+	pub positions: Vec<[f32; 3]>,
+	pub normals: Vec<[f32; 3]>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SkeletonBone<'a> {
+	pub id: u16,
+	/// None if the name is not valid utf8
+	pub name: Option<&'a str>,
+	/// Bones whose parent does not exist are roots
+	pub parent: Option<u16>,
+	pub bone: &'a Bone4,
+}
+
+/// A view of the bones and envelopes of a mesh.
+#[derive(Debug, Clone, Copy)]
+pub struct Skeleton<'a> {
+	bones: &'a [Bone4],
+	bone_names: &'a [u8],
+	envelopes: &'a [Envelope4],
+	subsets: &'a [Subset4],
+}
+
+impl<'a> Skeleton<'a> {
+	pub const fn new(
+		bones: &'a [Bone4],
+		bone_names: &'a [u8],
+		envelopes: &'a [Envelope4],
+		subsets: &'a [Subset4],
+	) -> Self {
+		Self {
+			bones,
+			bone_names,
+			envelopes,
+			subsets,
+		}
+	}
+	pub const fn len(&self) -> usize {
+		self.bones.len()
+	}
+	pub const fn is_empty(&self) -> bool {
+		self.bones.is_empty()
+	}
+	/// Bone names are null terminated strings in the bone_names buffer.
+	pub fn bone_name(&self, id: u16) -> Option<&'a str> {
+		let bone = self.bones.get(id as usize)?;
+		let name = self.bone_names.get(bone.bone_name_pos as usize..)?;
+		let name = name.split(|&b| b == 0).next()?;
+		core::str::from_utf8(name).ok()
+	}
+	pub fn parent(&self, id: u16) -> Option<u16> {
+		let parent = self.bones.get(id as usize)?.parent.get()?;
+		(parent != id && (parent as usize) < self.bones.len()).then_some(parent)
+	}
+	pub fn bone(&self, id: u16) -> Option<SkeletonBone<'a>> {
+		Some(SkeletonBone {
+			id,
+			name: self.bone_name(id),
+			parent: self.parent(id),
+			bone: self.bones.get(id as usize)?,
+		})
+	}
+	/// Iterate the bones in file order.
+	pub fn bones(&self) -> impl ExactSizeIterator<Item = SkeletonBone<'a>> + '_ {
+		(0..self.bones.len() as u16).map(|id| self.bone(id).unwrap())
+	}
+	pub fn find(&self, name: &str) -> Option<u16> {
+		(0..self.bones.len() as u16).find(|&id| self.bone_name(id) == Some(name))
+	}
+	pub fn roots(&self) -> impl Iterator<Item = u16> + '_ {
+		(0..self.bones.len() as u16).filter(|&id| self.parent(id).is_none())
+	}
+	pub fn children(&self, id: u16) -> impl Iterator<Item = u16> + '_ {
+		(0..self.bones.len() as u16).filter(move |&child| self.parent(child) == Some(id))
+	}
+	/// Iterate the bones so that every parent comes before its children.
+	/// Bones which are part of a cycle are never reached from a root, and are not visited.
+	pub fn depth_first(&self) -> impl Iterator<Item = u16> + '_ {
+		// index the children of every bone once instead of scanning with children()
+		let mut children = vec![Vec::new(); self.bones.len()];
+		let mut stack = Vec::new();
+		for id in (0..self.bones.len() as u16).rev() {
+			match self.parent(id) {
+				Some(parent) => children[parent as usize].push(id),
+				None => stack.push(id),
+			}
+		}
+		// both are in reverse file order, so the first child is popped first
+		core::iter::from_fn(move || {
+			let id = stack.pop()?;
+			stack.extend_from_slice(&children[id as usize]);
+			Some(id)
+		})
+	}
+	/// The bind pose transform of a bone, relative to the mesh.
+	pub fn world_cframe(&self, id: u16) -> Option<&'a CFrame4> {
+		Some(&self.bones.get(id as usize)?.cframe)
+	}
+	/// The bind pose transform of a bone, relative to its parent.
+	pub fn local_cframe(&self, id: u16) -> Option<CFrame4> {
+		let world = self.world_cframe(id)?;
+		Some(match self.parent(id) {
+			Some(parent) => &self.bones[parent as usize].cframe.inverse() * world,
+			None => world.clone(),
+		})
+	}
+	/// Resolve the envelope of every vertex through the subset which contains it.
+	/// Vertices outside of every subset or without any valid influence are None.
+	pub fn vertex_weights(&self) -> Vec<Option<VertexWeights>> {
+		let mut vertex_weights = vec![None; self.envelopes.len()];
+		for subset in self.subsets {
+			let start = (subset.vertices_offset as usize).min(self.envelopes.len());
+			let end = (start + subset.vertices_len as usize).min(self.envelopes.len());
+			for (envelope, vertex_weights) in self.envelopes[start..end]
+				.iter()
+				.zip(&mut vertex_weights[start..end])
+			{
+				let mut weights = VertexWeights {
+					bones: [0; 4],
+					weights: [0.0; 4],
+				};
+				let mut total = 0.0;
+				for (slot, (&local, &weight)) in
+					envelope.bones.iter().zip(&envelope.weights).enumerate()
+				{
+					// subset bones are only valid up to bone_count
+					let bone = subset
+						.bones
+						.get(local as usize)
+						.filter(|_| (local as u32) < subset.bone_count)
+						.and_then(|bone| bone.get())
+						.filter(|&bone| (bone as usize) < self.bones.len());
+					if let (Some(bone), 1..) = (bone, weight) {
+						weights.bones[slot] = bone;
+						weights.weights[slot] = weight as f32;
+						total += weight as f32;
+					}
+				}
+				if 0.0 < total {
+					weights.weights = weights.weights.map(|weight| weight / total);
+					*vertex_weights = Some(weights);
+				}
+			}
+		}
+		vertex_weights
+	}
+	/// The world transform of every bone in a pose.
+	/// Bones missing from the pose keep their bind pose relative to their parent.
+	pub fn posed_world_cframes(&self, pose: &Pose) -> Vec<CFrame4> {
+		let mut world: Vec<CFrame4> = self.bones.iter().map(|bone| bone.cframe.clone()).collect();
+		for id in self.depth_first() {
+			let local = match self.bone_name(id).and_then(|name| pose.get(name)) {
+				Some(local) => local.clone(),
+				None => self.local_cframe(id).unwrap(),
+			};
+			world[id as usize] = match self.parent(id) {
+				Some(parent) => &world[parent as usize] * &local,
+				None => local,
+			};
+		}
+		world
+	}
+	/// Deform vertices by linear blend skinning.
+	/// Vertices without any bone influence are left in place.
+	pub fn deform(&self, vertices: &[Vertex2], pose: &Pose) -> PosedVertices {
+		self.deform_points(
+			vertices.iter().map(|vertex| (vertex.pos, vertex.norm)),
+			pose,
+		)
+	}
+	/// Deform the position and normal of every vertex like deform,
+	/// for vertices which are not stored as Vertex2.
+	pub fn deform_points(
+		&self,
+		vertices: impl ExactSizeIterator<Item = ([f32; 3], [f32; 3])>,
+		pose: &Pose,
+	) -> PosedVertices {
+		let transforms: Vec<CFrame4> = self
+			.posed_world_cframes(pose)
+			.iter()
+			.zip(self.bones)
+			.map(|(posed, bone)| posed * &bone.cframe.inverse())
+			.collect();
+		let vertex_weights = self.vertex_weights();
+		let mut posed = PosedVertices {
+			positions: Vec::with_capacity(vertices.len()),
+			normals: Vec::with_capacity(vertices.len()),
+		};
+		for (i, (pos, norm)) in vertices.enumerate() {
+			let Some(Some(weights)) = vertex_weights.get(i) else {
+				posed.positions.push(pos);
+				posed.normals.push(norm);
+				continue;
+			};
+			let mut position = [0.0; 3];
+			let mut normal = [0.0; 3];
+			for (&bone, &weight) in weights.bones.iter().zip(&weights.weights) {
+				if weight != 0.0 {
+					let transform = &transforms[bone as usize];
+					let moved = transform.point_to_world_space(pos);
+					let turned = transform.vector_to_world_space(norm);
+					for axis in 0..3 {
+						position[axis] += weight * moved[axis];
+						normal[axis] += weight * turned[axis];
+					}
+				}
+			}
+			// blending rotations shortens the normal
+			let length = normal.iter().map(|n| n * n).sum::<f32>().sqrt();
+			if 0.0 < length {
+				normal = normal.map(|n| n / length);
+			}
+			posed.positions.push(position);
+			posed.normals.push(normal);
+		}
+		posed
+	}
+}
+
+impl Mesh4 {
+	pub fn skeleton(&self) -> Skeleton<'_> {
+		Skeleton::new(
+			&self.bones,
+			&self.bone_names,
+			&self.envelopes,
+			&self.subsets,
+		)
+	}
+	/// Deform the vertices into a pose by linear blend skinning.
+	pub fn pose(&self, pose: &Pose) -> PosedVertices {
+		self.skeleton().deform(&self.vertices, pose)
+	}
+}
+impl Mesh5 {
+	pub fn skeleton(&self) -> Skeleton<'_> {
+		Skeleton::new(
+			&self.bones,
+			&self.bone_names,
+			&self.envelopes,
+			&self.subsets,
+		)
+	}
+	/// Deform the vertices into a pose by linear blend skinning.
+	pub fn pose(&self, pose: &Pose) -> PosedVertices {
+		self.skeleton().deform(&self.vertices, pose)
+	}
+}
+impl Skinning6 {
+	pub fn skeleton(&self) -> Skeleton<'_> {
+		Skeleton::new(
+			&self.bones,
+			&self.bone_names,
+			&self.envelopes,
+			&self.subsets,
+		)
+	}
+}
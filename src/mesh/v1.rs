@@ -1,4 +1,6 @@
-use std::io::BufRead;
+use std::io::{BufRead, Read};
+
+use crate::limits::{LimitError, ReadLimits};
 
 #[derive(Debug)]
 pub enum Error1 {
@@ -16,41 +18,64 @@ impl std::error::Error for Error1 {}
 
 enum InnerError {
 	Io(std::io::Error),
-	Other(Error1),
+	Other { pos: u64, error: Error1 },
+	Limit { pos: u64, error: LimitError },
 }
 impl From<std::io::Error> for InnerError {
 	fn from(value: std::io::Error) -> Self {
 		Self::Io(value)
 	}
 }
-impl From<Error1> for InnerError {
-	fn from(value: Error1) -> Self {
-		Self::Other(value)
-	}
-}
 impl From<InnerError> for binrw::Error {
 	fn from(value: InnerError) -> Self {
 		match value {
 			InnerError::Io(error) => Self::Io(error),
-			InnerError::Other(error1) => Self::Custom {
-				pos: 0,
-				err: Box::new(error1),
+			InnerError::Other { pos, error } => Self::Custom {
+				pos,
+				err: Box::new(error),
+			},
+			InnerError::Limit { pos, error } => Self::Custom {
+				pos,
+				err: Box::new(error),
 			},
 		}
 	}
 }
 
+/// Reads lines while keeping track of their stream offsets.
 struct LineMachine<R: BufRead> {
-	lines: std::io::Lines<R>,
+	read: R,
+	pos: u64,
+	/// The number of bytes which the remaining lines may use
+	budget: u64,
 }
 impl<R: BufRead> LineMachine<R> {
-	fn new(read: R) -> Self {
-		Self {
-			lines: read.lines(),
-		}
+	const fn new(read: R, pos: u64, budget: u64) -> Self {
+		Self { read, pos, budget }
 	}
-	fn read_line(&mut self) -> Result<String, std::io::Error> {
-		self.lines.next().ok_or(std::io::ErrorKind::UnexpectedEof)?
+	/// Returns the offset of the start of the line and the line without its terminator.
+	fn read_line(&mut self) -> Result<(u64, String), InnerError> {
+		let start = self.pos;
+		let mut line = String::new();
+		let len = (&mut self.read).take(self.budget).read_line(&mut line)?;
+		if len == 0 {
+			return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+		}
+		if len as u64 == self.budget && !line.ends_with('\n') {
+			return Err(InnerError::Limit {
+				pos: start,
+				error: LimitError::AllocBytes(len as u64),
+			});
+		}
+		self.pos += len as u64;
+		self.budget -= len as u64;
+		if line.ends_with('\n') {
+			line.pop();
+			if line.ends_with('\r') {
+				line.pop();
+			}
+		}
+		Ok((start, line))
 	}
 }
 
@@ -59,6 +84,14 @@ pub enum Revision1 {
 	Version100,
 	Version101,
 }
+impl Revision1 {
+	pub const fn as_str(&self) -> &'static str {
+		match self {
+			Self::Version100 => "version 1.00",
+			Self::Version101 => "version 1.01",
+		}
+	}
+}
 impl std::str::FromStr for Revision1 {
 	type Err = Error1;
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -84,16 +117,140 @@ pub struct Mesh1 {
 }
 
 impl binrw::BinRead for Mesh1 {
-	type Args<'a> = ();
+	type Args<'a> = (ReadLimits,);
 	fn read_options<R: binrw::BinReaderExt>(
 		reader: &mut R,
 		_endian: binrw::Endian,
-		_args: Self::Args<'_>,
+		(limits,): Self::Args<'_>,
 	) -> binrw::BinResult<Self> {
-		Ok(read(binrw::io::BufReader::new(reader))?)
+		let pos = reader.stream_position()?;
+		Ok(read(binrw::io::BufReader::new(reader), pos, limits)?)
+	}
+}
+
+/// How floats are formatted when writing Mesh1.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FloatFormat1 {
+	/// The shortest decimal that parses back to the same value,
+	/// so reading the written mesh gives identical vertices.
+	#[default]
+	Shortest,
+	/// printf's `%g` with the given number of significant digits and a three digit exponent.
+	/// Roblox wrote meshes with 6 significant digits.  This is lossy.
+	General(u8),
+}
+impl FloatFormat1 {
+	fn write<W: std::io::Write>(self, writer: &mut W, value: f32) -> std::io::Result<()> {
+		match self {
+			Self::Shortest => write!(writer, "{value}"),
+			Self::General(digits) => write!(writer, "{}", format_general(value, digits)),
+		}
+	}
+}
+
+fn format_general(value: f32, digits: u8) -> String {
+	if !value.is_finite() {
+		return value.to_string();
+	}
+	let precision = digits.max(1) as usize - 1;
+	// round to the significant digits first, the exponent may change as a result
+	let scientific = format!("{value:.precision$e}");
+	let (mantissa, exponent) = scientific
+		.split_once('e')
+		.expect("LowerExp always contains an exponent");
+	let exponent: i32 = exponent.parse().expect("LowerExp exponent is an integer");
+	fn trim_zeros(s: &str) -> &str {
+		if s.contains('.') {
+			s.trim_end_matches('0').trim_end_matches('.')
+		} else {
+			s
+		}
+	}
+	if -4 <= exponent && exponent <= precision as i32 {
+		let decimals = (precision as i32 - exponent) as usize;
+		trim_zeros(&format!("{value:.decimals$}")).to_owned()
+	} else {
+		let sign = if exponent < 0 { '-' } else { '+' };
+		format!("{}e{sign}{:03}", trim_zeros(mantissa), exponent.abs())
 	}
 }
 
+/// Find the value which the reader's texture coordinate flip turns into tex.
+fn unflip_tex(tex: f32) -> f32 {
+	let flipped = 1.0 - tex;
+	// 1.0 - flipped may be off by a rounding error, search the neighbours
+	let mut best = flipped;
+	for candidate in [
+		flipped,
+		flipped.next_down(),
+		flipped.next_up(),
+		flipped.next_down().next_down(),
+		flipped.next_up().next_up(),
+	] {
+		let error = (1.0 - candidate - tex).abs();
+		if error == 0.0 {
+			return candidate;
+		}
+		if error < (1.0 - best - tex).abs() {
+			best = candidate;
+		}
+	}
+	best
+}
+
+impl binrw::BinWrite for Mesh1 {
+	type Args<'a> = FloatFormat1;
+	fn write_options<W: binrw::io::Write + binrw::io::Seek>(
+		&self,
+		writer: &mut W,
+		_endian: binrw::Endian,
+		args: Self::Args<'_>,
+	) -> binrw::BinResult<()> {
+		if !self.vertices.len().is_multiple_of(3) {
+			return Err(binrw::Error::Custom {
+				pos: writer.stream_position()?,
+				err: Box::new(Error1::VertexCount),
+			});
+		}
+		Ok(write(self, writer, args)?)
+	}
+}
+
+fn write<W: std::io::Write>(
+	mesh: &Mesh1,
+	writer: &mut W,
+	format: FloatFormat1,
+) -> std::io::Result<()> {
+	let pos_scale = match mesh.revision {
+		Revision1::Version100 => 2.0,
+		Revision1::Version101 => 1.0,
+	};
+
+	// lines are terminated like the files Roblox wrote, with no trailing newline
+	write!(
+		writer,
+		"{}\r\n{}\r\n",
+		mesh.revision.as_str(),
+		mesh.vertices.len() / 3
+	)?;
+
+	for vertex in &mesh.vertices {
+		let pos = vertex.pos.map(|p| p * pos_scale);
+		let tex = [vertex.tex[0], unflip_tex(vertex.tex[1]), vertex.tex[2]];
+		for triple in [pos, vertex.norm, tex] {
+			writer.write_all(b"[")?;
+			for (i, value) in triple.into_iter().enumerate() {
+				if i != 0 {
+					writer.write_all(b",")?;
+				}
+				format.write(writer, value)?;
+			}
+			writer.write_all(b"]")?;
+		}
+	}
+	Ok(())
+}
+
 fn parse_triple_float(x: &str, y: &str, z: &str) -> Result<[f32; 3], std::num::ParseFloatError> {
 	Ok([x.trim().parse()?, y.trim().parse()?, z.trim().parse()?])
 }
@@ -107,19 +264,52 @@ macro_rules! lazy_regex {
 	}};
 }
 
-fn read<R: BufRead>(reader: R) -> Result<Mesh1, InnerError> {
-	let mut lines = LineMachine::new(reader);
+/// Returns the revision, and the face count with the offset of its line.
+fn read_header<R: BufRead>(
+	lines: &mut LineMachine<R>,
+) -> Result<(Revision1, u64, u32), InnerError> {
+	let error = |pos: u64, error: Error1| InnerError::Other { pos, error };
 
 	// the first line contains the version number
-	let revision = lines.read_line()?.trim().parse()?;
+	let (pos, line) = lines.read_line()?;
+	let revision = line.trim().parse().map_err(|e| error(pos, e))?;
 
-	let face_count: u32 = lines
-		.read_line()?
+	let (pos, line) = lines.read_line()?;
+	let face_count: u32 = line
 		.trim()
 		.parse()
-		.map_err(Error1::ParseIntError)?;
+		.map_err(|e| error(pos, Error1::ParseIntError(e)))?;
+	Ok((revision, pos, face_count))
+}
+
+/// Read only the revision and face count lines.
+pub(super) fn read_header1<R: binrw::BinReaderExt>(
+	reader: &mut R,
+) -> binrw::BinResult<(Revision1, u32)> {
+	// much longer than the two lines, but the vertices line is not read into memory
+	const HEADER_BUDGET: u64 = 64;
+	let pos = reader.stream_position()?;
+	let mut lines = LineMachine::new(binrw::io::BufReader::new(reader), pos, HEADER_BUDGET);
+	let (revision, _, face_count) = read_header(&mut lines)?;
+	Ok((revision, face_count))
+}
+
+fn read<R: BufRead>(reader: R, pos: u64, limits: ReadLimits) -> Result<Mesh1, InnerError> {
+	let mut lines = LineMachine::new(reader, pos, limits.max_alloc_bytes);
+	let error = |pos: u64, error: Error1| InnerError::Other { pos, error };
+
+	let (revision, pos, face_count) = read_header(&mut lines)?;
+	let vertex_count = 3 * face_count as u64;
+	let limits = limits
+		.charge()
+		.faces(face_count)
+		.vertices(vertex_count)
+		.alloc::<Vertex1>(vertex_count)
+		.finish()
+		.map_err(|error| InnerError::Limit { pos, error })?;
+	lines.budget = lines.budget.min(limits.max_alloc_bytes);
 
-	let vertices_line = lines.read_line()?;
+	let (pos, vertices_line) = lines.read_line()?;
 
 	//match three at a time, otherwise fail
 	let vertex_pattern =
@@ -128,19 +318,23 @@ fn read<R: BufRead>(reader: R) -> Result<Mesh1, InnerError> {
 	let vertices = vertex_pattern
 		.captures_iter(vertices_line.as_str())
 		.map(|c| {
+			let vertex_pos = pos + c.get_match().start() as u64;
 			let (_, [px, py, pz, nx, ny, nz, tx, ty, tz]) = c.extract();
+			let parse = |x, y, z| {
+				parse_triple_float(x, y, z)
+					.map_err(|e| error(vertex_pos, Error1::ParseFloatError(e)))
+			};
 			Ok(Vertex1 {
-				pos: parse_triple_float(px, py, pz)?,
-				norm: parse_triple_float(nx, ny, nz)?,
-				tex: parse_triple_float(tx, ty, tz)?,
+				pos: parse(px, py, pz)?,
+				norm: parse(nx, ny, nz)?,
+				tex: parse(tx, ty, tz)?,
 			})
 		})
-		.collect::<Result<Vec<Vertex1>, _>>()
-		.map_err(Error1::ParseFloatError)?;
+		.collect::<Result<Vec<Vertex1>, InnerError>>()?;
 
 	// assert vertex count matches header
 	if 3 * (face_count as usize) != vertices.len() {
-		return Err(Error1::VertexCount.into());
+		return Err(error(pos, Error1::VertexCount));
 	}
 
 	let mut mesh = Mesh1 { revision, vertices };
@@ -1,3 +1,5 @@
+use crate::limits::ReadLimits;
+
 #[binrw::binrw]
 #[brw(little)]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -74,6 +76,7 @@ pub struct Face2(pub [VertexId2; 3]);
 
 #[binrw::binrw]
 #[brw(little)]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub struct Mesh2 {
 	pub revision: Revision2,
@@ -94,6 +97,15 @@ pub struct Mesh2 {
 	#[br(temp)]
 	#[bw(try_calc=faces.len().try_into())]
 	pub face_count: u32,
+	#[br(temp, try_calc = limits
+		.charge()
+		.vertices(vertex_count)
+		.faces(face_count)
+		.alloc::<Vertex2>(vertex_count)
+		.alloc::<Face2>(face_count)
+		.finish())]
+	#[bw(ignore)]
+	_limits: ReadLimits,
 	#[br(args(&sizeof_vertex, vertex_count))]
 	pub vertices: Vertices2,
 	#[br(count=face_count)]
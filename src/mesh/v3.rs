@@ -1,4 +1,5 @@
-use super::v2::{Face2, SizeOfVertex2, Vertices2};
+use super::v2::{Face2, SizeOfVertex2, Vertex2, Vertices2};
+use crate::limits::ReadLimits;
 
 #[binrw::binrw]
 #[brw(little)]
@@ -20,6 +21,7 @@ pub struct Lod3(pub u32);
 
 #[binrw::binrw]
 #[brw(little)]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub struct Mesh3 {
 	pub revision: Revision3,
@@ -47,6 +49,16 @@ pub struct Mesh3 {
 	#[br(temp)]
 	#[bw(try_calc=faces.len().try_into())]
 	pub face_count: u32,
+	#[br(temp, try_calc = limits
+		.charge()
+		.vertices(vertex_count)
+		.faces(face_count)
+		.alloc::<Vertex2>(vertex_count)
+		.alloc::<Face2>(face_count)
+		.alloc::<Lod3>(lod_count)
+		.finish())]
+	#[bw(ignore)]
+	_limits: ReadLimits,
 	#[br(args(&sizeof_vertex, vertex_count))]
 	pub vertices: Vertices2,
 	#[br(count=face_count)]
@@ -1,5 +1,6 @@
 use super::v2::{Face2, Vertex2};
 use super::v3::Lod3;
+use crate::limits::ReadLimits;
 
 #[binrw::binrw]
 #[brw(little)]
@@ -48,7 +49,8 @@ impl BoneId4 {
 
 #[binrw::binrw]
 #[brw(little)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+/// A rotation matrix stored row by row, followed by a position.
 pub struct CFrame4 {
 	pub r00: f32,
 	pub r01: f32,
@@ -63,6 +65,105 @@ pub struct CFrame4 {
 	pub y: f32,
 	pub z: f32,
 }
+impl CFrame4 {
+	pub const IDENTITY: Self = Self::from_position([0.0; 3]);
+	pub const fn from_position([x, y, z]: [f32; 3]) -> Self {
+		Self {
+			r00: 1.0,
+			r01: 0.0,
+			r02: 0.0,
+			r10: 0.0,
+			r11: 1.0,
+			r12: 0.0,
+			r20: 0.0,
+			r21: 0.0,
+			r22: 1.0,
+			x,
+			y,
+			z,
+		}
+	}
+	/// Same as Roblox's CFrame.fromEulerAnglesXYZ, angles are in radians.
+	pub fn from_euler_angles_xyz(rx: f32, ry: f32, rz: f32) -> Self {
+		let (sx, cx) = rx.sin_cos();
+		let (sy, cy) = ry.sin_cos();
+		let (sz, cz) = rz.sin_cos();
+		Self {
+			r00: cy * cz,
+			r01: -cy * sz,
+			r02: sy,
+			r10: cx * sz + sx * sy * cz,
+			r11: cx * cz - sx * sy * sz,
+			r12: -sx * cy,
+			r20: sx * sz - cx * sy * cz,
+			r21: sx * cz + cx * sy * sz,
+			r22: cx * cy,
+			x: 0.0,
+			y: 0.0,
+			z: 0.0,
+		}
+	}
+	pub const fn position(&self) -> [f32; 3] {
+		[self.x, self.y, self.z]
+	}
+	/// Rotate a vector without translating it.
+	pub fn vector_to_world_space(&self, [x, y, z]: [f32; 3]) -> [f32; 3] {
+		[
+			self.r00 * x + self.r01 * y + self.r02 * z,
+			self.r10 * x + self.r11 * y + self.r12 * z,
+			self.r20 * x + self.r21 * y + self.r22 * z,
+		]
+	}
+	pub fn point_to_world_space(&self, point: [f32; 3]) -> [f32; 3] {
+		let [x, y, z] = self.vector_to_world_space(point);
+		[x + self.x, y + self.y, z + self.z]
+	}
+	/// The rotation is assumed to be orthonormal, like Roblox's CFrame:Inverse.
+	pub fn inverse(&self) -> Self {
+		let mut inverse = Self {
+			r00: self.r00,
+			r01: self.r10,
+			r02: self.r20,
+			r10: self.r01,
+			r11: self.r11,
+			r12: self.r21,
+			r20: self.r02,
+			r21: self.r12,
+			r22: self.r22,
+			x: 0.0,
+			y: 0.0,
+			z: 0.0,
+		};
+		let [x, y, z] = inverse.vector_to_world_space(self.position());
+		(inverse.x, inverse.y, inverse.z) = (-x, -y, -z);
+		inverse
+	}
+}
+/// Compose two CFrames, the right hand side is relative to the left hand side.
+impl core::ops::Mul for &CFrame4 {
+	type Output = CFrame4;
+	fn mul(self, rhs: Self) -> CFrame4 {
+		let column = |x, y, z| self.vector_to_world_space([x, y, z]);
+		let [r00, r10, r20] = column(rhs.r00, rhs.r10, rhs.r20);
+		let [r01, r11, r21] = column(rhs.r01, rhs.r11, rhs.r21);
+		let [r02, r12, r22] = column(rhs.r02, rhs.r12, rhs.r22);
+		let [x, y, z] = self.point_to_world_space(rhs.position());
+		CFrame4 {
+			r00,
+			r01,
+			r02,
+			r10,
+			r11,
+			r12,
+			r20,
+			r21,
+			r22,
+			x,
+			y,
+			z,
+		}
+	}
+}
 
 #[binrw::binrw]
 #[brw(little)]
@@ -90,6 +191,7 @@ pub struct Subset4 {
 #[binrw::binrw]
 #[brw(little)]
 #[derive(Debug, Clone)]
+#[br(import(limits: ReadLimits))]
 /// envelopes has the same length as vertices when header.bone_count!=0
 pub struct Mesh4 {
 	pub revision: Revision4,
@@ -118,6 +220,21 @@ pub struct Mesh4 {
 	pub subset_count: u16,
 	pub lod_hq_count: u8,
 	pub _padding: u8,
+	#[br(temp, try_calc = limits
+		.charge()
+		.vertices(vertex_count)
+		.faces(face_count)
+		.bones(bone_count)
+		.alloc::<Vertex2>(vertex_count)
+		.alloc::<Envelope4>(if bone_count == 0 { 0 } else { vertex_count })
+		.alloc::<Face2>(face_count)
+		.alloc::<Lod3>(lod_count)
+		.alloc::<Bone4>(bone_count)
+		.alloc::<u8>(bone_names_len)
+		.alloc::<Subset4>(subset_count)
+		.finish())]
+	#[bw(ignore)]
+	_limits: ReadLimits,
 	#[br(count=vertex_count)]
 	pub vertices: Vec<Vertex2>,
 	#[br(count=if bone_count==0{0}else{vertex_count})]
@@ -1,6 +1,7 @@
 use super::v2::{Face2, Vertex2};
 use super::v3::Lod3;
 use super::v4::{Bone4, Envelope4, LodType4, Subset4};
+use crate::limits::ReadLimits;
 
 #[binrw::binrw]
 #[brw(little)]
@@ -26,7 +27,7 @@ pub enum QuantizedMatrix5 {
 	Raw {
 		x: u32,
 		y: u32,
-		#[br(count=x*y)]
+		#[br(count=x as u64*y as u64)]
 		matrix: Vec<f32>,
 	},
 	#[brw(magic = 2u16)]
@@ -35,7 +36,7 @@ pub enum QuantizedMatrix5 {
 		y: u32,
 		lerp0: f32,
 		lerp1: f32,
-		#[br(count=x*y)]
+		#[br(count=x as u64*y as u64)]
 		matrix: Vec<u16>,
 	},
 }
@@ -50,7 +51,7 @@ impl QuantizedMatrix5 {
 				// x, y
 				2 * size_of::<u32>()
 				// matrix
-				+ (x * y) as usize * size_of::<f32>()
+				+ *x as usize * *y as usize * size_of::<f32>()
 			}
 			QuantizedMatrix5::Quantized { x, y, .. } => {
 				// x, y
@@ -58,7 +59,7 @@ impl QuantizedMatrix5 {
 				// lerp0, lerp1
 				+ 2 * size_of::<f32>()
 				// matrix
-				+ (x * y) as usize * size_of::<u16>()
+				+ *x as usize * *y as usize * size_of::<u16>()
 			}
 		}
 	}
@@ -67,6 +68,9 @@ impl QuantizedMatrix5 {
 #[binrw::binrw]
 #[brw(little)]
 #[derive(Debug, Clone)]
+/// Each matrix has x face bones and y poses, stored as matrix[face_bone * y + pose].
+/// Positions are in studs and rotations are XYZ euler angles in degrees.
+/// See Facs5::decode
 pub struct QuantizedTransforms5 {
 	pub px: QuantizedMatrix5,
 	pub py: QuantizedMatrix5,
@@ -87,6 +91,25 @@ impl QuantizedTransforms5 {
 	}
 }
 
+/// No FACS data
+impl Default for QuantizedTransforms5 {
+	fn default() -> Self {
+		let empty = || QuantizedMatrix5::Raw {
+			x: 0,
+			y: 0,
+			matrix: Vec::new(),
+		};
+		Self {
+			px: empty(),
+			py: empty(),
+			pz: empty(),
+			rx: empty(),
+			ry: empty(),
+			rz: empty(),
+		}
+	}
+}
+
 #[binrw::binrw]
 #[brw(little)]
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
@@ -95,16 +118,19 @@ pub struct ControlId5(pub u16);
 #[binrw::binrw]
 #[brw(little)]
 #[derive(Debug, Clone)]
+/// A pose which is applied on top of its controls, weighted by the product of their weights
 pub struct TwoPoseCorrective5(pub [ControlId5; 2]);
 
 #[binrw::binrw]
 #[brw(little)]
 #[derive(Debug, Clone)]
+/// A pose which is applied on top of its controls, weighted by the product of their weights
 pub struct ThreePoseCorrective5(pub [ControlId5; 3]);
 
 #[binrw::binrw]
 #[brw(little)]
-#[derive(Debug, Clone)]
+#[br(import(limits: ReadLimits))]
+#[derive(Debug, Clone, Default)]
 pub struct Facs5 {
 	#[br(temp)]
 	#[bw(try_calc=face_bone_names.len().try_into())]
@@ -121,6 +147,16 @@ pub struct Facs5 {
 	#[br(temp)]
 	#[bw(try_calc=(three_pose_correctives.len()*size_of::<ThreePoseCorrective5>()).try_into())]
 	pub three_pose_correctives_len: u32,
+	#[br(temp, try_calc = limits
+		.charge()
+		.alloc::<u8>(face_bone_names_len)
+		.alloc::<u8>(face_control_names_len)
+		.bytes(quantized_transforms_len)
+		.bytes(two_pose_correctives_len)
+		.bytes(three_pose_correctives_len)
+		.finish())]
+	#[bw(ignore)]
+	_limits: ReadLimits,
 	#[br(count=face_bone_names_len)]
 	pub face_bone_names: Vec<u8>,
 	#[br(count=face_control_names_len)]
@@ -132,10 +168,27 @@ pub struct Facs5 {
 	#[br(count=three_pose_correctives_len as usize/size_of::<ThreePoseCorrective5>())]
 	pub three_pose_correctives: Vec<ThreePoseCorrective5>,
 }
+impl Facs5 {
+	/// The serialization length of this struct in bytes.
+	pub(crate) fn encoded_len(&self) -> usize {
+		// face_bone_names_len, face_control_names_len
+		2 * size_of::<u32>()
+		// quantized_transforms_len
+		+ size_of::<u64>()
+		// two_pose_correctives_len, three_pose_correctives_len
+		+ 2 * size_of::<u32>()
+		+ self.face_bone_names.len()
+		+ self.face_control_names.len()
+		+ self.quantized_transforms.len()
+		+ self.two_pose_correctives.len() * size_of::<TwoPoseCorrective5>()
+		+ self.three_pose_correctives.len() * size_of::<ThreePoseCorrective5>()
+	}
+}
 
 #[binrw::binrw]
 #[brw(little)]
 #[derive(Debug, Clone)]
+#[br(import(limits: ReadLimits))]
 /// envelopes has the same length as vertices when header.bone_count!=0
 pub struct Mesh5 {
 	pub revision: Revision5,
@@ -169,6 +222,21 @@ pub struct Mesh5 {
 	_padding: (),
 	pub facs_format: FacsFormat5,
 	pub sizeof_facs: u32,
+	#[br(temp, try_calc = limits
+		.charge()
+		.vertices(vertex_count)
+		.faces(face_count)
+		.bones(bone_count)
+		.alloc::<Vertex2>(vertex_count)
+		.alloc::<Envelope4>(if bone_count == 0 { 0 } else { vertex_count })
+		.alloc::<Face2>(face_count)
+		.alloc::<Lod3>(lod_count)
+		.alloc::<Bone4>(bone_count)
+		.alloc::<u8>(bone_names_len)
+		.alloc::<Subset4>(subset_count)
+		.finish())]
+	#[bw(ignore)]
+	limits: ReadLimits,
 	#[br(count=vertex_count)]
 	pub vertices: Vec<Vertex2>,
 	#[br(count=if bone_count==0{0}else{vertex_count})]
@@ -183,5 +251,6 @@ pub struct Mesh5 {
 	pub bone_names: Vec<u8>,
 	#[br(count=subset_count)]
 	pub subsets: Vec<Subset4>,
+	#[br(args(limits))]
 	pub facs: Facs5,
 }
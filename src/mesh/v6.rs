@@ -0,0 +1,326 @@
+use binrw::{BinRead, BinReaderExt, BinWrite, BinWriterExt};
+
+use super::v2::{Face2, Vertex2};
+use super::v3::Lod3;
+use super::v4::{Bone4, Envelope4, LodType4, Subset4};
+use super::v5::Facs5;
+use crate::limits::ReadLimits;
+
+#[binrw::binrw]
+#[brw(little)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Revision6 {
+	#[brw(magic = b"version 6.00\n")]
+	Version600,
+}
+
+#[derive(Debug)]
+pub struct ChunkSizeError {
+	pub expected: u32,
+	pub actual: u64,
+}
+impl std::fmt::Display for ChunkSizeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+impl core::error::Error for ChunkSizeError {}
+
+/// Read a u32 size followed by a chunk body, asserting that the body
+/// occupies exactly the number of bytes given by the size.
+pub(crate) fn read_sized<T, R>(
+	reader: &mut R,
+	endian: binrw::Endian,
+	args: (ReadLimits,),
+) -> binrw::BinResult<T>
+where
+	T: for<'a> BinRead<Args<'a> = (ReadLimits,)>,
+	R: BinReaderExt,
+{
+	let size: u32 = reader.read_type(endian)?;
+	let pos = reader.stream_position()?;
+	let value = T::read_options(reader, endian, args)?;
+	let actual = reader.stream_position()? - pos;
+	if actual != size as u64 {
+		return Err(binrw::Error::Custom {
+			pos,
+			err: Box::new(ChunkSizeError {
+				expected: size,
+				actual,
+			}),
+		});
+	}
+	Ok(value)
+}
+
+/// Write a u32 size followed by the chunk body.
+pub(crate) fn write_sized<T, W>(
+	value: &T,
+	writer: &mut W,
+	endian: binrw::Endian,
+	args: (),
+) -> binrw::BinResult<()>
+where
+	T: for<'a> BinWrite<Args<'a> = ()>,
+	W: BinWriterExt,
+{
+	// the size precedes the body, so the body must be serialized first
+	let mut body = std::io::Cursor::new(Vec::new());
+	value.write_options(&mut body, endian, args)?;
+	let body = body.into_inner();
+	let pos = writer.stream_position()?;
+	let size: u32 = body.len().try_into().map_err(|e| binrw::Error::Custom {
+		pos,
+		err: Box::new(e),
+	})?;
+	writer.write_type(&size, endian)?;
+	writer.write_all(&body)?;
+	Ok(())
+}
+
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
+#[derive(Debug, Clone)]
+pub struct CoreMesh6 {
+	#[br(temp)]
+	#[bw(try_calc=vertices.len().try_into())]
+	pub vertex_count: u32,
+	#[br(temp, try_calc = limits
+		.charge()
+		.vertices(vertex_count)
+		.alloc::<Vertex2>(vertex_count)
+		.finish())]
+	#[bw(ignore)]
+	vertex_limits: ReadLimits,
+	#[br(count=vertex_count)]
+	pub vertices: Vec<Vertex2>,
+	#[br(temp)]
+	#[bw(try_calc=faces.len().try_into())]
+	pub face_count: u32,
+	#[br(temp, try_calc = vertex_limits
+		.charge()
+		.faces(face_count)
+		.alloc::<Face2>(face_count)
+		.finish())]
+	#[bw(ignore)]
+	_face_limits: ReadLimits,
+	#[br(count=face_count)]
+	pub faces: Vec<Face2>,
+}
+
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
+#[derive(Debug, Clone)]
+pub struct Lods6 {
+	pub lod_type: LodType4,
+	pub lod_hq_count: u8,
+	#[br(temp)]
+	#[bw(try_calc=lods.len().try_into())]
+	pub lod_count: u32,
+	#[br(temp, try_calc = limits.charge().alloc::<Lod3>(lod_count).finish())]
+	#[bw(ignore)]
+	_limits: ReadLimits,
+	#[br(count=lod_count)]
+	pub lods: Vec<Lod3>,
+}
+
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
+#[derive(Debug, Clone)]
+/// Unlike Mesh4 and Mesh5, the envelope count is stored explicitly.
+pub struct Skinning6 {
+	#[br(temp)]
+	#[bw(try_calc=envelopes.len().try_into())]
+	pub envelope_count: u32,
+	#[br(temp, try_calc = limits
+		.charge()
+		.vertices(envelope_count)
+		.alloc::<Envelope4>(envelope_count)
+		.finish())]
+	#[bw(ignore)]
+	envelope_limits: ReadLimits,
+	#[br(count=envelope_count)]
+	pub envelopes: Vec<Envelope4>,
+	#[br(temp)]
+	#[bw(try_calc=bones.len().try_into())]
+	pub bone_count: u32,
+	#[br(temp, try_calc = envelope_limits
+		.charge()
+		.bones(bone_count)
+		.alloc::<Bone4>(bone_count)
+		.finish())]
+	#[bw(ignore)]
+	bone_limits: ReadLimits,
+	#[br(count=bone_count)]
+	pub bones: Vec<Bone4>,
+	#[br(temp)]
+	#[bw(try_calc=bone_names.len().try_into())]
+	pub bone_names_len: u32,
+	#[br(temp, try_calc = bone_limits.charge().alloc::<u8>(bone_names_len).finish())]
+	#[bw(ignore)]
+	bone_name_limits: ReadLimits,
+	#[br(count=bone_names_len)]
+	pub bone_names: Vec<u8>,
+	#[br(temp)]
+	#[bw(try_calc=subsets.len().try_into())]
+	pub subset_count: u32,
+	#[br(temp, try_calc = bone_name_limits.charge().alloc::<Subset4>(subset_count).finish())]
+	#[bw(ignore)]
+	_subset_limits: ReadLimits,
+	#[br(count=subset_count)]
+	pub subsets: Vec<Subset4>,
+}
+
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
+#[derive(Debug, Clone)]
+pub struct Facs6 {
+	#[br(temp)]
+	#[bw(try_calc=facs.encoded_len().try_into())]
+	pub sizeof_facs: u32,
+	#[br(args(limits))]
+	pub facs: Facs5,
+}
+
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
+#[derive(Debug, Clone)]
+/// Hidden surface removal visibility.  One bit per face, packed lsb-first.
+pub struct HsrAvis6 {
+	pub flag_count: u32,
+	#[br(temp, try_calc = limits
+		.charge()
+		.faces(flag_count)
+		.alloc::<u8>(flag_count.div_ceil(u8::BITS))
+		.finish())]
+	#[bw(ignore)]
+	_limits: ReadLimits,
+	#[br(count=flag_count.div_ceil(u8::BITS))]
+	pub flags: Vec<u8>,
+}
+impl HsrAvis6 {
+	pub fn get(&self, index: u32) -> Option<bool> {
+		if self.flag_count <= index {
+			return None;
+		}
+		let byte = self.flags.get((index / u8::BITS) as usize)?;
+		Some(byte & (1 << (index % u8::BITS)) != 0)
+	}
+}
+
+pub(crate) const KNOWN_CHUNK_TYPES: [[u8; 8]; 5] = [
+	*b"COREMESH",
+	*b"LODS\0\0\0\0",
+	*b"SKINNING",
+	*b"FACS\0\0\0\0",
+	*b"HSRAVIS\0",
+];
+
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
+#[derive(Debug, Clone)]
+/// A chunk which rbx_mesh does not know how to decode.
+/// Known chunk types with an unexpected version are an error.
+pub struct UnknownChunk6 {
+	#[br(assert(!KNOWN_CHUNK_TYPES.contains(&chunk_type)))]
+	pub chunk_type: [u8; 8],
+	pub version: u32,
+	#[br(temp)]
+	#[bw(try_calc=data.len().try_into())]
+	pub size: u32,
+	#[br(temp, try_calc = limits.charge().alloc::<u8>(size).finish())]
+	#[bw(ignore)]
+	_limits: ReadLimits,
+	#[br(count=size)]
+	pub data: Vec<u8>,
+}
+
+// concat_bytes!(chunk_type, version)
+#[expect(clippy::large_enum_variant)]
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
+#[derive(Debug, Clone)]
+pub enum Chunk6 {
+	#[brw(magic = b"COREMESH\x01\0\0\0")]
+	CoreMesh(
+		#[br(parse_with = read_sized, args(limits))]
+		#[bw(write_with = write_sized)]
+		CoreMesh6,
+	),
+	#[brw(magic = b"LODS\0\0\0\0\x01\0\0\0")]
+	Lods(
+		#[br(parse_with = read_sized, args(limits))]
+		#[bw(write_with = write_sized)]
+		Lods6,
+	),
+	#[brw(magic = b"SKINNING\x01\0\0\0")]
+	Skinning(
+		#[br(parse_with = read_sized, args(limits))]
+		#[bw(write_with = write_sized)]
+		Skinning6,
+	),
+	#[brw(magic = b"FACS\0\0\0\0\x01\0\0\0")]
+	Facs(
+		#[br(parse_with = read_sized, args(limits))]
+		#[bw(write_with = write_sized)]
+		Facs6,
+	),
+	#[brw(magic = b"HSRAVIS\0\x01\0\0\0")]
+	HsrAvis(
+		#[br(parse_with = read_sized, args(limits))]
+		#[bw(write_with = write_sized)]
+		HsrAvis6,
+	),
+	Unknown(#[br(args(limits))] UnknownChunk6),
+}
+
+#[binrw::binrw]
+#[brw(little)]
+#[derive(Debug, Clone)]
+/// Mesh v6 splits the Mesh5 header and sections into a list of chunks.
+/// The chunks are kept in file order so that they round trip exactly.
+#[br(import(limits: ReadLimits))]
+pub struct Mesh6 {
+	pub revision: Revision6,
+	#[br(parse_with=binrw::helpers::until_eof, args(limits))]
+	pub chunks: Vec<Chunk6>,
+}
+impl Mesh6 {
+	pub fn core_mesh(&self) -> Option<&CoreMesh6> {
+		self.chunks.iter().find_map(|chunk| match chunk {
+			Chunk6::CoreMesh(core_mesh) => Some(core_mesh),
+			_ => None,
+		})
+	}
+	pub fn lods(&self) -> Option<&Lods6> {
+		self.chunks.iter().find_map(|chunk| match chunk {
+			Chunk6::Lods(lods) => Some(lods),
+			_ => None,
+		})
+	}
+	pub fn skinning(&self) -> Option<&Skinning6> {
+		self.chunks.iter().find_map(|chunk| match chunk {
+			Chunk6::Skinning(skinning) => Some(skinning),
+			_ => None,
+		})
+	}
+	pub fn facs(&self) -> Option<&Facs5> {
+		self.chunks.iter().find_map(|chunk| match chunk {
+			Chunk6::Facs(facs) => Some(&facs.facs),
+			_ => None,
+		})
+	}
+	pub fn hsr_avis(&self) -> Option<&HsrAvis6> {
+		self.chunks.iter().find_map(|chunk| match chunk {
+			Chunk6::HsrAvis(hsr_avis) => Some(hsr_avis),
+			_ => None,
+		})
+	}
+}
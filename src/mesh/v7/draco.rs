@@ -0,0 +1,959 @@
+// Decoder for the subset of the draco bitstream that Roblox emits:
+// a triangular mesh using sequential connectivity and sequential attributes.
+// The encoder emits the simplest form of the same subset.
+// based on https://github.com/google/draco/tree/main/src/draco/compression
+
+use super::rans::decode_symbols;
+use crate::limits::{LimitError, ReadLimits};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DracoError {
+	UnexpectedEof,
+	InvalidMagic,
+	UnsupportedVersion(u8, u8),
+	UnsupportedEncoderType(u8),
+	UnsupportedEncoderMethod(u8),
+	UnsupportedMetadata,
+	InvalidConnectivity,
+	InvalidAttribute,
+	UnsupportedAttributeDecoder(u8),
+	UnsupportedPredictionTransform(i8),
+	InvalidSymbols,
+	InvalidQuantization,
+	/// Bytes left over after the attributes, the number of which is given.
+	TrailingData(usize),
+	Limit(LimitError),
+}
+impl core::fmt::Display for DracoError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+impl core::error::Error for DracoError {}
+
+pub struct DecoderBuffer<'a> {
+	data: &'a [u8],
+	version: (u8, u8),
+}
+impl<'a> DecoderBuffer<'a> {
+	fn new(data: &'a [u8]) -> Self {
+		Self {
+			data,
+			version: (0, 0),
+		}
+	}
+	pub const fn version(&self) -> (u8, u8) {
+		self.version
+	}
+	pub const fn remaining(&self) -> usize {
+		self.data.len()
+	}
+	pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], DracoError> {
+		let (bytes, rest) = self
+			.data
+			.split_at_checked(len)
+			.ok_or(DracoError::UnexpectedEof)?;
+		self.data = rest;
+		Ok(bytes)
+	}
+	fn read_array<const N: usize>(&mut self) -> Result<[u8; N], DracoError> {
+		let (bytes, rest) = self
+			.data
+			.split_first_chunk()
+			.ok_or(DracoError::UnexpectedEof)?;
+		self.data = rest;
+		Ok(*bytes)
+	}
+	pub fn read_u8(&mut self) -> Result<u8, DracoError> {
+		Ok(u8::from_le_bytes(self.read_array()?))
+	}
+	pub fn read_i8(&mut self) -> Result<i8, DracoError> {
+		Ok(i8::from_le_bytes(self.read_array()?))
+	}
+	pub fn read_u16(&mut self) -> Result<u16, DracoError> {
+		Ok(u16::from_le_bytes(self.read_array()?))
+	}
+	pub fn read_u32(&mut self) -> Result<u32, DracoError> {
+		Ok(u32::from_le_bytes(self.read_array()?))
+	}
+	pub fn read_i32(&mut self) -> Result<i32, DracoError> {
+		Ok(i32::from_le_bytes(self.read_array()?))
+	}
+	pub fn read_u64(&mut self) -> Result<u64, DracoError> {
+		Ok(u64::from_le_bytes(self.read_array()?))
+	}
+	pub fn read_f32(&mut self) -> Result<f32, DracoError> {
+		Ok(f32::from_le_bytes(self.read_array()?))
+	}
+	/// Little endian base 128 variable length integer.
+	pub fn read_varint_u64(&mut self) -> Result<u64, DracoError> {
+		let mut value = 0u64;
+		for shift in (0..u64::BITS).step_by(7) {
+			let byte = self.read_u8()?;
+			value |= ((byte & 0x7F) as u64) << shift;
+			if byte & 0x80 == 0 {
+				return Ok(value);
+			}
+		}
+		Err(DracoError::InvalidSymbols)
+	}
+	pub fn read_varint_u32(&mut self) -> Result<u32, DracoError> {
+		self.read_varint_u64()?
+			.try_into()
+			.map_err(|_| DracoError::InvalidSymbols)
+	}
+	/// Begin reading lsb-first bits from the remaining data.
+	/// The buffer is not advanced until the bit decoder reports how many bytes it used.
+	pub fn start_bit_decoding(&self) -> BitDecoder<'a> {
+		BitDecoder {
+			data: self.data,
+			bit_offset: 0,
+		}
+	}
+}
+
+pub struct BitDecoder<'a> {
+	data: &'a [u8],
+	bit_offset: usize,
+}
+impl BitDecoder<'_> {
+	fn read_bit(&mut self) -> Result<u32, DracoError> {
+		let byte_offset = self.bit_offset / 8;
+		let bit_shift = self.bit_offset % 8;
+		// draco reads zeros past the end of the buffer, which would let a
+		// truncated stream produce any number of values
+		let &byte = self
+			.data
+			.get(byte_offset)
+			.ok_or(DracoError::UnexpectedEof)?;
+		self.bit_offset += 1;
+		Ok(((byte >> bit_shift) & 1) as u32)
+	}
+	pub fn read_bits(&mut self, bits: u32) -> Result<u32, DracoError> {
+		let mut value = 0;
+		for bit in 0..bits {
+			value |= self.read_bit()? << bit;
+		}
+		Ok(value)
+	}
+	pub fn bytes_decoded(&self) -> usize {
+		self.bit_offset.div_ceil(8)
+	}
+}
+
+const ENCODER_TYPE_TRIANGULAR_MESH: u8 = 1;
+const ENCODER_METHOD_MESH_SEQUENTIAL: u8 = 0;
+const METADATA_FLAG_MASK: u16 = 0x8000;
+
+const CONNECTIVITY_COMPRESSED_INDICES: u8 = 0;
+const CONNECTIVITY_UNCOMPRESSED_INDICES: u8 = 1;
+
+const PREDICTION_NONE: i8 = -2;
+const PREDICTION_TRANSFORM_WRAP: i8 = 1;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AttributeType {
+	Position,
+	Normal,
+	Color,
+	TexCoord,
+	Generic,
+}
+impl From<AttributeType> for u8 {
+	fn from(value: AttributeType) -> Self {
+		match value {
+			AttributeType::Position => 0,
+			AttributeType::Normal => 1,
+			AttributeType::Color => 2,
+			AttributeType::TexCoord => 3,
+			AttributeType::Generic => 4,
+		}
+	}
+}
+impl TryFrom<u8> for AttributeType {
+	type Error = DracoError;
+	fn try_from(value: u8) -> Result<Self, Self::Error> {
+		Ok(match value {
+			0 => Self::Position,
+			1 => Self::Normal,
+			2 => Self::Color,
+			3 => Self::TexCoord,
+			4 => Self::Generic,
+			_ => return Err(DracoError::InvalidAttribute),
+		})
+	}
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DataType {
+	Int8,
+	UInt8,
+	Int16,
+	UInt16,
+	Int32,
+	UInt32,
+	Int64,
+	UInt64,
+	Float32,
+	Float64,
+	Bool,
+}
+impl DataType {
+	pub const fn size(self) -> usize {
+		match self {
+			DataType::Int8 | DataType::UInt8 | DataType::Bool => 1,
+			DataType::Int16 | DataType::UInt16 => 2,
+			DataType::Int32 | DataType::UInt32 | DataType::Float32 => 4,
+			DataType::Int64 | DataType::UInt64 | DataType::Float64 => 8,
+		}
+	}
+}
+impl From<DataType> for u8 {
+	fn from(value: DataType) -> Self {
+		match value {
+			DataType::Int8 => 1,
+			DataType::UInt8 => 2,
+			DataType::Int16 => 3,
+			DataType::UInt16 => 4,
+			DataType::Int32 => 5,
+			DataType::UInt32 => 6,
+			DataType::Int64 => 7,
+			DataType::UInt64 => 8,
+			DataType::Float32 => 9,
+			DataType::Float64 => 10,
+			DataType::Bool => 11,
+		}
+	}
+}
+impl TryFrom<u8> for DataType {
+	type Error = DracoError;
+	fn try_from(value: u8) -> Result<Self, Self::Error> {
+		Ok(match value {
+			1 => Self::Int8,
+			2 => Self::UInt8,
+			3 => Self::Int16,
+			4 => Self::UInt16,
+			5 => Self::Int32,
+			6 => Self::UInt32,
+			7 => Self::Int64,
+			8 => Self::UInt64,
+			9 => Self::Float32,
+			10 => Self::Float64,
+			11 => Self::Bool,
+			_ => return Err(DracoError::InvalidAttribute),
+		})
+	}
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum AttributeDecoderType {
+	Generic,
+	Integer,
+	Quantization,
+}
+impl From<AttributeDecoderType> for u8 {
+	fn from(value: AttributeDecoderType) -> Self {
+		match value {
+			AttributeDecoderType::Generic => 0,
+			AttributeDecoderType::Integer => 1,
+			AttributeDecoderType::Quantization => 2,
+		}
+	}
+}
+impl TryFrom<u8> for AttributeDecoderType {
+	type Error = DracoError;
+	fn try_from(value: u8) -> Result<Self, Self::Error> {
+		Ok(match value {
+			0 => Self::Generic,
+			1 => Self::Integer,
+			2 => Self::Quantization,
+			// 3 is the octahedral normal decoder, which only applies to Normal attributes
+			_ => return Err(DracoError::UnsupportedAttributeDecoder(value)),
+		})
+	}
+}
+
+/// A decoded attribute.  Values are stored as little endian bytes of the data type.
+#[derive(Debug, Clone)]
+pub struct Attribute {
+	pub attribute_type: AttributeType,
+	pub data_type: DataType,
+	pub num_components: usize,
+	pub values: Vec<u8>,
+}
+impl Attribute {
+	/// Iterate the values of this attribute as arrays of N little endian
+	/// values each S bytes wide.
+	pub fn iter_values<const S: usize, const N: usize>(
+		&self,
+	) -> Option<impl Iterator<Item = [[u8; S]; N]> + '_> {
+		if self.data_type.size() != S || self.num_components != N {
+			return None;
+		}
+		let (chunks, _) = self.values.as_chunks::<S>();
+		let (values, _) = chunks.as_chunks::<N>();
+		Some(values.iter().copied())
+	}
+}
+
+pub struct DracoMesh {
+	pub faces: Vec<[u32; 3]>,
+	pub num_points: u32,
+	pub attributes: Vec<Attribute>,
+}
+
+fn decode_header(buffer: &mut DecoderBuffer) -> Result<(), DracoError> {
+	if buffer.read_bytes(5)? != b"DRACO" {
+		return Err(DracoError::InvalidMagic);
+	}
+	let version = (buffer.read_u8()?, buffer.read_u8()?);
+	if !((2, 0)..=(2, 2)).contains(&version) {
+		return Err(DracoError::UnsupportedVersion(version.0, version.1));
+	}
+	buffer.version = version;
+	let encoder_type = buffer.read_u8()?;
+	if encoder_type != ENCODER_TYPE_TRIANGULAR_MESH {
+		return Err(DracoError::UnsupportedEncoderType(encoder_type));
+	}
+	let encoder_method = buffer.read_u8()?;
+	if encoder_method != ENCODER_METHOD_MESH_SEQUENTIAL {
+		return Err(DracoError::UnsupportedEncoderMethod(encoder_method));
+	}
+	let flags = buffer.read_u16()?;
+	if flags & METADATA_FLAG_MASK != 0 {
+		return Err(DracoError::UnsupportedMetadata);
+	}
+	Ok(())
+}
+
+/// Returns the faces, the number of points and the limits left for the attributes.
+fn decode_connectivity(
+	buffer: &mut DecoderBuffer,
+	limits: ReadLimits,
+) -> Result<(Vec<[u32; 3]>, u32, ReadLimits), DracoError> {
+	let (num_faces, num_points) = if buffer.version() < (2, 2) {
+		(buffer.read_u32()?, buffer.read_u32()?)
+	} else {
+		(buffer.read_varint_u32()?, buffer.read_varint_u32()?)
+	};
+	let limits = limits
+		.charge()
+		.faces(num_faces)
+		.vertices(num_points)
+		.alloc::<[u32; 3]>(num_faces)
+		.alloc::<super::Vertex2>(num_points)
+		.finish()
+		.map_err(DracoError::Limit)?;
+	let num_indices = (num_faces as usize)
+		.checked_mul(3)
+		.ok_or(DracoError::InvalidConnectivity)?;
+
+	let indices = match buffer.read_u8()? {
+		CONNECTIVITY_COMPRESSED_INDICES => {
+			let symbols = decode_symbols(num_indices, 1, buffer)?;
+			// indices are delta coded with the sign in the lowest bit
+			let mut last_index: i32 = 0;
+			let mut indices = Vec::with_capacity(num_indices);
+			for symbol in symbols {
+				let mut index_diff = (symbol >> 1) as i32;
+				if symbol & 1 != 0 {
+					if last_index < index_diff {
+						return Err(DracoError::InvalidConnectivity);
+					}
+					index_diff = -index_diff;
+				}
+				last_index = index_diff
+					.checked_add(last_index)
+					.ok_or(DracoError::InvalidConnectivity)?;
+				indices.push(last_index as u32);
+			}
+			indices
+		}
+		CONNECTIVITY_UNCOMPRESSED_INDICES => {
+			// the index width is chosen by the number of points
+			let read_index: fn(&mut DecoderBuffer) -> Result<u32, DracoError> =
+				if num_points < 1 << 8 {
+					|buffer| Ok(buffer.read_u8()? as u32)
+				} else if num_points < 1 << 16 {
+					|buffer| Ok(buffer.read_u16()? as u32)
+				} else if num_points < 1 << 21 && (2, 2) <= buffer.version() {
+					|buffer| buffer.read_varint_u32()
+				} else {
+					|buffer| buffer.read_u32()
+				};
+			if buffer.remaining() < num_indices {
+				return Err(DracoError::UnexpectedEof);
+			}
+			(0..num_indices)
+				.map(|_| read_index(buffer))
+				.collect::<Result<Vec<u32>, _>>()?
+		}
+		_ => return Err(DracoError::InvalidConnectivity),
+	};
+
+	if indices.iter().any(|&index| num_points <= index) {
+		return Err(DracoError::InvalidConnectivity);
+	}
+
+	let (faces, _) = indices.as_chunks();
+	Ok((faces.to_vec(), num_points, limits))
+}
+
+struct AttributeHeader {
+	attribute_type: AttributeType,
+	data_type: DataType,
+	num_components: usize,
+	decoder_type: AttributeDecoderType,
+}
+
+/// The prediction scheme transform data which must be applied to integer values.
+struct WrapTransform {
+	min_value: i32,
+	max_value: i32,
+}
+impl WrapTransform {
+	fn decode(buffer: &mut DecoderBuffer) -> Result<Self, DracoError> {
+		let min_value = buffer.read_i32()?;
+		let max_value = buffer.read_i32()?;
+		let dif = max_value as i64 - min_value as i64;
+		if dif < 0 || i32::MAX as i64 <= dif {
+			return Err(DracoError::InvalidSymbols);
+		}
+		Ok(Self {
+			min_value,
+			max_value,
+		})
+	}
+	/// Undo delta prediction with the wrap transform.
+	fn compute_original_values(&self, values: &mut [i32], num_components: usize) {
+		let max_dif = self.max_value - self.min_value + 1;
+		let clamp = |value: i32| value.clamp(self.min_value, self.max_value);
+		let mut predicted = vec![0i32; num_components];
+		for entry in values.chunks_exact_mut(num_components) {
+			for (value, &predicted) in entry.iter_mut().zip(&predicted) {
+				let mut original = clamp(predicted).wrapping_add(*value);
+				if self.max_value < original {
+					original = original.wrapping_sub(max_dif);
+				} else if original < self.min_value {
+					original = original.wrapping_add(max_dif);
+				}
+				*value = original;
+			}
+			predicted.copy_from_slice(entry);
+		}
+	}
+}
+
+/// Decode the integer values of a portable attribute.
+fn decode_integer_values(
+	buffer: &mut DecoderBuffer,
+	num_points: usize,
+	num_components: usize,
+) -> Result<Vec<i32>, DracoError> {
+	let prediction_scheme = buffer.read_i8()?;
+	let has_prediction = prediction_scheme != PREDICTION_NONE;
+	if has_prediction {
+		// The sequential decoders have no corner table, so every prediction
+		// scheme falls back to delta prediction.  Only the wrap transform is supported.
+		let transform = buffer.read_i8()?;
+		if transform != PREDICTION_TRANSFORM_WRAP {
+			return Err(DracoError::UnsupportedPredictionTransform(transform));
+		}
+	}
+
+	let num_values = num_points
+		.checked_mul(num_components)
+		.ok_or(DracoError::InvalidAttribute)?;
+	let symbols = if buffer.read_u8()? != 0 {
+		decode_symbols(num_values, num_components, buffer)?
+	} else {
+		let num_bytes = buffer.read_u8()? as usize;
+		if !(1..=4).contains(&num_bytes) {
+			return Err(DracoError::InvalidAttribute);
+		}
+		let data = buffer.read_bytes(
+			num_values
+				.checked_mul(num_bytes)
+				.ok_or(DracoError::InvalidAttribute)?,
+		)?;
+		data.chunks_exact(num_bytes)
+			.map(|bytes| {
+				let mut le_bytes = [0u8; 4];
+				le_bytes[..num_bytes].copy_from_slice(bytes);
+				u32::from_le_bytes(le_bytes)
+			})
+			.collect()
+	};
+
+	// wrap transform corrections are signed, with the sign in the lowest bit
+	let mut values: Vec<i32> = symbols
+		.into_iter()
+		.map(|symbol| {
+			let magnitude = (symbol >> 1) as i32;
+			if symbol & 1 == 0 {
+				magnitude
+			} else {
+				-magnitude - 1
+			}
+		})
+		.collect();
+
+	if has_prediction && num_values != 0 {
+		let transform = WrapTransform::decode(buffer)?;
+		transform.compute_original_values(&mut values, num_components);
+	}
+
+	Ok(values)
+}
+
+fn integer_to_bytes(data_type: DataType, values: &[i32]) -> Result<Vec<u8>, DracoError> {
+	// integer values are truncated to the attribute data type
+	let mut bytes = Vec::with_capacity(values.len() * data_type.size());
+	for &value in values {
+		match data_type {
+			DataType::Int8 | DataType::UInt8 | DataType::Bool => bytes.push(value as u8),
+			DataType::Int16 | DataType::UInt16 => {
+				bytes.extend_from_slice(&(value as u16).to_le_bytes())
+			}
+			DataType::Int32 | DataType::UInt32 => bytes.extend_from_slice(&value.to_le_bytes()),
+			DataType::Int64 | DataType::UInt64 => {
+				bytes.extend_from_slice(&(value as i64).to_le_bytes())
+			}
+			DataType::Float32 | DataType::Float64 => {
+				return Err(DracoError::InvalidAttribute);
+			}
+		}
+	}
+	Ok(bytes)
+}
+
+enum PortableValues {
+	Done(Vec<u8>),
+	Quantized(Vec<i32>),
+}
+
+/// Read the quantization parameters and convert the quantized values to f32 bytes.
+fn dequantize(
+	buffer: &mut DecoderBuffer,
+	values: &[i32],
+	num_components: usize,
+) -> Result<Vec<u8>, DracoError> {
+	let min_values = (0..num_components)
+		.map(|_| buffer.read_f32())
+		.collect::<Result<Vec<f32>, _>>()?;
+	let range = buffer.read_f32()?;
+	let quantization_bits = buffer.read_u8()?;
+	if !(1..=30).contains(&quantization_bits) {
+		return Err(DracoError::InvalidQuantization);
+	}
+	let max_quantized_value = (1u32 << quantization_bits) - 1;
+	let delta = range / max_quantized_value as f32;
+	let mut bytes = Vec::with_capacity(values.len() * size_of::<f32>());
+	for entry in values.chunks_exact(num_components) {
+		for (&value, &min_value) in entry.iter().zip(&min_values) {
+			let value = value as f32 * delta + min_value;
+			bytes.extend_from_slice(&value.to_le_bytes());
+		}
+	}
+	Ok(bytes)
+}
+
+fn decode_attributes(
+	buffer: &mut DecoderBuffer,
+	num_points: u32,
+	limits: ReadLimits,
+) -> Result<Vec<Attribute>, DracoError> {
+	let num_points = num_points as usize;
+	let num_decoders = buffer.read_u8()?;
+	let mut headers = Vec::new();
+	let mut decoder_attribute_counts = Vec::new();
+	for _ in 0..num_decoders {
+		let num_attributes = buffer.read_varint_u32()?;
+		if num_attributes == 0 {
+			return Err(DracoError::InvalidAttribute);
+		}
+		let start = headers.len();
+		for _ in 0..num_attributes {
+			let attribute_type = AttributeType::try_from(buffer.read_u8()?)?;
+			let data_type = DataType::try_from(buffer.read_u8()?)?;
+			let num_components = buffer.read_u8()? as usize;
+			let _normalized = buffer.read_u8()?;
+			let _unique_id = buffer.read_varint_u32()?;
+			if num_components == 0 {
+				return Err(DracoError::InvalidAttribute);
+			}
+			headers.push(AttributeHeader {
+				attribute_type,
+				data_type,
+				num_components,
+				decoder_type: AttributeDecoderType::Generic,
+			});
+		}
+		for header in &mut headers[start..] {
+			header.decoder_type = AttributeDecoderType::try_from(buffer.read_u8()?)?;
+		}
+		decoder_attribute_counts.push(start..headers.len());
+	}
+
+	// every attribute is kept until the end, so they share the limits.
+	// integer values are decoded as symbols and then as values before their bytes
+	let mut charge = limits.charge();
+	for header in &headers {
+		let num_values = (num_points as u64).saturating_mul(header.num_components as u64);
+		if header.decoder_type != AttributeDecoderType::Generic {
+			charge = charge.alloc::<u32>(num_values).alloc::<i32>(num_values);
+		}
+		charge = charge.bytes(num_values.saturating_mul(header.data_type.size() as u64));
+	}
+	charge.finish().map_err(DracoError::Limit)?;
+
+	let mut attributes = Vec::with_capacity(headers.len());
+	for range in decoder_attribute_counts {
+		let headers = &headers[range];
+
+		// portable attributes are decoded first
+		let mut portable = Vec::with_capacity(headers.len());
+		for header in headers {
+			portable.push(match header.decoder_type {
+				AttributeDecoderType::Generic => {
+					let stride = header.data_type.size() * header.num_components;
+					let len = num_points
+						.checked_mul(stride)
+						.ok_or(DracoError::InvalidAttribute)?;
+					PortableValues::Done(buffer.read_bytes(len)?.to_vec())
+				}
+				AttributeDecoderType::Integer => {
+					let values = decode_integer_values(buffer, num_points, header.num_components)?;
+					PortableValues::Done(integer_to_bytes(header.data_type, &values)?)
+				}
+				AttributeDecoderType::Quantization => {
+					if header.data_type != DataType::Float32 {
+						return Err(DracoError::InvalidQuantization);
+					}
+					let values = decode_integer_values(buffer, num_points, header.num_components)?;
+					PortableValues::Quantized(values)
+				}
+			});
+		}
+
+		// followed by the data needed to transform them into their original format
+		for (header, portable) in headers.iter().zip(portable) {
+			let values = match portable {
+				PortableValues::Done(bytes) => bytes,
+				PortableValues::Quantized(values) => {
+					dequantize(buffer, &values, header.num_components)?
+				}
+			};
+			attributes.push(Attribute {
+				attribute_type: header.attribute_type,
+				data_type: header.data_type,
+				num_components: header.num_components,
+				values,
+			});
+		}
+	}
+
+	Ok(attributes)
+}
+
+/// Decode a complete draco bitstream.  The entire buffer must be consumed.
+pub fn decode(data: &[u8], limits: ReadLimits) -> Result<DracoMesh, DracoError> {
+	let mut buffer = DecoderBuffer::new(data);
+	decode_header(&mut buffer)?;
+	let (faces, num_points, limits) = decode_connectivity(&mut buffer, limits)?;
+	let attributes = decode_attributes(&mut buffer, num_points, limits)?;
+	if buffer.remaining() != 0 {
+		return Err(DracoError::TrailingData(buffer.remaining()));
+	}
+	Ok(DracoMesh {
+		faces,
+		num_points,
+		attributes,
+	})
+}
+
+struct EncoderBuffer {
+	data: Vec<u8>,
+}
+impl EncoderBuffer {
+	fn write_bytes(&mut self, bytes: &[u8]) {
+		self.data.extend_from_slice(bytes);
+	}
+	fn write_u8(&mut self, value: u8) {
+		self.data.push(value);
+	}
+	fn write_u16(&mut self, value: u16) {
+		self.write_bytes(&value.to_le_bytes());
+	}
+	fn write_u32(&mut self, value: u32) {
+		self.write_bytes(&value.to_le_bytes());
+	}
+	fn write_varint_u32(&mut self, mut value: u32) {
+		while 0x80 <= value {
+			self.write_u8(value as u8 | 0x80);
+			value >>= 7;
+		}
+		self.write_u8(value as u8);
+	}
+}
+
+/// Encode a complete draco bitstream version 2.2.
+/// Indices are stored uncompressed and every attribute uses the generic encoder.
+pub fn encode(mesh: &DracoMesh) -> Result<Vec<u8>, DracoError> {
+	let mut buffer = EncoderBuffer { data: Vec::new() };
+
+	// header
+	buffer.write_bytes(b"DRACO");
+	buffer.write_bytes(&[2, 2]);
+	buffer.write_u8(ENCODER_TYPE_TRIANGULAR_MESH);
+	buffer.write_u8(ENCODER_METHOD_MESH_SEQUENTIAL);
+	buffer.write_u16(0);
+
+	// connectivity
+	let num_points = mesh.num_points;
+	let num_faces = u32::try_from(mesh.faces.len()).map_err(|_| DracoError::InvalidConnectivity)?;
+	buffer.write_varint_u32(num_faces);
+	buffer.write_varint_u32(num_points);
+	buffer.write_u8(CONNECTIVITY_UNCOMPRESSED_INDICES);
+	for &index in mesh.faces.as_flattened() {
+		if num_points <= index {
+			return Err(DracoError::InvalidConnectivity);
+		}
+		// the index width is chosen by the number of points
+		if num_points < 1 << 8 {
+			buffer.write_u8(index as u8);
+		} else if num_points < 1 << 16 {
+			buffer.write_u16(index as u16);
+		} else if num_points < 1 << 21 {
+			buffer.write_varint_u32(index);
+		} else {
+			buffer.write_u32(index);
+		}
+	}
+
+	// a single attributes decoder holds every attribute
+	if mesh.attributes.is_empty() {
+		buffer.write_u8(0);
+		return Ok(buffer.data);
+	}
+	buffer.write_u8(1);
+	let num_attributes =
+		u32::try_from(mesh.attributes.len()).map_err(|_| DracoError::InvalidAttribute)?;
+	buffer.write_varint_u32(num_attributes);
+	for (unique_id, attribute) in mesh.attributes.iter().enumerate() {
+		let stride = attribute.data_type.size() * attribute.num_components;
+		let num_components =
+			u8::try_from(attribute.num_components).map_err(|_| DracoError::InvalidAttribute)?;
+		if num_components == 0 || attribute.values.len() != num_points as usize * stride {
+			return Err(DracoError::InvalidAttribute);
+		}
+		buffer.write_u8(attribute.attribute_type.into());
+		buffer.write_u8(attribute.data_type.into());
+		buffer.write_u8(num_components);
+		// normalized
+		buffer.write_u8(0);
+		buffer.write_varint_u32(unique_id as u32);
+	}
+	for _ in &mesh.attributes {
+		buffer.write_u8(AttributeDecoderType::Generic.into());
+	}
+	for attribute in &mesh.attributes {
+		buffer.write_bytes(&attribute.values);
+	}
+
+	Ok(buffer.data)
+}
+
+#[test]
+fn trailing_data() {
+	let mesh = DracoMesh {
+		faces: vec![[0, 1, 2]],
+		num_points: 3,
+		attributes: vec![Attribute {
+			attribute_type: AttributeType::Position,
+			data_type: DataType::Float32,
+			num_components: 3,
+			values: vec![0; 36],
+		}],
+	};
+	let mut data = encode(&mesh).unwrap();
+	assert!(decode(&data, ReadLimits::DEFAULT).is_ok());
+	data.push(0);
+	assert!(matches!(
+		decode(&data, ReadLimits::DEFAULT),
+		Err(DracoError::TrailingData(1))
+	));
+}
+
+#[cfg(test)]
+fn integer_attribute_stream(num_points: u32, num_components: u8, symbols: &[u8]) -> Vec<u8> {
+	let mut buffer = EncoderBuffer { data: Vec::new() };
+	buffer.write_bytes(b"DRACO");
+	buffer.write_bytes(&[2, 2, ENCODER_TYPE_TRIANGULAR_MESH]);
+	buffer.write_u8(ENCODER_METHOD_MESH_SEQUENTIAL);
+	buffer.write_u16(0);
+	// no faces
+	buffer.write_varint_u32(0);
+	buffer.write_varint_u32(num_points);
+	buffer.write_u8(CONNECTIVITY_UNCOMPRESSED_INDICES);
+	// one decoder with one attribute
+	buffer.write_u8(1);
+	buffer.write_varint_u32(1);
+	buffer.write_u8(AttributeType::Generic.into());
+	buffer.write_u8(DataType::Int32.into());
+	buffer.write_u8(num_components);
+	buffer.write_u8(0);
+	buffer.write_varint_u32(0);
+	buffer.write_u8(AttributeDecoderType::Integer.into());
+	buffer.write_u8(PREDICTION_NONE as u8);
+	// compressed
+	buffer.write_u8(1);
+	buffer.write_bytes(symbols);
+	buffer.data
+}
+
+#[test]
+fn attribute_limits() {
+	// 16M points of 255 Int32 components would allocate gigabytes
+	let data = integer_attribute_stream(16_000_000, 255, &[]);
+	assert!(matches!(
+		decode(&data, ReadLimits::DEFAULT),
+		Err(DracoError::Limit(LimitError::AllocBytes(_)))
+	));
+}
+
+#[test]
+fn exhausted_symbols() {
+	// raw symbols with a max bit length of 1, two symbols of half probability,
+	// and one encoded byte which holds a single symbol
+	let symbols = [1, 1, 2, 1, 32, 1, 32, 1, 0];
+	let data = integer_attribute_stream(1, 1, &symbols);
+	assert!(decode(&data, ReadLimits::DEFAULT).is_ok());
+	let data = integer_attribute_stream(4, 1, &symbols);
+	assert!(matches!(
+		decode(&data, ReadLimits::DEFAULT),
+		Err(DracoError::UnexpectedEof)
+	));
+}
+
+/// A box with its corners moved so that the positions are not exactly representable,
+/// stored with delta coded compressed indices and 11 bit quantized positions
+/// with delta prediction, both entropy coded with the raw rANS scheme.
+#[cfg(test)]
+pub(super) const QUANTIZED_CUBE: [[u32; 3]; 8] = [
+	[0, 0, 0],
+	[2047, 13, 0],
+	[5, 1900, 7],
+	[2047, 2047, 100],
+	[12, 0, 2047],
+	[1999, 40, 2047],
+	[0, 2047, 1500],
+	[1024, 2047, 2047],
+];
+#[cfg(test)]
+pub(super) const QUANTIZED_CUBE_FACES: [[u32; 3]; 12] = [
+	[0, 2, 1],
+	[1, 2, 3],
+	[4, 5, 6],
+	[5, 7, 6],
+	[0, 1, 4],
+	[1, 5, 4],
+	[2, 6, 3],
+	[3, 6, 7],
+	[0, 4, 2],
+	[2, 4, 6],
+	[1, 3, 5],
+	[3, 7, 5],
+];
+
+#[cfg(test)]
+pub(super) fn quantized_cube_stream() -> Vec<u8> {
+	use super::rans::encode_raw_symbols;
+	// zigzag with the sign in the lowest bit, as the decoder expects for each kind of value
+	let index_symbol = |diff: i32| (diff.unsigned_abs() << 1) | (diff < 0) as u32;
+	let value_symbol = |value: i32| {
+		if value < 0 {
+			((-value - 1) << 1 | 1) as u32
+		} else {
+			(value << 1) as u32
+		}
+	};
+
+	let mut buffer = EncoderBuffer { data: Vec::new() };
+	buffer.write_bytes(b"DRACO");
+	buffer.write_bytes(&[2, 2, ENCODER_TYPE_TRIANGULAR_MESH]);
+	buffer.write_u8(ENCODER_METHOD_MESH_SEQUENTIAL);
+	buffer.write_u16(0);
+	buffer.write_varint_u32(QUANTIZED_CUBE_FACES.len() as u32);
+	buffer.write_varint_u32(QUANTIZED_CUBE.len() as u32);
+	buffer.write_u8(CONNECTIVITY_COMPRESSED_INDICES);
+	let mut last_index = 0;
+	let symbols: Vec<u32> = QUANTIZED_CUBE_FACES
+		.as_flattened()
+		.iter()
+		.map(|&index| {
+			let diff = index as i32 - last_index;
+			last_index = index as i32;
+			index_symbol(diff)
+		})
+		.collect();
+	buffer.write_bytes(&encode_raw_symbols(&symbols));
+
+	// one decoder with one quantized position attribute
+	buffer.write_u8(1);
+	buffer.write_varint_u32(1);
+	buffer.write_u8(AttributeType::Position.into());
+	buffer.write_u8(DataType::Float32.into());
+	buffer.write_u8(3);
+	buffer.write_u8(0);
+	buffer.write_varint_u32(0);
+	buffer.write_u8(AttributeDecoderType::Quantization.into());
+	// delta prediction with the wrap transform
+	buffer.write_u8(0);
+	buffer.write_u8(PREDICTION_TRANSFORM_WRAP as u8);
+	buffer.write_u8(1);
+	let mut predicted = [0; 3];
+	let symbols: Vec<u32> = QUANTIZED_CUBE
+		.iter()
+		.flat_map(|&value| {
+			let corrections = core::array::from_fn::<_, 3, _>(|i| value[i] as i32 - predicted[i]);
+			predicted = value.map(|value| value as i32);
+			corrections.map(value_symbol)
+		})
+		.collect();
+	buffer.write_bytes(&encode_raw_symbols(&symbols));
+	buffer.write_u32(0);
+	buffer.write_u32(2047);
+	// min values, range and quantization bits
+	for min_value in [-0.5f32, -0.25, -1.0] {
+		buffer.write_bytes(&min_value.to_le_bytes());
+	}
+	buffer.write_bytes(&1.0f32.to_le_bytes());
+	buffer.write_u8(11);
+	buffer.data
+}
+
+#[test]
+fn compressed_quantized() {
+	let mesh = decode(&quantized_cube_stream(), ReadLimits::DEFAULT).unwrap();
+	assert_eq!(mesh.faces, QUANTIZED_CUBE_FACES);
+	assert_eq!(mesh.num_points, 8);
+	let positions: Vec<[f32; 3]> = mesh.attributes[0]
+		.iter_values()
+		.unwrap()
+		.map(|value: [[u8; 4]; 3]| value.map(f32::from_le_bytes))
+		.collect();
+	let expected: Vec<[f32; 3]> = QUANTIZED_CUBE
+		.iter()
+		.map(|value| {
+			let delta = 1.0 / 2047.0;
+			let [x, y, z] = value.map(|value| value as f32 * delta);
+			[x - 0.5, y - 0.25, z - 1.0]
+		})
+		.collect();
+	assert_eq!(positions, expected);
+
+	// the core mesh of the fixture is exactly this stream
+	let fixture = std::fs::read("meshes/mesh7_quantized_cube.bin").unwrap();
+	assert!(fixture.ends_with(&quantized_cube_stream()));
+}
@@ -0,0 +1,275 @@
+mod draco;
+mod rans;
+
+use std::io::Read;
+
+use binrw::{BinReaderExt, BinWriterExt};
+
+use super::v2::{Face2, Vertex2, VertexId2};
+use super::v5::Facs5;
+use super::v6::{Facs6, HsrAvis6, Lods6, Skinning6, UnknownChunk6, read_sized, write_sized};
+pub use draco::DracoError;
+use draco::{Attribute, AttributeType, DataType, DracoMesh};
+
+use crate::limits::ReadLimits;
+
+#[binrw::binrw]
+#[brw(little)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Revision7 {
+	#[brw(magic = b"version 7.00\n")]
+	Version700,
+}
+
+/// COREMESH version 2 stores the geometry as a draco compressed bitstream.
+/// It is decoded into the same vertex and face layout as Mesh5.
+/// Writing produces an uncompressed draco bitstream, so it does not round trip byte for byte.
+#[derive(Debug, Clone)]
+pub struct CoreMesh7 {
+	pub vertices: Vec<Vertex2>,
+	pub faces: Vec<Face2>,
+}
+impl binrw::BinRead for CoreMesh7 {
+	type Args<'a> = (ReadLimits,);
+	fn read_options<R: BinReaderExt>(
+		reader: &mut R,
+		endian: binrw::Endian,
+		(limits,): Self::Args<'_>,
+	) -> binrw::BinResult<Self> {
+		let draco_len: u32 = reader.read_type(endian)?;
+		// use the stream position at the beginning of the draco data
+		let pos = reader.stream_position()?;
+		let mut data = Vec::new();
+		reader.take(draco_len as u64).read_to_end(&mut data)?;
+		if data.len() != draco_len as usize {
+			return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+		}
+		let draco_mesh = draco::decode(&data, limits).map_err(|e| binrw::Error::Custom {
+			pos,
+			err: Box::new(e),
+		})?;
+		Ok(Self::from_draco(draco_mesh))
+	}
+}
+impl binrw::BinWrite for CoreMesh7 {
+	type Args<'a> = ();
+	fn write_options<W: BinWriterExt>(
+		&self,
+		writer: &mut W,
+		endian: binrw::Endian,
+		_args: Self::Args<'_>,
+	) -> binrw::BinResult<()> {
+		let pos = writer.stream_position()?;
+		let data = draco::encode(&self.to_draco()).map_err(|e| binrw::Error::Custom {
+			pos,
+			err: Box::new(e),
+		})?;
+		let draco_len: u32 = data.len().try_into().map_err(|e| binrw::Error::Custom {
+			pos,
+			err: Box::new(e),
+		})?;
+		writer.write_type(&draco_len, endian)?;
+		writer.write_all(&data)?;
+		Ok(())
+	}
+}
+impl CoreMesh7 {
+	fn to_draco(&self) -> DracoMesh {
+		let attribute = |attribute_type, data_type, num_components, values| Attribute {
+			attribute_type,
+			data_type,
+			num_components,
+			values,
+		};
+		let vertices = &self.vertices;
+		let positions = vertices
+			.iter()
+			.flat_map(|vertex| vertex.pos)
+			.flat_map(f32::to_le_bytes)
+			.collect();
+		let normals = vertices
+			.iter()
+			.flat_map(|vertex| vertex.norm)
+			.flat_map(f32::to_le_bytes)
+			.collect();
+		let tex = vertices
+			.iter()
+			.flat_map(|vertex| vertex.tex)
+			.flat_map(f32::to_le_bytes)
+			.collect();
+		// tangents are stored as generic bytes like Roblox does
+		let tangents = vertices
+			.iter()
+			.flat_map(|vertex| vertex.tangent.map(|value| value as u8))
+			.collect();
+		let colors = vertices.iter().flat_map(|vertex| vertex.color).collect();
+
+		let faces = self
+			.faces
+			.iter()
+			.map(|Face2(face)| face.clone().map(|VertexId2(id)| id))
+			.collect();
+
+		DracoMesh {
+			faces,
+			num_points: vertices.len() as u32,
+			attributes: vec![
+				attribute(AttributeType::Position, DataType::Float32, 3, positions),
+				attribute(AttributeType::Normal, DataType::Float32, 3, normals),
+				attribute(AttributeType::TexCoord, DataType::Float32, 2, tex),
+				attribute(AttributeType::Generic, DataType::UInt8, 4, tangents),
+				attribute(AttributeType::Color, DataType::UInt8, 4, colors),
+			],
+		}
+	}
+	fn from_draco(draco_mesh: DracoMesh) -> Self {
+		let DracoMesh {
+			faces,
+			num_points,
+			attributes,
+		} = draco_mesh;
+		let find = |attribute_type: AttributeType, data_type: DataType, num_components: usize| {
+			attributes.iter().find(|attribute| {
+				attribute.attribute_type == attribute_type
+					&& attribute.data_type == data_type
+					&& attribute.num_components == num_components
+			})
+		};
+		let f32x3 = |value: [[u8; 4]; 3]| value.map(f32::from_le_bytes);
+		let f32x2 = |value: [[u8; 4]; 2]| value.map(f32::from_le_bytes);
+		let u8x4 = |value: [[u8; 1]; 4]| value.map(|[byte]| byte);
+		fn collect<T>(values: Option<impl Iterator<Item = T>>) -> Vec<T> {
+			values.into_iter().flatten().collect()
+		}
+
+		// Roblox stores normals and tangents as generic attributes
+		let positions = collect(
+			find(AttributeType::Position, DataType::Float32, 3)
+				.and_then(|attribute| attribute.iter_values())
+				.map(|values| values.map(f32x3)),
+		);
+		let normals = collect(
+			find(AttributeType::Normal, DataType::Float32, 3)
+				.or_else(|| find(AttributeType::Generic, DataType::Float32, 3))
+				.and_then(|attribute| attribute.iter_values())
+				.map(|values| values.map(f32x3)),
+		);
+		let tex = collect(
+			find(AttributeType::TexCoord, DataType::Float32, 2)
+				.and_then(|attribute| attribute.iter_values())
+				.map(|values| values.map(f32x2)),
+		);
+		let tangents = collect(
+			find(AttributeType::Generic, DataType::UInt8, 4)
+				.or_else(|| find(AttributeType::Generic, DataType::Int8, 4))
+				.and_then(|attribute| attribute.iter_values())
+				.map(|values| values.map(|value| u8x4(value).map(|byte| byte as i8))),
+		);
+		let colors = collect(
+			find(AttributeType::Color, DataType::UInt8, 4)
+				.and_then(|attribute| attribute.iter_values())
+				.map(|values| values.map(u8x4)),
+		);
+
+		// missing attributes are filled with defaults
+		let vertices = (0..num_points as usize)
+			.map(|i| Vertex2 {
+				pos: positions.get(i).copied().unwrap_or_default(),
+				norm: normals.get(i).copied().unwrap_or_default(),
+				tex: tex.get(i).copied().unwrap_or_default(),
+				tangent: tangents.get(i).copied().unwrap_or_default(),
+				color: colors.get(i).copied().unwrap_or([u8::MAX; 4]),
+			})
+			.collect();
+
+		let faces = faces
+			.into_iter()
+			.map(|face| Face2(face.map(VertexId2)))
+			.collect();
+
+		Self { vertices, faces }
+	}
+}
+
+// concat_bytes!(chunk_type, version)
+#[expect(clippy::large_enum_variant)]
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
+#[derive(Debug, Clone)]
+pub enum Chunk7 {
+	#[brw(magic = b"COREMESH\x02\0\0\0")]
+	CoreMesh(
+		#[br(parse_with = read_sized, args(limits))]
+		#[bw(write_with = write_sized)]
+		CoreMesh7,
+	),
+	#[brw(magic = b"LODS\0\0\0\0\x01\0\0\0")]
+	Lods(
+		#[br(parse_with = read_sized, args(limits))]
+		#[bw(write_with = write_sized)]
+		Lods6,
+	),
+	#[brw(magic = b"SKINNING\x01\0\0\0")]
+	Skinning(
+		#[br(parse_with = read_sized, args(limits))]
+		#[bw(write_with = write_sized)]
+		Skinning6,
+	),
+	#[brw(magic = b"FACS\0\0\0\0\x01\0\0\0")]
+	Facs(
+		#[br(parse_with = read_sized, args(limits))]
+		#[bw(write_with = write_sized)]
+		Facs6,
+	),
+	#[brw(magic = b"HSRAVIS\0\x01\0\0\0")]
+	HsrAvis(
+		#[br(parse_with = read_sized, args(limits))]
+		#[bw(write_with = write_sized)]
+		HsrAvis6,
+	),
+	Unknown(#[br(args(limits))] UnknownChunk6),
+}
+
+#[binrw::binrw]
+#[brw(little)]
+#[derive(Debug, Clone)]
+/// Mesh v7 uses the same chunks as Mesh6, except that the core mesh is draco compressed.
+#[br(import(limits: ReadLimits))]
+pub struct Mesh7 {
+	pub revision: Revision7,
+	#[br(parse_with=binrw::helpers::until_eof, args(limits))]
+	pub chunks: Vec<Chunk7>,
+}
+impl Mesh7 {
+	pub fn core_mesh(&self) -> Option<&CoreMesh7> {
+		self.chunks.iter().find_map(|chunk| match chunk {
+			Chunk7::CoreMesh(core_mesh) => Some(core_mesh),
+			_ => None,
+		})
+	}
+	pub fn lods(&self) -> Option<&Lods6> {
+		self.chunks.iter().find_map(|chunk| match chunk {
+			Chunk7::Lods(lods) => Some(lods),
+			_ => None,
+		})
+	}
+	pub fn skinning(&self) -> Option<&Skinning6> {
+		self.chunks.iter().find_map(|chunk| match chunk {
+			Chunk7::Skinning(skinning) => Some(skinning),
+			_ => None,
+		})
+	}
+	pub fn facs(&self) -> Option<&Facs5> {
+		self.chunks.iter().find_map(|chunk| match chunk {
+			Chunk7::Facs(facs) => Some(&facs.facs),
+			_ => None,
+		})
+	}
+	pub fn hsr_avis(&self) -> Option<&HsrAvis6> {
+		self.chunks.iter().find_map(|chunk| match chunk {
+			Chunk7::HsrAvis(hsr_avis) => Some(hsr_avis),
+			_ => None,
+		})
+	}
+}
@@ -0,0 +1,304 @@
+// rANS entropy decoding as used by draco's symbol coding
+// based on draco/compression/entropy/{ans.h,rans_symbol_decoder.h,symbol_decoding.cc}
+
+use super::draco::{DecoderBuffer, DracoError};
+
+const IO_BASE: u32 = 256;
+
+const TAGGED_SYMBOLS: u8 = 0;
+const RAW_SYMBOLS: u8 = 1;
+
+const fn precision_from_bit_length(symbols_bit_length: u32) -> u32 {
+	let precision = (3 * symbols_bit_length) / 2;
+	if precision < 12 {
+		12
+	} else if 20 < precision {
+		20
+	} else {
+		precision
+	}
+}
+
+#[derive(Clone, Copy)]
+struct SymbolProbability {
+	prob: u32,
+	cum_prob: u32,
+}
+
+struct RAnsSymbolDecoder<'a> {
+	precision: u32,
+	lut: Vec<u32>,
+	probability_table: Vec<SymbolProbability>,
+	buf: &'a [u8],
+	buf_offset: usize,
+	state: u32,
+}
+impl<'a> RAnsSymbolDecoder<'a> {
+	/// Decode the probability table.
+	fn create(buffer: &mut DecoderBuffer<'a>, symbols_bit_length: u32) -> Result<Self, DracoError> {
+		let precision = 1 << precision_from_bit_length(symbols_bit_length);
+		let num_symbols = if buffer.version() < (2, 0) {
+			buffer.read_u32()?
+		} else {
+			buffer.read_varint_u32()?
+		};
+
+		let mut token_probs = Vec::new();
+		let mut i = 0;
+		while i < num_symbols {
+			let prob_data = buffer.read_u8()?;
+			// The first two bits are a token.  Values 0-2 are the number of extra bytes,
+			// and 3 denotes a run-length of zero probability symbols.
+			let token = prob_data & 3;
+			if token == 3 {
+				let offset = (prob_data >> 2) as u32;
+				if num_symbols <= i + offset {
+					return Err(DracoError::InvalidSymbols);
+				}
+				token_probs.extend(core::iter::repeat_n(0, offset as usize + 1));
+				i += offset + 1;
+			} else {
+				let mut prob = (prob_data >> 2) as u32;
+				for b in 0..token as u32 {
+					let extra_byte = buffer.read_u8()? as u32;
+					// shift 8 bits for each extra byte and subtract 2 for the token bits
+					prob |= extra_byte << (8 * (b + 1) - 2);
+				}
+				token_probs.push(prob);
+				i += 1;
+			}
+		}
+
+		// build look up table
+		let mut lut = vec![0; precision as usize];
+		let mut probability_table = Vec::with_capacity(token_probs.len());
+		let mut cum_prob = 0u32;
+		for (symbol, &prob) in token_probs.iter().enumerate() {
+			probability_table.push(SymbolProbability { prob, cum_prob });
+			let next_cum_prob = cum_prob
+				.checked_add(prob)
+				.filter(|&next_cum_prob| next_cum_prob <= precision)
+				.ok_or(DracoError::InvalidSymbols)?;
+			lut[cum_prob as usize..next_cum_prob as usize].fill(symbol as u32);
+			cum_prob = next_cum_prob;
+		}
+		if cum_prob != precision {
+			return Err(DracoError::InvalidSymbols);
+		}
+
+		Ok(Self {
+			precision,
+			lut,
+			probability_table,
+			buf: &[],
+			buf_offset: 0,
+			state: 0,
+		})
+	}
+	fn l_rans_base(&self) -> u32 {
+		self.precision * 4
+	}
+	/// Take the encoded bytes from the buffer and initialize the state.
+	fn start_decoding(&mut self, buffer: &mut DecoderBuffer<'a>) -> Result<(), DracoError> {
+		let bytes_encoded = if buffer.version() < (2, 0) {
+			buffer.read_u64()?
+		} else {
+			buffer.read_varint_u64()?
+		};
+		let bytes_encoded =
+			usize::try_from(bytes_encoded).map_err(|_| DracoError::UnexpectedEof)?;
+		let buf = buffer.read_bytes(bytes_encoded)?;
+
+		let Some(&last) = buf.last() else {
+			return Err(DracoError::InvalidSymbols);
+		};
+		let offset = buf.len();
+		let (header_len, mask) = match last >> 6 {
+			0 => (1, 0x3F),
+			1 => (2, 0x3FFF),
+			2 => (3, 0x3FFFFF),
+			_ => (4, 0x3FFFFFFF),
+		};
+		if offset < header_len {
+			return Err(DracoError::InvalidSymbols);
+		}
+		let mut le_bytes = [0u8; 4];
+		le_bytes[..header_len].copy_from_slice(&buf[offset - header_len..]);
+		self.buf = buf;
+		self.buf_offset = offset - header_len;
+		self.state = (u32::from_le_bytes(le_bytes) & mask) + self.l_rans_base();
+		if self.l_rans_base() * IO_BASE <= self.state {
+			return Err(DracoError::InvalidSymbols);
+		}
+		Ok(())
+	}
+	/// The state never drops below l_rans_base while symbols remain,
+	/// so a state which cannot be refilled means the encoded bytes are exhausted.
+	fn decode_symbol(&mut self) -> Result<u32, DracoError> {
+		let l_rans_base = self.l_rans_base();
+		while self.state < l_rans_base && 0 < self.buf_offset {
+			self.buf_offset -= 1;
+			self.state = self.state * IO_BASE + self.buf[self.buf_offset] as u32;
+		}
+		if self.state < l_rans_base {
+			return Err(DracoError::UnexpectedEof);
+		}
+		let quo = self.state / self.precision;
+		let rem = self.state % self.precision;
+		let symbol = self.lut[rem as usize];
+		let sym = self.probability_table[symbol as usize];
+		self.state = quo * sym.prob + rem - sym.cum_prob;
+		Ok(symbol)
+	}
+	fn num_symbols(&self) -> usize {
+		self.probability_table.len()
+	}
+}
+
+fn decode_tagged_symbols(
+	num_values: usize,
+	num_components: usize,
+	buffer: &mut DecoderBuffer,
+	out_values: &mut Vec<u32>,
+) -> Result<(), DracoError> {
+	let mut tag_decoder = RAnsSymbolDecoder::create(buffer, 5)?;
+	tag_decoder.start_decoding(buffer)?;
+	if tag_decoder.num_symbols() == 0 {
+		return Err(DracoError::InvalidSymbols);
+	}
+	let mut bit_decoder = buffer.start_bit_decoding();
+	while out_values.len() < num_values {
+		let bit_length = tag_decoder.decode_symbol()?;
+		if 32 < bit_length {
+			return Err(DracoError::InvalidSymbols);
+		}
+		for _ in 0..num_components {
+			out_values.push(bit_decoder.read_bits(bit_length)?);
+		}
+	}
+	out_values.truncate(num_values);
+	let bytes_decoded = bit_decoder.bytes_decoded();
+	buffer.read_bytes(bytes_decoded)?;
+	Ok(())
+}
+
+fn decode_raw_symbols(
+	num_values: usize,
+	buffer: &mut DecoderBuffer,
+	out_values: &mut Vec<u32>,
+) -> Result<(), DracoError> {
+	let max_bit_length = buffer.read_u8()? as u32;
+	if !(1..=18).contains(&max_bit_length) {
+		return Err(DracoError::InvalidSymbols);
+	}
+	let mut decoder = RAnsSymbolDecoder::create(buffer, max_bit_length)?;
+	if decoder.num_symbols() == 0 {
+		return Err(DracoError::InvalidSymbols);
+	}
+	decoder.start_decoding(buffer)?;
+	for _ in 0..num_values {
+		out_values.push(decoder.decode_symbol()?);
+	}
+	Ok(())
+}
+
+/// Decode `num_values` entropy coded symbols.
+pub fn decode_symbols(
+	num_values: usize,
+	num_components: usize,
+	buffer: &mut DecoderBuffer,
+) -> Result<Vec<u32>, DracoError> {
+	let mut out_values = Vec::with_capacity(num_values);
+	if num_values == 0 {
+		return Ok(out_values);
+	}
+	match buffer.read_u8()? {
+		TAGGED_SYMBOLS => {
+			decode_tagged_symbols(num_values, num_components, buffer, &mut out_values)?
+		}
+		RAW_SYMBOLS => decode_raw_symbols(num_values, buffer, &mut out_values)?,
+		_ => return Err(DracoError::InvalidSymbols),
+	}
+	Ok(out_values)
+}
+
+/// Encode symbols with a single rANS coder, the inverse of the raw scheme of `decode_symbols`.
+/// Probabilities follow the symbol frequencies.  Only used to build test streams.
+#[cfg(test)]
+pub fn encode_raw_symbols(symbols: &[u32]) -> Vec<u8> {
+	fn write_varint(data: &mut Vec<u8>, mut value: usize) {
+		while 0x80 <= value {
+			data.push(value as u8 | 0x80);
+			value >>= 7;
+		}
+		data.push(value as u8);
+	}
+	let max_symbol = symbols.iter().copied().max().unwrap_or(0);
+	let max_bit_length = (u32::BITS - max_symbol.leading_zeros()).max(1);
+	let precision = 1 << precision_from_bit_length(max_bit_length);
+
+	let mut probs = vec![0u32; max_symbol as usize + 1];
+	for &symbol in symbols {
+		probs[symbol as usize] += 1;
+	}
+	let total = symbols.len().max(1) as u32;
+	for prob in &mut probs {
+		if *prob != 0 {
+			*prob = (*prob * precision / total).max(1);
+		}
+	}
+	// the most frequent symbol takes up the rounding error
+	let sum: u32 = probs.iter().sum();
+	let most_frequent = (0..probs.len()).max_by_key(|&i| probs[i]).unwrap();
+	probs[most_frequent] = probs[most_frequent] + precision - sum;
+
+	let mut data = vec![RAW_SYMBOLS, max_bit_length as u8];
+	write_varint(&mut data, probs.len());
+	let mut i = 0;
+	while i < probs.len() {
+		let prob = probs[i];
+		i += 1;
+		if prob == 0 {
+			// runs of up to 64 zero probabilities share a byte
+			let run = probs[i..]
+				.iter()
+				.take(63)
+				.take_while(|&&prob| prob == 0)
+				.count();
+			data.push((run << 2 | 3) as u8);
+			i += run;
+		} else if prob < 1 << 6 {
+			data.push((prob << 2) as u8);
+		} else if prob < 1 << 14 {
+			data.extend_from_slice(&[(prob << 2 | 1) as u8, (prob >> 6) as u8]);
+		} else {
+			data.extend_from_slice(&[(prob << 2 | 2) as u8, (prob >> 6) as u8, (prob >> 14) as u8]);
+		}
+	}
+
+	// symbols are encoded in reverse, and the decoder reads the bytes from the end
+	let l_rans_base = precision * 4;
+	let mut state = l_rans_base;
+	let mut encoded = Vec::new();
+	for &symbol in symbols.iter().rev() {
+		let prob = probs[symbol as usize];
+		let cum_prob: u32 = probs[..symbol as usize].iter().sum();
+		while (l_rans_base / precision * IO_BASE) * prob <= state {
+			encoded.push(state as u8);
+			state /= IO_BASE;
+		}
+		state = (state / prob) * precision + state % prob + cum_prob;
+	}
+	let state = state - l_rans_base;
+	let (header, header_len) = match state {
+		state if state < 1 << 6 => (state, 1),
+		state if state < 1 << 14 => (state | 1 << 14, 2),
+		state if state < 1 << 22 => (state | 2 << 22, 3),
+		state => (state | 3 << 30, 4),
+	};
+	encoded.extend_from_slice(&header.to_le_bytes()[..header_len]);
+
+	write_varint(&mut data, encoded.len());
+	data.extend_from_slice(&encoded);
+	data
+}
@@ -0,0 +1,358 @@
+//! Wavefront OBJ export.
+//!
+//! Every mesh and union format converts into an `Obj`, which is a TriangleMesh
+//! with its faces split into named groups.  Each group is written as an `o`
+//! object, and uses its own material when an MTL file is written alongside.
+//!
+//! OBJ files can also be read back into an `Obj`.
+
+use core::ops::Range;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use crate::TriangleMesh;
+
+/// A named range of faces which is written as its own `o` object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjGroup {
+	pub name: String,
+	/// Range of TriangleMesh.indices
+	pub faces: Range<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Obj {
+	pub mesh: TriangleMesh,
+	/// In order and without gaps
+	pub groups: Vec<ObjGroup>,
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+	Io(std::io::Error),
+	/// The 1 based line number
+	InvalidLine(usize),
+	/// A face refers to a vertex which does not exist, on this 1 based line number.
+	IndexOutOfRange(usize),
+}
+impl std::fmt::Display for ObjError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+impl core::error::Error for ObjError {}
+
+/// Name each part, falling back to a single group when the mesh has no parts.
+fn part_groups(mesh: &TriangleMesh, prefix: &str) -> Vec<ObjGroup> {
+	if mesh.parts.is_empty() {
+		if mesh.indices.is_empty() {
+			return Vec::new();
+		}
+		return vec![ObjGroup {
+			name: format!("{prefix}0"),
+			faces: 0..mesh.indices.len(),
+		}];
+	}
+	mesh.parts
+		.iter()
+		.enumerate()
+		.map(|(i, part)| ObjGroup {
+			name: format!("{prefix}{i}"),
+			faces: part.clone(),
+		})
+		.collect()
+}
+
+/// One group per subset.  Faces outside of every subset, usually the lower
+/// levels of detail, are grouped by the lod they belong to.
+#[cfg(feature = "mesh")]
+fn subset_groups(mesh: &TriangleMesh, subsets: &[crate::mesh::Subset4]) -> Vec<ObjGroup> {
+	let face_count = mesh.indices.len();
+	let mut groups: Vec<ObjGroup> = subsets
+		.iter()
+		.enumerate()
+		.map(|(i, subset)| {
+			let start = (subset.faces_offset as usize).min(face_count);
+			let end = (start + subset.faces_len as usize).min(face_count);
+			ObjGroup {
+				name: format!("subset{i}"),
+				faces: start..end,
+			}
+		})
+		.filter(|group| !group.faces.is_empty())
+		.collect();
+	groups.sort_by_key(|group| group.faces.start);
+	// subsets which overlap cannot be written as separate objects
+	let mut end = 0;
+	groups.retain(|group| {
+		let keep = end <= group.faces.start;
+		if keep {
+			end = group.faces.end;
+		}
+		keep
+	});
+
+	// fill the gaps between subsets with the lods
+	let lods = part_groups(mesh, "lod");
+	let mut gaps = Vec::new();
+	let mut start = 0;
+	for group in groups
+		.iter()
+		.map(|group| group.faces.clone())
+		.chain(core::iter::once(face_count..face_count))
+	{
+		for lod in &lods {
+			let gap = start.max(lod.faces.start)..group.start.min(lod.faces.end);
+			if !gap.is_empty() {
+				gaps.push(ObjGroup {
+					name: lod.name.clone(),
+					faces: gap,
+				});
+			}
+		}
+		start = group.end;
+	}
+	groups.extend(gaps);
+	groups.sort_by_key(|group| group.faces.start);
+	groups
+}
+
+impl Obj {
+	/// Write the OBJ file.  When `mtllib` is given, the file references it
+	/// and each group uses the material of the same name from [Obj::write_mtl].
+	///
+	/// Texture coordinates are flipped vertically, as OBJ puts the origin in the bottom left.
+	pub fn write<W: Write>(&self, mut write: W, mtllib: Option<&str>) -> std::io::Result<()> {
+		let mesh = &self.mesh;
+		if let Some(mtllib) = mtllib {
+			writeln!(write, "mtllib {mtllib}")?;
+		}
+		for [x, y, z] in &mesh.positions {
+			writeln!(write, "v {x} {y} {z}")?;
+		}
+		for [u, v] in &mesh.uvs {
+			writeln!(write, "vt {u} {}", 1.0 - v)?;
+		}
+		for [x, y, z] in &mesh.normals {
+			writeln!(write, "vn {x} {y} {z}")?;
+		}
+		let has_uvs = !mesh.uvs.is_empty();
+		let has_normals = !mesh.normals.is_empty();
+		for group in &self.groups {
+			writeln!(write, "o {}", group.name)?;
+			if mtllib.is_some() {
+				writeln!(write, "usemtl {}", group.name)?;
+			}
+			for face in &mesh.indices[group.faces.clone()] {
+				write!(write, "f")?;
+				for index in face {
+					// obj indices are 1 based
+					let index = index + 1;
+					match (has_uvs, has_normals) {
+						(false, false) => write!(write, " {index}")?,
+						(true, false) => write!(write, " {index}/{index}")?,
+						(false, true) => write!(write, " {index}//{index}")?,
+						(true, true) => write!(write, " {index}/{index}/{index}")?,
+					}
+				}
+				writeln!(write)?;
+			}
+		}
+		Ok(())
+	}
+	/// Read an OBJ file.  Each `o` or `g` statement starts a new group, and
+	/// polygons are split into triangle fans.  The groups are not parts, so
+	/// converting the TriangleMesh into a mesh version produces a single lod.
+	///
+	/// Texture coordinates are flipped vertically to undo [Obj::write].
+	/// Materials, smoothing groups and other statements are ignored.
+	pub fn read<R: BufRead>(read: R) -> Result<Self, ObjError> {
+		let mut positions = Vec::new();
+		let mut uvs = Vec::new();
+		let mut normals = Vec::new();
+		// unique combinations of position, texture coordinate and normal
+		let mut corners: Vec<[Option<usize>; 3]> = Vec::new();
+		let mut corner_ids = HashMap::new();
+		let mut indices = Vec::new();
+		let mut groups: Vec<ObjGroup> = Vec::new();
+		let mut group_name = None;
+
+		for (line_number, line) in read.lines().enumerate() {
+			let line = line.map_err(ObjError::Io)?;
+			let line_number = line_number + 1;
+			let invalid = || ObjError::InvalidLine(line_number);
+			let mut words = line.split_whitespace();
+			let floats = |words: core::str::SplitWhitespace| {
+				words
+					.map(|word| word.parse::<f32>().map_err(|_| invalid()))
+					.collect::<Result<Vec<_>, _>>()
+			};
+			match words.next() {
+				Some("v") => match floats(words)?[..] {
+					[x, y, z, ..] => positions.push([x, y, z]),
+					_ => return Err(invalid()),
+				},
+				Some("vt") => match floats(words)?[..] {
+					[u] => uvs.push([u, 1.0]),
+					[u, v, ..] => uvs.push([u, 1.0 - v]),
+					_ => return Err(invalid()),
+				},
+				Some("vn") => match floats(words)?[..] {
+					[x, y, z] => normals.push([x, y, z]),
+					_ => return Err(invalid()),
+				},
+				Some("f") => {
+					let mut face = Vec::new();
+					for word in words {
+						// indices are 1 based, or negative to count back from the end
+						let resolve = |index: Option<&str>, len: usize| match index {
+							None | Some("") => Ok(None),
+							Some(index) => {
+								let index: i64 = index.parse().map_err(|_| invalid())?;
+								let resolved = match index {
+									1.. => index - 1,
+									..0 => len as i64 + index,
+									0 => -1,
+								};
+								match usize::try_from(resolved) {
+									Ok(resolved) if resolved < len => Ok(Some(resolved)),
+									_ => Err(ObjError::IndexOutOfRange(line_number)),
+								}
+							}
+						};
+						let mut parts = word.split('/');
+						let corner = [
+							resolve(parts.next(), positions.len())?,
+							resolve(parts.next(), uvs.len())?,
+							resolve(parts.next(), normals.len())?,
+						];
+						if corner[0].is_none() {
+							return Err(invalid());
+						}
+						let id = *corner_ids.entry(corner).or_insert_with(|| {
+							corners.push(corner);
+							corners.len() as u32 - 1
+						});
+						face.push(id);
+					}
+					if face.len() < 3 {
+						return Err(invalid());
+					}
+					if let Some(name) = group_name.take() {
+						groups.push(ObjGroup {
+							name,
+							faces: indices.len()..indices.len(),
+						});
+					} else if groups.is_empty() {
+						groups.push(ObjGroup {
+							name: "default".to_owned(),
+							faces: 0..0,
+						});
+					}
+					for i in 1..face.len() - 1 {
+						indices.push([face[0], face[i], face[i + 1]]);
+					}
+					if let Some(group) = groups.last_mut() {
+						group.faces.end = indices.len();
+					}
+				}
+				Some("o" | "g") => {
+					group_name = Some(words.collect::<Vec<_>>().join(" "));
+				}
+				_ => (),
+			}
+		}
+
+		let has_uvs = corners.iter().any(|corner| corner[1].is_some());
+		let has_normals = corners.iter().any(|corner| corner[2].is_some());
+		let mesh = TriangleMesh {
+			positions: corners
+				.iter()
+				.map(|corner| positions[corner[0].unwrap_or_default()])
+				.collect(),
+			normals: corners
+				.iter()
+				.filter(|_| has_normals)
+				.map(|corner| corner[2].map_or([0.0; 3], |normal| normals[normal]))
+				.collect(),
+			uvs: corners
+				.iter()
+				.filter(|_| has_uvs)
+				.map(|corner| corner[1].map_or([0.0; 2], |uv| uvs[uv]))
+				.collect(),
+			indices,
+			..Default::default()
+		};
+		Ok(Self { mesh, groups })
+	}
+	/// Write an MTL file with a plain white material for each group.
+	pub fn write_mtl<W: Write>(&self, mut write: W) -> std::io::Result<()> {
+		for group in &self.groups {
+			writeln!(write, "newmtl {}", group.name)?;
+			writeln!(write, "Kd 1 1 1")?;
+		}
+		Ok(())
+	}
+}
+
+/// One group per part.
+impl From<TriangleMesh> for Obj {
+	fn from(mesh: TriangleMesh) -> Self {
+		let groups = part_groups(&mesh, "part");
+		Self { mesh, groups }
+	}
+}
+
+#[cfg(feature = "mesh")]
+impl From<&crate::mesh::Mesh> for Obj {
+	fn from(mesh: &crate::mesh::Mesh) -> Self {
+		use crate::mesh::Mesh;
+		let subsets = match mesh {
+			Mesh::V4(mesh) => &mesh.subsets[..],
+			Mesh::V5(mesh) => &mesh.subsets,
+			Mesh::V6(mesh) => mesh
+				.skinning()
+				.map_or(&[][..], |skinning| &skinning.subsets),
+			Mesh::V7(mesh) => mesh
+				.skinning()
+				.map_or(&[][..], |skinning| &skinning.subsets),
+			_ => &[],
+		};
+		let mesh = TriangleMesh::from(mesh);
+		let groups = if subsets.is_empty() {
+			part_groups(&mesh, "lod")
+		} else {
+			subset_groups(&mesh, subsets)
+		};
+		Self { mesh, groups }
+	}
+}
+
+#[cfg(feature = "union-graphics")]
+impl From<&crate::union_graphics::UnionGraphics> for Obj {
+	fn from(mesh: &crate::union_graphics::UnionGraphics) -> Self {
+		let mesh = TriangleMesh::from(mesh);
+		let groups = part_groups(&mesh, "mesh");
+		Self { mesh, groups }
+	}
+}
+
+/// One group per physics mesh or convex hull.
+#[cfg(feature = "union-physics")]
+impl From<&crate::union_physics::UnionPhysics> for Obj {
+	fn from(mesh: &crate::union_physics::UnionPhysics) -> Self {
+		let mesh = TriangleMesh::from(mesh);
+		let groups = part_groups(&mesh, "hull");
+		Self { mesh, groups }
+	}
+}
+
+/// One group per convex hull.
+#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+impl From<&crate::union_physics::Hulls> for Obj {
+	fn from(hulls: &crate::union_physics::Hulls) -> Self {
+		let mesh = TriangleMesh::from(hulls);
+		let groups = part_groups(&mesh, "hull");
+		Self { mesh, groups }
+	}
+}
@@ -11,6 +11,44 @@ fn mesh_100() {
 	assert_eq!(mesh.revision, Revision1::Version100);
 	insta::assert_debug_snapshot!(mesh);
 }
+#[cfg(feature = "mesh-v1")]
+#[test]
+fn mesh_100_write() {
+	use crate::mesh::{FloatFormat1, Mesh1};
+	use binrw::BinWrite;
+	let bytes = read("meshes/158071912").unwrap();
+	let mesh = super::readonly::<Mesh1>(bytes.clone()).unwrap();
+
+	// Roblox's own formatting reproduces the file
+	let mut wbuf = std::io::Cursor::new(Vec::new());
+	mesh.write_options(&mut wbuf, binrw::Endian::Little, FloatFormat1::General(6))
+		.unwrap();
+	assert!(bytes == wbuf.into_inner(), "Round trip failed");
+
+	// the default formatting reproduces the vertices
+	let mut wbuf = std::io::Cursor::new(Vec::new());
+	mesh.write_options(&mut wbuf, binrw::Endian::Little, FloatFormat1::default())
+		.unwrap();
+	let rewritten = super::readonly::<Mesh1>(wbuf.into_inner()).unwrap();
+	assert_eq!(format!("{mesh:?}"), format!("{rewritten:?}"));
+}
+#[cfg(feature = "mesh-v1")]
+#[test]
+fn triangle_mesh_100() {
+	use crate::mesh::Mesh1;
+	use crate::triangle_mesh::{DroppedAttribute, ToTriangleMesh};
+	let bytes = read("meshes/158071912").unwrap();
+	let mut mesh = super::readonly::<Mesh1>(bytes).unwrap();
+	mesh.vertices
+		.iter_mut()
+		.for_each(|vertex| vertex.tex[2] = 0.0);
+	let converted = mesh.to_triangle_mesh();
+	assert!(converted.dropped.is_empty());
+	assert_eq!(converted.mesh.triangle_count() * 3, mesh.vertices.len());
+
+	mesh.vertices[0].tex[2] = 0.5;
+	assert_eq!(mesh.to_triangle_mesh().dropped, [DroppedAttribute::TexW]);
+}
 #[test]
 fn mesh_200() {
 	let bytes = read("meshes/torso.mesh").unwrap();
@@ -69,3 +107,1041 @@ fn mesh_500_alt2() {
 	insta::assert_debug_snapshot!(mesh);
 }
 //also tested against ~2500 meshes from bhop and surf maps
+/// Generated with the Mesh6 writer from meshes/13674780763, since no real mesh v6 is available.
+/// The HSRAVIS flags and the unknown chunk are made up to cover those chunks.
+#[test]
+fn mesh_600() {
+	use crate::mesh::{Chunk6, Mesh, Mesh6, Revision6};
+	use crate::triangle_mesh::{DroppedAttribute, ToTriangleMesh};
+	let bytes = read("meshes/13674780763").unwrap();
+	let mesh5 = super::readonly::<Mesh5>(bytes).unwrap();
+	let bytes = read("meshes/mesh6_from_13674780763.bin").unwrap();
+	let mesh6 = roundtrip::<Mesh6>(bytes.clone()).unwrap();
+	assert_eq!(mesh6.revision, Revision6::Version600);
+	assert!(matches!(
+		mesh6.chunks[..],
+		[
+			Chunk6::CoreMesh(_),
+			Chunk6::Lods(_),
+			Chunk6::Skinning(_),
+			Chunk6::Facs(_),
+			Chunk6::HsrAvis(_),
+			Chunk6::Unknown(_),
+		]
+	));
+	let core_mesh = mesh6.core_mesh().unwrap();
+	assert_eq!(
+		format!("{:?}", core_mesh.vertices),
+		format!("{:?}", mesh5.vertices)
+	);
+	assert_eq!(mesh6.lods().unwrap().lod_hq_count, mesh5.lod_hq_count);
+	assert_eq!(mesh6.skinning().unwrap().subsets.len(), mesh5.subsets.len());
+	assert_eq!(
+		mesh6.facs().unwrap().encoded_len() as u32,
+		mesh5.sizeof_facs
+	);
+	let hsr_avis = mesh6.hsr_avis().unwrap();
+	assert_eq!(hsr_avis.flag_count as usize, mesh5.faces.len());
+	assert!(hsr_avis.get(hsr_avis.flag_count).is_none());
+	let Some(Chunk6::Unknown(unknown)) = mesh6.chunks.last() else {
+		unreachable!()
+	};
+	assert_eq!(&unknown.chunk_type, b"UNKNOWN\0");
+
+	let dropped = mesh6.to_triangle_mesh().dropped;
+	assert_eq!(
+		dropped,
+		[
+			DroppedAttribute::Skinning,
+			DroppedAttribute::Facs,
+			DroppedAttribute::Chunks
+		]
+	);
+	let versioned = crate::read_mesh_versioned(std::io::Cursor::new(bytes)).unwrap();
+	assert!(matches!(versioned, Mesh::V6(_)));
+}
+#[test]
+fn mesh_600_layout() {
+	use crate::mesh::{
+		Chunk6, CoreMesh6, Face2, Lod3, LodType4, Lods6, Mesh6, Revision6, Vertex2, VertexId2,
+	};
+	let mesh6 = Mesh6 {
+		revision: Revision6::Version600,
+		chunks: vec![
+			Chunk6::CoreMesh(CoreMesh6 {
+				vertices: vec![Vertex2 {
+					pos: [1.0, 2.0, 3.0],
+					norm: [0.0, 1.0, 0.0],
+					tex: [0.5, 0.25],
+					tangent: [0, 0, -127, 127],
+					color: [255, 0, 0, 255],
+				}],
+				faces: vec![Face2([VertexId2(0), VertexId2(0), VertexId2(0)])],
+			}),
+			Chunk6::Lods(Lods6 {
+				lod_type: LodType4::ZeuxMeshOptimizer,
+				lod_hq_count: 1,
+				lods: vec![Lod3(0), Lod3(1)],
+			}),
+		],
+	};
+	let mut expected = b"version 6.00\n".to_vec();
+	// chunk type and version, then the size of the body:
+	// vertex count, one 40 byte vertex, face count and one 12 byte face
+	expected.extend_from_slice(b"COREMESH\x01\0\0\0");
+	expected.extend_from_slice(&60u32.to_le_bytes());
+	expected.extend_from_slice(&1u32.to_le_bytes());
+	for value in [1.0f32, 2.0, 3.0, 0.0, 1.0, 0.0, 0.5, 0.25] {
+		expected.extend_from_slice(&value.to_le_bytes());
+	}
+	expected.extend_from_slice(&[0x00, 0x00, 0x81, 0x7F, 0xFF, 0x00, 0x00, 0xFF]);
+	expected.extend_from_slice(&1u32.to_le_bytes());
+	expected.extend_from_slice(&[0; 12]);
+	// lod type, high quality count, lod count and two lods
+	expected.extend_from_slice(b"LODS\0\0\0\0\x01\0\0\0");
+	expected.extend_from_slice(&15u32.to_le_bytes());
+	expected.extend_from_slice(&3u16.to_le_bytes());
+	expected.push(1);
+	expected.extend_from_slice(&2u32.to_le_bytes());
+	expected.extend_from_slice(&0u32.to_le_bytes());
+	expected.extend_from_slice(&1u32.to_le_bytes());
+
+	assert_eq!(super::write(&mesh6).unwrap(), expected);
+	let mesh6 = roundtrip::<Mesh6>(expected).unwrap();
+	assert_eq!(mesh6.lods().unwrap().lods, [Lod3(0), Lod3(1)]);
+}
+#[test]
+fn mesh_700() {
+	use crate::mesh::{Mesh7, Revision7};
+	let bytes = read("meshes/mesh7_127279296594138.bin").unwrap();
+	let mesh = super::readonly::<Mesh7>(bytes).unwrap();
+	assert_eq!(mesh.revision, Revision7::Version700);
+	let core_mesh = mesh.core_mesh().unwrap();
+	assert_eq!(core_mesh.vertices.len(), 408);
+	assert_eq!(core_mesh.faces.len(), 268);
+	insta::assert_debug_snapshot!(mesh);
+}
+#[test]
+fn mesh_700_rewrite() {
+	use crate::mesh::Mesh7;
+	let bytes = read("meshes/mesh7_127279296594138.bin").unwrap();
+	let mesh = super::readonly::<Mesh7>(bytes).unwrap();
+	// the draco bitstream is written uncompressed, which round trips exactly
+	let wbuf = super::write(&mesh).unwrap();
+	let rewritten = roundtrip::<Mesh7>(wbuf).unwrap();
+	assert_eq!(format!("{mesh:?}"), format!("{rewritten:?}"));
+}
+/// Built by draco::quantized_cube_stream, since no draco produced mesh v7 with compressed
+/// indices and quantized positions is available.
+#[test]
+fn mesh_700_quantized() {
+	use crate::mesh::Mesh7;
+	let bytes = read("meshes/mesh7_quantized_cube.bin").unwrap();
+	let mesh = super::readonly::<Mesh7>(bytes).unwrap();
+	let core_mesh = mesh.core_mesh().unwrap();
+	assert_eq!(core_mesh.faces.len(), 12);
+	insta::assert_debug_snapshot!(core_mesh.vertices);
+}
+/// Generated with the Mesh7 writer from the core mesh and lods of meshes/5648093777,
+/// since no real mesh v7 with varying tangents and colors is available.
+#[test]
+fn mesh_700_from_300() {
+	use crate::mesh::{Mesh7, Vertices2};
+	let bytes = read("meshes/5648093777").unwrap();
+	let mesh3 = super::readonly::<Mesh3>(bytes).unwrap();
+	let Vertices2::Full(vertices) = &mesh3.vertices else {
+		panic!("5648093777 has colors")
+	};
+	let bytes = read("meshes/mesh7_from_5648093777.bin").unwrap();
+	let mesh7 = roundtrip::<Mesh7>(bytes).unwrap();
+	let core_mesh = mesh7.core_mesh().unwrap();
+	// tangents and colors differ between vertices
+	let first = &core_mesh.vertices[0];
+	assert!(
+		core_mesh
+			.vertices
+			.iter()
+			.any(|vertex| vertex.tangent != first.tangent)
+	);
+	assert!(
+		core_mesh
+			.vertices
+			.iter()
+			.any(|vertex| vertex.color != first.color)
+	);
+	assert_eq!(format!("{:?}", core_mesh.vertices), format!("{vertices:?}"));
+	assert_eq!(
+		format!("{:?}", core_mesh.faces),
+		format!("{:?}", mesh3.faces)
+	);
+	assert_eq!(mesh7.lods().unwrap().lods, mesh3.lods);
+}
+#[test]
+fn versioned_rewrite() {
+	use crate::{read_mesh_versioned, write_mesh_versioned};
+	for path in [
+		#[cfg(feature = "mesh-v1")]
+		"meshes/158071912",
+		"meshes/torso.mesh",
+		"meshes/5115672913",
+		"meshes/5648093777",
+		"meshes/sphere.mesh",
+		"meshes/7665777615",
+		"meshes/13674780763",
+		"meshes/14818281896",
+		"meshes/15256456161",
+		"meshes/mesh6_from_13674780763.bin",
+		"meshes/mesh7_127279296594138.bin",
+	] {
+		let bytes = read(path).unwrap();
+		let mesh = read_mesh_versioned(std::io::Cursor::new(bytes)).unwrap();
+		let mut wbuf = std::io::Cursor::new(Vec::new());
+		write_mesh_versioned(&mut wbuf, &mesh).unwrap();
+		wbuf.set_position(0);
+		let rewritten = read_mesh_versioned(wbuf).unwrap();
+		assert_eq!(format!("{mesh:?}"), format!("{rewritten:?}"), "{path}");
+	}
+}
+#[test]
+fn triangle_mesh_500() {
+	use crate::TriangleMesh;
+	let bytes = read("meshes/13674780763").unwrap();
+	let mesh = super::readonly::<Mesh5>(bytes).unwrap();
+	let triangle_mesh = TriangleMesh::from(&mesh);
+	assert_eq!(triangle_mesh.positions.len(), mesh.vertices.len());
+	assert_eq!(triangle_mesh.tangents.len(), mesh.vertices.len());
+	assert_eq!(triangle_mesh.indices.len(), mesh.faces.len());
+	// one part per lod
+	assert_eq!(triangle_mesh.parts.len(), mesh.lods.len() - 1);
+	assert_eq!(
+		triangle_mesh.iter_parts().map(<[_]>::len).sum::<usize>(),
+		mesh.faces.len()
+	);
+	for (vertex, tangent) in mesh.vertices.iter().zip(&triangle_mesh.tangents) {
+		assert_eq!(
+			crate::triangle_mesh::tangent_to_i8(*tangent),
+			vertex.tangent
+		);
+	}
+	assert_eq!(triangle_mesh.lod_hq_count, Some(mesh.lod_hq_count));
+}
+#[test]
+fn triangle_mesh_dropped() {
+	use crate::mesh::Lod3;
+	use crate::triangle_mesh::{DroppedAttribute, ToTriangleMesh};
+	let bytes = read("meshes/13674780763").unwrap();
+	let mut mesh = super::readonly::<Mesh5>(bytes).unwrap();
+	let converted = mesh.to_triangle_mesh();
+	assert_eq!(
+		converted.dropped,
+		[DroppedAttribute::Skinning, DroppedAttribute::Facs]
+	);
+
+	// the faces are kept, but a decreasing lod cannot be
+	let face_count = mesh.faces.len() as u32;
+	mesh.lods = vec![Lod3(0), Lod3(face_count / 2), Lod3(1), Lod3(face_count)];
+	let converted = mesh.to_triangle_mesh();
+	assert!(converted.dropped.contains(&DroppedAttribute::Lods));
+	assert_eq!(
+		converted.mesh.parts,
+		[
+			0..face_count as usize / 2,
+			face_count as usize / 2..face_count as usize
+		]
+	);
+
+	// lods which stop short of the last face
+	mesh.lods = vec![Lod3(0), Lod3(face_count / 2)];
+	let converted = mesh.to_triangle_mesh();
+	assert!(converted.dropped.contains(&DroppedAttribute::Lods));
+	assert_eq!(converted.mesh.triangle_count(), mesh.faces.len());
+}
+#[test]
+fn triangle_mesh_to_versions() {
+	use crate::TriangleMesh;
+	use crate::mesh::Vertices2;
+	use crate::triangle_mesh::DroppedAttribute;
+	let bytes = read("meshes/13674780763").unwrap();
+	let mesh = super::readonly::<Mesh5>(bytes).unwrap();
+	let triangle_mesh = TriangleMesh::from(&mesh);
+
+	let mesh2 = triangle_mesh.to_mesh2().unwrap();
+	assert_eq!(mesh2.dropped, [DroppedAttribute::Parts]);
+	assert!(matches!(mesh2.mesh.vertices, Vertices2::Full(_)));
+	let bytes = super::write(&mesh2.mesh).unwrap();
+	roundtrip::<Mesh2>(bytes).unwrap();
+
+	let mesh3 = triangle_mesh.to_mesh3().unwrap();
+	assert!(mesh3.dropped.is_empty());
+	assert_eq!(mesh3.mesh.lods, mesh.lods);
+	let bytes = super::write(&mesh3.mesh).unwrap();
+	roundtrip::<Mesh3>(bytes).unwrap();
+
+	let mesh4 = triangle_mesh.to_mesh4().unwrap();
+	let bytes = super::write(&mesh4.mesh).unwrap();
+	roundtrip::<Mesh4>(bytes).unwrap();
+
+	let mesh5 = triangle_mesh.to_mesh5().unwrap();
+	assert_eq!(
+		format!("{:?}", mesh5.mesh.vertices),
+		format!("{:?}", mesh.vertices)
+	);
+	assert_eq!(
+		format!("{:?}", mesh5.mesh.faces),
+		format!("{:?}", mesh.faces)
+	);
+	assert_eq!(mesh5.mesh.lods, mesh.lods);
+	let bytes = super::write(&mesh5.mesh).unwrap();
+	let rewritten = roundtrip::<Mesh5>(bytes).unwrap();
+	assert_eq!(TriangleMesh::from(&rewritten), triangle_mesh);
+
+	// without colors the vertices are truncated
+	let triangle_mesh = TriangleMesh {
+		colors: Vec::new(),
+		..triangle_mesh
+	};
+	let mesh2 = triangle_mesh.to_mesh2().unwrap();
+	assert!(matches!(mesh2.mesh.vertices, Vertices2::Truncated(_)));
+	assert_eq!(mesh2.dropped, [DroppedAttribute::Parts]);
+	let mesh4 = triangle_mesh.to_mesh4().unwrap();
+	assert_eq!(mesh4.dropped, [DroppedAttribute::DefaultColors]);
+
+	// lod_hq_count is carried from the source, or only the first level is high quality
+	assert_eq!(mesh4.mesh.lod_hq_count, mesh.lod_hq_count);
+	let triangle_mesh = TriangleMesh {
+		tangents: Vec::new(),
+		lod_hq_count: None,
+		..triangle_mesh
+	};
+	let mesh5 = triangle_mesh.to_mesh5().unwrap();
+	assert_eq!(mesh5.mesh.lod_hq_count, 1);
+	assert_eq!(
+		mesh5.dropped,
+		[
+			DroppedAttribute::DefaultTangents,
+			DroppedAttribute::DefaultColors
+		]
+	);
+}
+#[test]
+fn obj_500() {
+	use crate::mesh::Mesh;
+	use crate::obj::Obj;
+	let bytes = read("meshes/13674780763").unwrap();
+	let mesh = Mesh::V5(super::readonly::<Mesh5>(bytes).unwrap());
+	let Mesh::V5(mesh5) = &mesh else {
+		unreachable!()
+	};
+	let obj = Obj::from(&mesh);
+	// every face is written exactly once
+	let mut end = 0;
+	for group in &obj.groups {
+		assert_eq!(group.faces.start, end);
+		end = group.faces.end;
+	}
+	assert_eq!(end, mesh5.faces.len());
+	let subset_groups = obj
+		.groups
+		.iter()
+		.filter(|group| group.name.starts_with("subset"))
+		.count();
+	assert_eq!(subset_groups, mesh5.subsets.len());
+
+	let mut wbuf = Vec::new();
+	obj.write(&mut wbuf, Some("13674780763.mtl")).unwrap();
+	let text = String::from_utf8(wbuf).unwrap();
+	let count = |prefix: &str| text.lines().filter(|line| line.starts_with(prefix)).count();
+	assert_eq!(count("v "), mesh5.vertices.len());
+	assert_eq!(count("vt "), mesh5.vertices.len());
+	assert_eq!(count("vn "), mesh5.vertices.len());
+	assert_eq!(count("f "), mesh5.faces.len());
+	assert_eq!(count("o "), obj.groups.len());
+	assert_eq!(count("usemtl "), obj.groups.len());
+
+	let mut wbuf = Vec::new();
+	obj.write_mtl(&mut wbuf).unwrap();
+	let text = String::from_utf8(wbuf).unwrap();
+	assert_eq!(
+		text.lines()
+			.filter(|line| line.starts_with("newmtl "))
+			.count(),
+		obj.groups.len()
+	);
+}
+#[test]
+fn glb_500() {
+	use crate::gltf::Glb;
+	let bytes = read("meshes/13674780763").unwrap();
+	let mesh = super::readonly::<Mesh5>(bytes).unwrap();
+	let glb = Glb::from(&mesh);
+	let skin = glb.skin.as_ref().unwrap();
+	assert_eq!(skin.joints.len(), mesh.bones.len());
+	assert_eq!(skin.joints[0].name, "Root");
+	assert_eq!(skin.joints[2].parent, Some(1));
+	for weights in &skin.vertex_weights {
+		assert!((weights.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+	}
+	// controls followed by the two and three pose correctives
+	assert_eq!(glb.morph_targets.len(), 50 + 54 + 17);
+	let moved = |name: &str| {
+		let target = glb
+			.morph_targets
+			.iter()
+			.find(|target| target.name == name)
+			.unwrap();
+		target
+			.position_deltas
+			.iter()
+			.filter(|delta| 1e-3 < delta.iter().map(|d| d.abs()).sum::<f32>())
+			.count()
+	};
+	// jaw drop opens the mouth
+	assert_ne!(moved("c_JD"), 0);
+	// the morph target is the mesh posed with the control alone
+	let rig = mesh.facs.decode().unwrap();
+	let mut control_weights = vec![0.0; rig.controls.len()];
+	control_weights[rig.find_control("c_JD").unwrap()] = 1.0;
+	let posed = mesh.pose(&rig.pose(&mesh.skeleton(), &control_weights));
+	let jaw_drop = glb
+		.morph_targets
+		.iter()
+		.find(|target| target.name == "c_JD")
+		.unwrap();
+	for ((delta, posed), vertex) in jaw_drop
+		.position_deltas
+		.iter()
+		.zip(&posed.positions)
+		.zip(&mesh.vertices)
+	{
+		for axis in 0..3 {
+			assert!((vertex.pos[axis] + delta[axis] - posed[axis]).abs() < 1e-4);
+		}
+	}
+
+	let mut wbuf = Vec::new();
+	glb.write(&mut wbuf).unwrap();
+	assert_eq!(&wbuf[0..4], b"glTF");
+	let u32_at = |offset: usize| u32::from_le_bytes(wbuf[offset..offset + 4].try_into().unwrap());
+	assert_eq!(u32_at(8) as usize, wbuf.len());
+	let json_len = u32_at(12) as usize;
+	assert_eq!(&wbuf[16..20], b"JSON");
+	let json = std::str::from_utf8(&wbuf[20..20 + json_len]).unwrap();
+	assert!(json.contains("\"JOINTS_0\""));
+	assert!(json.contains("\"inverseBindMatrices\""));
+	assert!(json.contains("\"c_JD\""));
+	let bin_len = u32_at(20 + json_len) as usize;
+	assert_eq!(&wbuf[24 + json_len..28 + json_len], b"BIN\0");
+	assert_eq!(28 + json_len + bin_len, wbuf.len());
+}
+#[test]
+fn obj_import_500() {
+	use crate::mesh::Mesh;
+	use crate::obj::Obj;
+	let bytes = read("meshes/13674780763").unwrap();
+	let mesh = Mesh::V5(super::readonly::<Mesh5>(bytes).unwrap());
+	let obj = Obj::from(&mesh);
+	let mut wbuf = Vec::new();
+	obj.write(&mut wbuf, None).unwrap();
+	let imported = Obj::read(wbuf.as_slice()).unwrap();
+	assert_eq!(imported.groups.len(), obj.groups.len());
+	assert_eq!(imported.mesh.indices.len(), obj.mesh.indices.len());
+	for (face, imported_face) in obj.mesh.indices.iter().zip(&imported.mesh.indices) {
+		for (&index, &imported_index) in face.iter().zip(imported_face) {
+			let (index, imported_index) = (index as usize, imported_index as usize);
+			assert_eq!(
+				obj.mesh.positions[index],
+				imported.mesh.positions[imported_index]
+			);
+			assert_eq!(
+				obj.mesh.normals[index],
+				imported.mesh.normals[imported_index]
+			);
+		}
+	}
+	let mesh5 = imported.mesh.to_mesh5().unwrap().mesh;
+	let bytes = super::write(&mesh5).unwrap();
+	roundtrip::<Mesh5>(bytes).unwrap();
+}
+#[test]
+fn glb_import_500() {
+	use crate::gltf::Glb;
+	use crate::triangle_mesh::DroppedAttribute;
+	let bytes = read("meshes/13674780763").unwrap();
+	let mesh = super::readonly::<Mesh5>(bytes).unwrap();
+	let glb = Glb::from(&mesh);
+	let mut wbuf = Vec::new();
+	glb.write(&mut wbuf).unwrap();
+	let imported = Glb::read(&wbuf).unwrap();
+	assert_eq!(imported.mesh.parts, glb.mesh.parts);
+
+	// FACS poses cannot be recovered from morph targets
+	let dropped = glb.to_mesh5().unwrap().dropped;
+	assert!(dropped.contains(&DroppedAttribute::MorphTargets));
+
+	let converted = imported.to_mesh5().unwrap();
+	assert!(converted.dropped.is_empty());
+	let bytes = super::write(&converted.mesh).unwrap();
+	let mesh5 = roundtrip::<Mesh5>(bytes).unwrap();
+	assert_eq!(mesh5.bones.len(), mesh.bones.len());
+	assert_eq!(mesh5.bone_names, mesh.bone_names);
+	assert_eq!(mesh5.lods, mesh.lods);
+	assert!(mesh5.subsets.iter().all(|subset| subset.bone_count <= 26));
+
+	// every corner keeps its position and bone weights
+	let reimported = Glb::from(&mesh5);
+	let (skin, reskin) = (glb.skin.unwrap(), reimported.skin.unwrap());
+	let weights = |skin: &crate::gltf::GlbSkin, vertex: usize| {
+		let mut weights = vec![0.0; skin.joints.len()];
+		for (&joint, &weight) in skin.vertex_joints[vertex]
+			.iter()
+			.zip(&skin.vertex_weights[vertex])
+		{
+			weights[joint as usize] += weight;
+		}
+		weights
+	};
+	for (face, reface) in glb.mesh.indices.iter().zip(&reimported.mesh.indices) {
+		for (&vertex, &revertex) in face.iter().zip(reface) {
+			let (vertex, revertex) = (vertex as usize, revertex as usize);
+			assert_eq!(
+				glb.mesh.positions[vertex],
+				reimported.mesh.positions[revertex]
+			);
+			for (weight, reweight) in weights(&skin, vertex)
+				.iter()
+				.zip(weights(&reskin, revertex))
+			{
+				assert!((weight - reweight).abs() < 1.0 / 255.0);
+			}
+		}
+	}
+}
+#[test]
+fn skeleton_500() {
+	let bytes = read("meshes/13674780763").unwrap();
+	let mesh = super::readonly::<Mesh5>(bytes).unwrap();
+	let skeleton = mesh.skeleton();
+	assert_eq!(skeleton.len(), mesh.bones.len());
+	assert_eq!(skeleton.bone_name(0), Some("Root"));
+	assert_eq!(skeleton.find("Root"), Some(0));
+	assert_eq!(skeleton.roots().collect::<Vec<_>>(), [0]);
+	assert!(skeleton.children(0).any(|child| child == 1));
+
+	// every parent is visited before its children
+	let order: Vec<u16> = skeleton.depth_first().collect();
+	assert_eq!(order.len(), skeleton.len());
+	for (i, &id) in order.iter().enumerate() {
+		if let Some(parent) = skeleton.parent(id) {
+			assert!(order[..i].contains(&parent));
+			// the local transform composes back into the world transform
+			let world =
+				skeleton.world_cframe(parent).unwrap() * &skeleton.local_cframe(id).unwrap();
+			let expected = skeleton.world_cframe(id).unwrap();
+			let position = world.position();
+			for (a, b) in position.iter().zip(expected.position()) {
+				assert!((a - b).abs() < 1e-3);
+			}
+		}
+	}
+
+	let vertex_weights = skeleton.vertex_weights();
+	assert_eq!(vertex_weights.len(), mesh.vertices.len());
+	for subset in &mesh.subsets {
+		let start = subset.vertices_offset as usize;
+		let end = start + subset.vertices_len as usize;
+		for weights in vertex_weights[start..end].iter().flatten() {
+			assert!((weights.weights.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+			for (&bone, &weight) in weights.bones.iter().zip(&weights.weights) {
+				if weight != 0.0 {
+					assert!(subset.bones.iter().any(|id| id.get() == Some(bone)));
+				}
+			}
+		}
+	}
+}
+#[test]
+fn pose_500() {
+	use crate::mesh::{CFrame4, Pose};
+	let bytes = read("meshes/13674780763").unwrap();
+	let mesh = super::readonly::<Mesh5>(bytes).unwrap();
+	let close = |a: &[f32; 3], b: &[f32; 3]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-3);
+
+	// the bind pose leaves the mesh unchanged
+	let bind = mesh.pose(&Pose::new());
+	assert_eq!(bind.positions.len(), mesh.vertices.len());
+	for (vertex, (position, normal)) in mesh
+		.vertices
+		.iter()
+		.zip(bind.positions.iter().zip(&bind.normals))
+	{
+		assert!(close(&vertex.pos, position));
+		assert!(close(&vertex.norm, normal));
+	}
+
+	// moving the root moves every skinned vertex with it
+	let root = mesh.skeleton().world_cframe(0).unwrap();
+	let mut pose = Pose::new();
+	pose.insert(
+		"Root".to_owned(),
+		&CFrame4::from_position([0.0, 10.0, 0.0]) * root,
+	);
+	let weights = mesh.skeleton().vertex_weights();
+	let raised = mesh.pose(&pose);
+	for (i, vertex) in mesh.vertices.iter().enumerate() {
+		if weights[i].is_some() {
+			let [x, y, z] = vertex.pos;
+			assert!(close(&[x, y + 10.0, z], &raised.positions[i]));
+			assert!(close(&vertex.norm, &raised.normals[i]));
+		}
+	}
+}
+#[test]
+fn facs_500() {
+	let bytes = read("meshes/13674780763").unwrap();
+	let mesh = super::readonly::<Mesh5>(bytes).unwrap();
+	let rig = mesh.facs.decode().unwrap();
+	assert_eq!(rig.controls.len(), 50);
+	assert_eq!(rig.poses.len(), 50 + 54 + 17);
+	assert_eq!(rig.poses[50].controls.len(), 2);
+	assert_eq!(rig.poses[50 + 54].controls.len(), 3);
+
+	// no weights is the bind pose
+	let neutral = rig.evaluate(&[]);
+	assert_eq!(neutral.len(), rig.face_bones.len());
+	assert!(neutral.iter().all(|offset| *offset == Default::default()));
+
+	// a corrective adds the product of its control weights
+	let corrective = &rig.poses[50];
+	let mut weights = vec![0.0; rig.controls.len()];
+	for &control in &corrective.controls {
+		weights[control] = 0.5;
+	}
+	let [a, b] = [0, 1].map(|i| &rig.poses[corrective.controls[i]]);
+	for (face_bone, offset) in rig.evaluate(&weights).iter().enumerate() {
+		for axis in 0..3 {
+			let expected = 0.5 * a.offsets[face_bone].position[axis]
+				+ 0.5 * b.offsets[face_bone].position[axis]
+				+ 0.25 * corrective.offsets[face_bone].position[axis];
+			assert!((offset.position[axis] - expected).abs() < 1e-5);
+		}
+	}
+
+	// jaw drop opens the mouth
+	let jaw_drop = rig.find_control("c_JD").unwrap();
+	let mut weights = vec![0.0; rig.controls.len()];
+	weights[jaw_drop] = 1.0;
+	let pose = rig.pose(&mesh.skeleton(), &weights);
+	assert!(!pose.is_empty());
+	let posed = mesh.pose(&pose);
+	let moved = mesh
+		.vertices
+		.iter()
+		.zip(&posed.positions)
+		.filter(|(vertex, position)| {
+			vertex
+				.pos
+				.iter()
+				.zip(position.iter())
+				.any(|(a, b)| 1e-3 < (a - b).abs())
+		})
+		.count();
+	assert_ne!(moved, 0);
+}
+#[test]
+fn lod_levels_500() {
+	let bytes = read("meshes/13674780763").unwrap();
+	let mesh = super::readonly::<Mesh5>(bytes).unwrap();
+	let levels = mesh.lod_levels().unwrap();
+	assert_eq!(levels.len(), mesh.lods.len().max(2) - 1);
+	assert_eq!(
+		levels.iter().map(|lod| lod.faces.len()).sum::<usize>(),
+		mesh.faces.len()
+	);
+	for lod in &levels {
+		assert_eq!(lod.high_quality, lod.level < mesh.lod_hq_count as usize);
+		assert_eq!(lod.vertices.len(), lod.vertex_ids.len());
+		// the compacted faces point at the same vertices
+		for (face, compacted) in lod.faces.iter().zip(&lod.compacted_faces) {
+			for (vertex, compacted) in face.0.iter().zip(&compacted.0) {
+				assert_eq!(lod.vertex_ids[compacted.0 as usize], *vertex);
+				let compacted = &lod.vertices[compacted.0 as usize];
+				assert_eq!(compacted.pos, mesh.vertices[vertex.0 as usize].pos);
+			}
+		}
+	}
+}
+#[test]
+fn lod_levels_300() {
+	let bytes = read("meshes/5115672913").unwrap();
+	let mesh = super::readonly::<Mesh3>(bytes).unwrap();
+	let levels = mesh.lod_levels().unwrap();
+	assert!(levels[0].high_quality);
+	assert!(levels[1..].iter().all(|lod| !lod.high_quality));
+	assert_eq!(
+		levels.iter().map(|lod| lod.faces.len()).sum::<usize>(),
+		mesh.faces.len()
+	);
+}
+#[test]
+fn generate_lods_500() {
+	use crate::mesh::{LodOptions, LodType4};
+	let bytes = read("meshes/13674780763").unwrap();
+	let mut mesh = super::readonly::<Mesh5>(bytes).unwrap();
+	let base_faces = mesh.lods[1].0 as usize;
+	mesh.generate_lods(&LodOptions::default()).unwrap();
+	assert!(matches!(mesh.lod_type, LodType4::Unknown));
+	assert_eq!(mesh.lod_hq_count, 1);
+
+	let levels = mesh.lod_levels().unwrap();
+	assert_eq!(levels.len(), 4);
+	assert!(levels[1..].iter().all(|lod| !lod.high_quality));
+	assert_eq!(levels[0].faces.len(), base_faces);
+	for lods in levels.windows(2) {
+		assert!(lods[1].faces.len() < lods[0].faces.len());
+	}
+	// subsets never span levels, and their faces stay inside their vertices
+	let lods: Vec<u32> = mesh.lods.iter().map(|lod| lod.0).collect();
+	let mut vertices_end = 0;
+	for subset in &mesh.subsets {
+		let faces = subset.faces_offset..subset.faces_offset + subset.faces_len;
+		assert!(
+			lods.windows(2)
+				.any(|lod| lod[0] <= faces.start && faces.end <= lod[1])
+		);
+		let vertices = subset.vertices_offset..subset.vertices_offset + subset.vertices_len;
+		assert!(vertices_end <= vertices.start);
+		vertices_end = vertices.end;
+		for face in &mesh.faces[faces.start as usize..faces.end as usize] {
+			assert!(face.0.iter().all(|vertex| vertices.contains(&vertex.0)));
+		}
+	}
+	assert_eq!(mesh.envelopes.len(), mesh.vertices.len());
+	assert!(mesh.skeleton().vertex_weights().iter().all(Option::is_some));
+
+	// the new lods can be written
+	let mut wbuf = std::io::Cursor::new(Vec::new());
+	binrw::BinWrite::write(&mesh, &mut wbuf).unwrap();
+	let rmesh = super::readonly::<Mesh5>(wbuf.into_inner()).unwrap();
+	assert_eq!(rmesh.lods, mesh.lods);
+}
+#[test]
+fn generate_lods_face_outside_subset() {
+	use crate::mesh::{LodError, LodOptions, VertexId2};
+	let bytes = read("meshes/13674780763").unwrap();
+	let mut mesh = super::readonly::<Mesh5>(bytes).unwrap();
+	// a face of the first subset which uses a vertex of the second
+	let vertex = mesh.subsets[1].vertices_offset;
+	let face = mesh.subsets[0].faces_offset as usize;
+	mesh.faces[face].0[0] = VertexId2(vertex);
+	let original = format!("{mesh:?}");
+	assert_eq!(
+		mesh.generate_lods(&LodOptions::default()),
+		Err(LodError::VertexOutOfRange(vertex))
+	);
+	// the mesh is left as it was
+	assert_eq!(format!("{mesh:?}"), original);
+}
+#[test]
+fn generate_lods_300() {
+	use crate::mesh::LodOptions;
+	let bytes = read("meshes/5115672913").unwrap();
+	let mut mesh = super::readonly::<Mesh3>(bytes).unwrap();
+	let base_faces = mesh.lod_levels().unwrap()[0].faces.len();
+	let options = LodOptions {
+		level_count: 3,
+		..LodOptions::default()
+	};
+	mesh.generate_lods(&options).unwrap();
+	let levels = mesh.lod_levels().unwrap();
+	assert_eq!(levels[0].faces.len(), base_faces);
+	assert!(levels.len() <= 3);
+	for lods in levels.windows(2) {
+		assert!(lods[1].faces.len() < lods[0].faces.len());
+	}
+}
+#[test]
+fn validate_500() {
+	use crate::mesh::{BoneId4, VertexId2};
+	use crate::validate::{DiagnosticKind, Location, Severity, has_errors};
+	let bytes = read("meshes/13674780763").unwrap();
+	let mut mesh = super::readonly::<Mesh5>(bytes).unwrap();
+	assert_eq!(mesh.validate(), []);
+
+	let vertex_count = mesh.vertices.len() as u32;
+	mesh.faces[3].0[1] = VertexId2(vertex_count);
+	mesh.vertices[7].pos[0] = f32::NAN;
+	mesh.envelopes[9].weights[0] = mesh.envelopes[9].weights[0].wrapping_add(1);
+	mesh.bones[2].bone_name_pos = mesh.bone_names.len() as u32;
+	mesh.bones[0].parent = BoneId4::new(Some(2));
+	mesh.subsets[1].vertices_offset = 0;
+	let diagnostics = mesh.validate();
+	assert!(has_errors(&diagnostics));
+	let has = |location: Location, kind: DiagnosticKind| {
+		diagnostics
+			.iter()
+			.any(|diagnostic| diagnostic.location == location && diagnostic.kind == kind)
+	};
+	assert!(has(
+		Location::Face(3),
+		DiagnosticKind::FaceIndexOutOfRange(vertex_count)
+	));
+	assert!(has(Location::Vertex(7), DiagnosticKind::NonFinite));
+	assert!(has(
+		Location::Envelope(9),
+		DiagnosticKind::EnvelopeWeightSum(256)
+	));
+	assert!(has(
+		Location::Bone(2),
+		DiagnosticKind::BoneNameOutOfRange(mesh.bone_names.len() as u32)
+	));
+	assert!(has(Location::Bone(0), DiagnosticKind::BoneCycle));
+	assert!(has(Location::Subset(1), DiagnosticKind::SubsetOverlap(0)));
+	// each subset is reported at most once
+	let overlaps = diagnostics
+		.iter()
+		.filter(|diagnostic| matches!(diagnostic.kind, DiagnosticKind::SubsetOverlap(_)))
+		.count();
+	assert_eq!(overlaps, 1);
+	let weight_sum = diagnostics
+		.iter()
+		.find(|diagnostic| matches!(diagnostic.kind, DiagnosticKind::EnvelopeWeightSum(_)))
+		.unwrap();
+	assert_eq!(weight_sum.severity(), Severity::Warning);
+}
+#[test]
+fn validate_bone_cycle() {
+	use crate::mesh::BoneId4;
+	use crate::validate::{DiagnosticKind, Location};
+	let bytes = read("meshes/13674780763").unwrap();
+	let mut mesh = super::readonly::<Mesh5>(bytes).unwrap();
+	// a long chain which ends in a loop of the last two bones
+	let bone_count = mesh.bones.len();
+	for (i, bone) in mesh.bones.iter_mut().enumerate() {
+		bone.parent = BoneId4::new(Some((i + 1).min(bone_count - 1) as u16));
+	}
+	mesh.bones[bone_count - 1].parent = BoneId4::new(Some(bone_count as u16 - 2));
+	let cycles: Vec<_> = mesh
+		.validate()
+		.into_iter()
+		.filter(|diagnostic| diagnostic.kind == DiagnosticKind::BoneCycle)
+		.map(|diagnostic| diagnostic.location)
+		.collect();
+	assert_eq!(
+		cycles,
+		[
+			Location::Bone(bone_count - 2),
+			Location::Bone(bone_count - 1)
+		]
+	);
+}
+#[test]
+fn validate_nan() {
+	use crate::validate::DiagnosticKind;
+	// this mesh really does have NaN vertices
+	let bytes = read("meshes/5648093777").unwrap();
+	let mesh = crate::read_mesh_versioned(std::io::Cursor::new(bytes)).unwrap();
+	let diagnostics = mesh.validate();
+	assert!(
+		diagnostics
+			.iter()
+			.any(|diagnostic| diagnostic.kind == DiagnosticKind::NonFinite)
+	);
+}
+#[test]
+fn error_kind() {
+	use crate::Format;
+	use crate::error::ErrorKind;
+	let read = |bytes: &[u8]| crate::read_mesh_versioned(std::io::Cursor::new(bytes)).unwrap_err();
+
+	let error = read(b"version 9.00\n");
+	assert!(matches!(error.kind, ErrorKind::NoVariantMatch));
+	assert_eq!(error.pos, Some(0));
+
+	let bytes = std::fs::read("meshes/sphere.mesh").unwrap();
+	let error = read(&bytes[..bytes.len() / 2]);
+	assert!(matches!(error.kind, ErrorKind::UnexpectedEof));
+	assert_eq!(error.pos, Some(bytes.len() as u64 / 2));
+	assert_eq!(error.format, Some(Format::Mesh { major: 4, minor: 1 }));
+	assert_eq!(
+		error.context,
+		[
+			"While parsing field 'self_0' in Mesh::V4",
+			"While parsing field 'vertices' in Mesh4",
+			"While parsing field 'tex' in Vertex2",
+		]
+	);
+	assert_eq!(error.field(), Some("While parsing field 'tex' in Vertex2"));
+}
+#[cfg(feature = "mesh-v1")]
+#[test]
+fn error_kind_100() {
+	use crate::error::ErrorKind;
+	use crate::mesh::Error1;
+	// the second vertex has a bad tex coordinate
+	let bytes = b"version 1.00\r\n1\r\n[1,2,3][1,2,3][1,2,3][1,2,x][1,2,3][1,2,3]";
+	let error = crate::read_mesh_versioned(std::io::Cursor::new(bytes)).unwrap_err();
+	assert!(matches!(
+		error.kind,
+		ErrorKind::Mesh1(Error1::ParseFloatError(_))
+	));
+	assert_eq!(error.pos, Some(38));
+}
+#[test]
+fn read_limits() {
+	use crate::error::ErrorKind;
+	use crate::limits::{LimitError, ReadLimits};
+	let read = |bytes: &[u8], limits| {
+		crate::read_mesh_versioned_with_limits(std::io::Cursor::new(bytes), limits).unwrap_err()
+	};
+
+	// a hostile vertex_count is rejected before anything is allocated for it
+	let mut bytes = std::fs::read("meshes/sphere.mesh").unwrap();
+	bytes[17..21].copy_from_slice(&u32::MAX.to_le_bytes());
+	let error = read(&bytes, ReadLimits::DEFAULT);
+	assert!(matches!(
+		error.kind,
+		ErrorKind::Limit(LimitError::Vertices(0xFFFF_FFFF))
+	));
+
+	let bytes = std::fs::read("meshes/sphere.mesh").unwrap();
+	let limits = ReadLimits {
+		max_faces: 16,
+		..ReadLimits::DEFAULT
+	};
+	let error = read(&bytes, limits);
+	assert!(matches!(error.kind, ErrorKind::Limit(LimitError::Faces(_))));
+	let limits = ReadLimits {
+		max_alloc_bytes: 1024,
+		..ReadLimits::DEFAULT
+	};
+	let error = read(&bytes, limits);
+	assert!(matches!(
+		error.kind,
+		ErrorKind::Limit(LimitError::AllocBytes(_))
+	));
+	crate::read_mesh_versioned_with_limits(std::io::Cursor::new(bytes), ReadLimits::DEFAULT)
+		.unwrap();
+}
+#[test]
+fn borrowed_4() {
+	use crate::mesh::{Mesh4, Mesh4Ref};
+	let bytes = std::fs::read("meshes/sphere.mesh").unwrap();
+	let mesh = Mesh4Ref::parse(&bytes).unwrap();
+	let owned = super::readonly::<Mesh4>(bytes.clone()).unwrap();
+	let arrays = mesh.arrays;
+	assert_eq!(arrays.vertices.len(), owned.vertices.len());
+	assert_eq!(
+		format!("{:?}", arrays.vertices.iter().collect::<Vec<_>>()),
+		format!("{:?}", owned.vertices)
+	);
+	assert_eq!(
+		format!("{:?}", arrays.faces.iter().collect::<Vec<_>>()),
+		format!("{:?}", owned.faces)
+	);
+	assert_eq!(
+		format!("{:?}", arrays.lods.iter().collect::<Vec<_>>()),
+		format!("{:?}", owned.lods)
+	);
+	assert_eq!(
+		format!("{:?}", arrays.vertices.get(1)),
+		format!("{:?}", owned.vertices.get(1))
+	);
+	assert!(arrays.vertices.get(arrays.vertices.len()).is_none());
+	assert!(
+		arrays
+			.vertices
+			.positions()
+			.eq(owned.vertices.iter().map(|v| v.pos))
+	);
+
+	assert!(Mesh4Ref::parse(&bytes[..bytes.len() - 1]).is_err());
+}
+#[test]
+fn borrowed_5() {
+	use crate::mesh::{Mesh5, Mesh5Ref};
+	let bytes = std::fs::read("meshes/13674780763").unwrap();
+	let mesh = Mesh5Ref::parse(&bytes).unwrap();
+	let owned = super::readonly::<Mesh5>(bytes.clone()).unwrap();
+	let arrays = mesh.arrays;
+	for (borrowed, owned) in [
+		(
+			format!("{:?}", arrays.vertices.iter().collect::<Vec<_>>()),
+			format!("{:?}", owned.vertices),
+		),
+		(
+			format!("{:?}", arrays.envelopes.iter().collect::<Vec<_>>()),
+			format!("{:?}", owned.envelopes),
+		),
+		(
+			format!("{:?}", arrays.faces.iter().collect::<Vec<_>>()),
+			format!("{:?}", owned.faces),
+		),
+		(
+			format!("{:?}", arrays.bones.iter().collect::<Vec<_>>()),
+			format!("{:?}", owned.bones),
+		),
+		(
+			format!("{:?}", arrays.subsets.iter().rev().collect::<Vec<_>>()),
+			format!("{:?}", owned.subsets.iter().rev().collect::<Vec<_>>()),
+		),
+		(
+			format!("{:?}", mesh.facs().unwrap()),
+			format!("{:?}", owned.facs),
+		),
+	] {
+		assert_eq!(borrowed, owned);
+	}
+	assert!(!arrays.bones.is_empty() && !arrays.subsets.is_empty());
+	assert_eq!(arrays.bone_names, owned.bone_names);
+	assert_eq!(mesh.facs.len() as u32, owned.sizeof_facs);
+}
+#[test]
+fn read_mesh_header() {
+	use crate::mesh::MeshHeader;
+	let read = |path| {
+		let bytes = std::fs::read(path).unwrap();
+		crate::read_mesh_header(std::io::Cursor::new(bytes)).unwrap()
+	};
+	#[cfg(feature = "mesh-v1")]
+	assert_eq!(
+		read("meshes/158071912"),
+		MeshHeader {
+			major: 1,
+			minor: 0,
+			vertex_count: 3 * 1388,
+			face_count: 1388,
+			lod_count: 0,
+			bone_count: 0,
+			has_facs: false,
+		}
+	);
+	let header = read("meshes/torso.mesh");
+	assert_eq!((header.major, header.minor), (2, 0));
+	let header = read("meshes/5648093777");
+	assert_eq!((header.major, header.minor), (3, 1));
+	let header = read("meshes/sphere.mesh");
+	let mesh = super::readonly::<crate::mesh::Mesh4>(std::fs::read("meshes/sphere.mesh").unwrap())
+		.unwrap();
+	assert_eq!(
+		header,
+		MeshHeader {
+			major: 4,
+			minor: 1,
+			vertex_count: mesh.vertices.len() as u32,
+			face_count: mesh.faces.len() as u32,
+			lod_count: mesh.lods.len() as u16,
+			bone_count: 0,
+			has_facs: false,
+		}
+	);
+	let header = read("meshes/13674780763");
+	let mesh = super::readonly::<crate::mesh::Mesh5>(std::fs::read("meshes/13674780763").unwrap())
+		.unwrap();
+	assert_eq!(header.vertex_count, mesh.vertices.len() as u32);
+	assert_eq!(header.bone_count, mesh.bones.len() as u16);
+	assert!(header.has_facs);
+
+	// versions without a fixed header are not supported
+	let bytes = std::fs::read("meshes/mesh7_127279296594138.bin").unwrap();
+	assert!(crate::read_mesh_header(std::io::Cursor::new(bytes)).is_err());
+}
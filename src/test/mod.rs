@@ -5,53 +5,130 @@ mod union_graphics;
 #[cfg(feature = "union-physics")]
 mod union_physics;
 
-use binrw::{BinRead, BinReaderExt, BinWrite, BinWriterExt};
-use std::io::Cursor;
+use binrw::{BinRead, BinWrite};
 
-fn binread<M>(bytes: Vec<u8>) -> binrw::BinResult<(M, Cursor<Vec<u8>>)>
-where
-	M: std::fmt::Debug,
-	M: for<'a> BinRead<Args<'a> = ()>,
-{
-	let bytes_len = bytes.len() as u64;
+// the same helpers as the fuzz targets, with the input compared byte for byte
+use crate::fuzz::{read, write};
 
-	let mut rbuf = Cursor::new(bytes);
-	let mesh: M = rbuf.read_le()?;
-	assert_eq!(rbuf.position(), bytes_len, "Unread data in file");
-
-	Ok((mesh, rbuf))
-}
-fn binwrite<M>(mesh: &M) -> binrw::BinResult<Cursor<Vec<u8>>>
+fn binread<M>(bytes: &[u8]) -> binrw::BinResult<M>
 where
 	M: std::fmt::Debug,
-	M: for<'a> BinWrite<Args<'a> = ()>,
+	M: for<'a> BinRead<Args<'a>: Default>,
 {
-	let mut wbuf = Cursor::new(Vec::new());
-	wbuf.write_le(mesh)?;
-	Ok(wbuf)
+	let (mesh, position) = read(bytes)?;
+	assert_eq!(position, bytes.len() as u64, "Unread data in file");
+	Ok(mesh)
 }
 
 #[cfg_attr(
-	not(any(feature = "mesh-v1", feature = "union-graphics")),
+	not(any(
+		feature = "mesh",
+		feature = "union-graphics",
+		feature = "union-physics",
+	)),
 	expect(unused)
 )]
 pub fn readonly<M>(bytes: Vec<u8>) -> binrw::BinResult<M>
 where
 	M: std::fmt::Debug,
-	M: for<'a> BinRead<Args<'a> = ()>,
+	M: for<'a> BinRead<Args<'a>: Default>,
 {
-	let (mesh, _rbuf) = binread(bytes)?;
-	Ok(mesh)
+	binread(&bytes)
 }
+#[cfg_attr(
+	not(any(
+		feature = "mesh",
+		feature = "union-graphics",
+		feature = "union-physics",
+	)),
+	expect(unused)
+)]
 pub fn roundtrip<M>(bytes: Vec<u8>) -> binrw::BinResult<M>
 where
 	M: std::fmt::Debug,
-	M: for<'a> BinRead<Args<'a> = ()>,
+	M: for<'a> BinRead<Args<'a>: Default>,
 	M: for<'a> BinWrite<Args<'a> = ()>,
 {
-	let (mesh, rbuf) = binread(bytes)?;
-	let wbuf = binwrite(&mesh)?;
+	let mesh = binread(&bytes)?;
+	let written = write(&mesh)?;
 
-	assert_eq!(rbuf, wbuf, "Round trip failed");
+	assert_eq!(bytes, written, "Round trip failed");
 	Ok(mesh)
 }
+
+#[test]
+fn detect_format() {
+	use crate::{Format, detect_format};
+	for (path, format) in [
+		("meshes/158071912", Format::Mesh { major: 1, minor: 0 }),
+		("meshes/torso.mesh", Format::Mesh { major: 2, minor: 0 }),
+		("meshes/5648093777", Format::Mesh { major: 3, minor: 1 }),
+		("meshes/sphere.mesh", Format::Mesh { major: 4, minor: 1 }),
+		("meshes/13674780763", Format::Mesh { major: 5, minor: 0 }),
+		(
+			"meshes/mesh7_127279296594138.bin",
+			Format::Mesh { major: 7, minor: 0 },
+		),
+		("meshes/385416572.meshdata", Format::UnionGraphics(2)),
+		("meshes/4500696697_4.meshdata", Format::UnionGraphics(4)),
+		("meshes/13626979828.meshdata5", Format::UnionGraphics(5)),
+		("meshes/CSGK.data", Format::CSGK),
+		("meshes/CSGPHS_3.data", Format::UnionPhysics(3)),
+		("meshes/CSGPHS_7.data", Format::UnionPhysics(7)),
+		("meshes/CSGPHS_8_00.data", Format::UnionPhysics(8)),
+	] {
+		let bytes = std::fs::read(path).unwrap();
+		assert_eq!(detect_format(&bytes), Some(format), "{path}");
+		assert!(!format.features().is_empty(), "{path}");
+	}
+}
+
+#[cfg(all(
+	feature = "mesh",
+	feature = "union-graphics",
+	feature = "union-physics"
+))]
+#[test]
+fn read_any() {
+	use crate::error::ErrorKind;
+	use crate::{AnyMesh, Format, read_any};
+	for path in [
+		"meshes/sphere.mesh",
+		"meshes/13626979828.meshdata5",
+		"meshes/CSGK.data",
+		"meshes/CSGPHS_7.data",
+	] {
+		let bytes = std::fs::read(path).unwrap();
+		let mesh = read_any(std::io::Cursor::new(bytes)).unwrap();
+		match (path, mesh) {
+			("meshes/sphere.mesh", AnyMesh::Mesh(_))
+			| ("meshes/13626979828.meshdata5", AnyMesh::UnionGraphics(_))
+			| ("meshes/CSGK.data", AnyMesh::CSGK(_))
+			| ("meshes/CSGPHS_7.data", AnyMesh::UnionPhysics(_)) => (),
+			(path, mesh) => panic!("{path} read as {mesh:?}"),
+		}
+	}
+	let error = read_any(std::io::Cursor::new(b"CSGPHS\x09\0\0\0")).unwrap_err();
+	assert!(matches!(
+		error.kind,
+		ErrorKind::UnsupportedVersion(Format::UnionPhysics(9))
+	));
+	let error = read_any(std::io::Cursor::new(b"not a mesh")).unwrap_err();
+	assert!(matches!(error.kind, ErrorKind::UnknownFormat));
+}
+
+#[cfg(all(
+	any(feature = "mesh", feature = "union-graphics"),
+	not(feature = "union-physics")
+))]
+#[test]
+fn read_any_feature_not_enabled() {
+	use crate::error::ErrorKind;
+	use crate::{Format, read_any};
+	let bytes = std::fs::read("meshes/CSGPHS_7.data").unwrap();
+	let error = read_any(std::io::Cursor::new(bytes)).unwrap_err();
+	assert!(matches!(
+		error.kind,
+		ErrorKind::FeatureNotEnabled(Format::UnionPhysics(7))
+	));
+}
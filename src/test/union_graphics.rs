@@ -1,4 +1,4 @@
-use super::{readonly, roundtrip};
+use super::roundtrip;
 use crate::union_graphics::{CSGMDL2, CSGMDL4, CSGMDL5};
 use std::fs::read;
 
@@ -29,18 +29,101 @@ fn meshdata_4500696697_4() {
 #[test]
 fn meshdata_15124417947_5() {
 	let bytes = read("meshes/15124417947_5.meshdata").unwrap();
-	let mesh = readonly::<CSGMDL5>(bytes).unwrap();
+	let mesh = roundtrip::<CSGMDL5>(bytes).unwrap();
 	insta::assert_debug_snapshot!(mesh);
 }
 #[test]
 fn meshdata_14846974687_5() {
 	let bytes = read("meshes/14846974687_5.meshdata").unwrap();
-	let mesh = readonly::<CSGMDL5>(bytes).unwrap();
+	let mesh = roundtrip::<CSGMDL5>(bytes).unwrap();
 	insta::assert_debug_snapshot!(mesh);
 }
 #[test]
 fn meshdata_13626979828_5() {
 	let bytes = read("meshes/13626979828.meshdata5").unwrap();
-	let mesh = readonly::<CSGMDL5>(bytes).unwrap();
+	let mesh = roundtrip::<CSGMDL5>(bytes).unwrap();
 	insta::assert_debug_snapshot!(mesh);
 }
+#[test]
+fn versioned_roundtrip() {
+	use crate::{read_union_graphics_versioned, write_union_graphics_versioned};
+	for path in [
+		"meshes/385416572.meshdata",
+		"meshes/394453730.meshdata",
+		"meshes/5692112940_2.meshdata",
+		"meshes/4500696697_4.meshdata",
+		"meshes/15124417947_5.meshdata",
+		"meshes/14846974687_5.meshdata",
+		"meshes/13626979828.meshdata5",
+	] {
+		let bytes = read(path).unwrap();
+		let mesh = read_union_graphics_versioned(std::io::Cursor::new(&bytes)).unwrap();
+		let mut wbuf = std::io::Cursor::new(Vec::new());
+		write_union_graphics_versioned(&mut wbuf, &mesh).unwrap();
+		assert!(bytes == wbuf.into_inner(), "{path}");
+	}
+}
+#[test]
+fn triangle_mesh_5() {
+	use crate::TriangleMesh;
+	let bytes = read("meshes/15124417947_5.meshdata").unwrap();
+	let mesh = super::readonly::<CSGMDL5>(bytes).unwrap();
+	let triangle_mesh = TriangleMesh::from(&mesh);
+	assert_eq!(triangle_mesh.positions, mesh.positions);
+	assert_eq!(triangle_mesh.indices.len() * 3, mesh.faces.indices.len());
+	assert_eq!(triangle_mesh.parts.len(), 1);
+	assert_eq!(triangle_mesh.parts[0], 0..triangle_mesh.indices.len());
+
+	// the unknown index lists are reported, and so is a partial trailing face
+	use crate::triangle_mesh::{DroppedAttribute, ToTriangleMesh};
+	let mut mesh = mesh;
+	mesh.faces._unknown.clear();
+	assert!(mesh.to_triangle_mesh().dropped.is_empty());
+	mesh.faces._unknown.push(vec![0, 1, 2]);
+	mesh.faces.indices.push(0);
+	assert_eq!(
+		mesh.to_triangle_mesh().dropped,
+		[DroppedAttribute::Unknown, DroppedAttribute::PartialFace]
+	);
+}
+#[test]
+fn error_kind_5() {
+	use crate::error::ErrorKind;
+	use crate::union_graphics::RangeMarkerError;
+	let mut bytes = read("meshes/13626979828.meshdata5").unwrap();
+	// the last 16 bytes are the final four range markers
+	let len = bytes.len();
+	bytes[len - 16..len - 12].copy_from_slice(&0x2000u32.to_le_bytes());
+	let error = crate::read_union_graphics_versioned(std::io::Cursor::new(bytes)).unwrap_err();
+	assert!(matches!(
+		error.kind,
+		ErrorKind::RangeMarker(RangeMarkerError::OutOfRange {
+			marker: 1,
+			value: 0x2000
+		})
+	));
+	assert_eq!(error.pos, Some(36327));
+	assert_eq!(error.format, Some(crate::Format::UnionGraphics(5)));
+}
+#[test]
+fn validate_5() {
+	use crate::validate::{DiagnosticKind, Location};
+	let bytes = read("meshes/13626979828.meshdata5").unwrap();
+	let mut mesh = super::readonly::<CSGMDL5>(bytes).unwrap();
+	assert_eq!(mesh.validate(), []);
+
+	let vertex_count = mesh.positions.len() as u32;
+	mesh.faces._unknown.push(vec![0, 1, vertex_count, 2]);
+	let unknown = mesh.faces._unknown.len() - 1;
+	let diagnostics = mesh.validate();
+	assert!(diagnostics.contains(&crate::validate::Diagnostic {
+		kind: DiagnosticKind::FaceIndexOutOfRange(vertex_count),
+		location: Location::UnknownFaces(unknown),
+		mesh: None,
+	}));
+	assert!(diagnostics.contains(&crate::validate::Diagnostic {
+		kind: DiagnosticKind::IncompleteFace,
+		location: Location::UnknownFaces(unknown),
+		mesh: None,
+	}));
+}
@@ -50,3 +50,178 @@ fn csgphs_8_raw_hull_2() {
 	let mesh = super::readonly::<CSGPHS8>(bytes).unwrap();
 	insta::assert_debug_snapshot!(mesh);
 }
+#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+#[test]
+fn csgphs_8_rewrite() {
+	use crate::union_physics::CSGPHS8;
+	for path in [
+		"meshes/CSGPHS_8_00.data",
+		"meshes/CSGPHS_8_01.data",
+		"meshes/CSGPHS_8_02.data",
+		"meshes/CSGPHS_8_03.data",
+		"meshes/CSGPHS_8_04.data",
+		"meshes/CSGPHS_8_05.data",
+		"meshes/CSGPHS_8_raw_hulls_206.data",
+		"meshes/CSGPHS_8_raw_hulls_972.data",
+	] {
+		let bytes = read(path).unwrap();
+		let mesh = super::readonly::<CSGPHS8>(bytes.clone()).unwrap();
+		let wbuf = super::write(&mesh).unwrap();
+		// the zstd frame is not reproduced exactly, but the decompressed data is
+		#[cfg(feature = "csgphs-v8-zstd")]
+		{
+			// magic, geom_type and padding
+			const HEADER_LEN: usize = 12;
+			assert_eq!(bytes[..HEADER_LEN], wbuf[..HEADER_LEN], "{path}");
+			let original = zstd::stream::decode_all(&bytes[HEADER_LEN..]).unwrap();
+			let rewritten = zstd::stream::decode_all(&wbuf[HEADER_LEN..]).unwrap();
+			assert!(original == rewritten, "{path}");
+		}
+		let rewritten = super::readonly::<CSGPHS8>(wbuf).unwrap();
+		assert_eq!(format!("{mesh:?}"), format!("{rewritten:?}"), "{path}");
+	}
+}
+#[test]
+fn versioned_roundtrip() {
+	use crate::{read_union_physics_versioned, write_union_physics_versioned};
+	for path in [
+		"meshes/CSGK.data",
+		"meshes/CSGPHS_3.data",
+		"meshes/CSGPHS_5.data",
+		"meshes/CSGPHS_7.data",
+	] {
+		let bytes = read(path).unwrap();
+		let mesh = read_union_physics_versioned(std::io::Cursor::new(&bytes)).unwrap();
+		let mut wbuf = std::io::Cursor::new(Vec::new());
+		write_union_physics_versioned(&mut wbuf, &mesh).unwrap();
+		assert!(bytes == wbuf.into_inner(), "{path}");
+	}
+}
+#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+#[test]
+fn versioned_rewrite_8() {
+	use crate::union_physics::UnionPhysics;
+	use crate::{read_union_physics_versioned, write_union_physics_versioned};
+	let bytes = read("meshes/CSGPHS_8_00.data").unwrap();
+	let mesh = read_union_physics_versioned(std::io::Cursor::new(bytes)).unwrap();
+	assert!(matches!(mesh, UnionPhysics::V8(_)));
+	let mut wbuf = std::io::Cursor::new(Vec::new());
+	write_union_physics_versioned(&mut wbuf, &mesh).unwrap();
+	wbuf.set_position(0);
+	let rewritten = read_union_physics_versioned(wbuf).unwrap();
+	assert_eq!(format!("{mesh:?}"), format!("{rewritten:?}"));
+}
+#[test]
+fn triangle_mesh_7() {
+	use crate::TriangleMesh;
+	let bytes = read("meshes/CSGPHS_7.data").unwrap();
+	let mesh = roundtrip::<CSGPHS7>(bytes).unwrap();
+	let triangle_mesh = TriangleMesh::from(&mesh);
+	// one part per physics mesh
+	assert_eq!(triangle_mesh.parts.len(), mesh.meshes.len());
+	for (part, physics_mesh) in triangle_mesh.iter_parts().zip(&mesh.meshes) {
+		assert_eq!(part.len(), physics_mesh.faces.len());
+	}
+	let vertex_count = triangle_mesh.vertex_count() as u32;
+	assert!(
+		triangle_mesh
+			.indices
+			.as_flattened()
+			.iter()
+			.all(|&index| index < vertex_count)
+	);
+}
+#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+#[test]
+fn obj_8() {
+	use crate::obj::Obj;
+	use crate::union_physics::CSGPHS8;
+	let bytes = read("meshes/CSGPHS_8_00.data").unwrap();
+	let mesh = super::readonly::<CSGPHS8>(bytes).unwrap();
+	let hulls = &mesh.mesh.hulls;
+	let obj = Obj::from(hulls);
+	// one group per hull
+	assert_eq!(obj.groups.len(), hulls.iter_hulls().len());
+	let mut wbuf = Vec::new();
+	obj.write(&mut wbuf, None).unwrap();
+	let text = String::from_utf8(wbuf).unwrap();
+	let count = |prefix: &str| text.lines().filter(|line| line.starts_with(prefix)).count();
+	assert_eq!(count("o hull"), obj.groups.len());
+	assert_eq!(count("v "), obj.mesh.positions.len());
+	assert_eq!(count("f "), obj.mesh.indices.len());
+	assert_eq!(count("vn "), 0);
+	assert_eq!(count("usemtl "), 0);
+}
+#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+#[test]
+fn validate_8() {
+	use crate::union_physics::CSGPHS8;
+	use crate::validate::{DiagnosticKind, Location};
+	let bytes = read("meshes/CSGPHS_8_00.data").unwrap();
+	let mut mesh = super::readonly::<CSGPHS8>(bytes).unwrap();
+	assert_eq!(mesh.validate(), []);
+
+	// a face of the second hull points past its positions
+	let hulls = &mut mesh.mesh.hulls;
+	let face_start = hulls.face_ranges[1] as usize;
+	hulls.faces[face_start] = u32::MAX;
+	let diagnostics = mesh.validate();
+	assert_eq!(diagnostics.len(), 1);
+	assert_eq!(diagnostics[0].mesh, Some(1));
+	assert_eq!(diagnostics[0].location, Location::Face(0));
+
+	// ranges past the end are caught before the hulls are sliced
+	let hulls = &mut mesh.mesh.hulls;
+	*hulls.pos_ranges.last_mut().unwrap() += 3;
+	assert_eq!(mesh.validate()[0].kind, DiagnosticKind::HullRanges);
+}
+#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+#[test]
+fn read_limits_8() {
+	use crate::error::ErrorKind;
+	use crate::limits::{LimitError, ReadLimits};
+	let bytes = read("meshes/CSGPHS_8_00.data").unwrap();
+	let limits = ReadLimits {
+		max_decompressed_bytes: 64,
+		..ReadLimits::DEFAULT
+	};
+	let error =
+		crate::read_union_physics_versioned_with_limits(std::io::Cursor::new(bytes), limits)
+			.unwrap_err();
+	assert!(matches!(
+		error.kind,
+		ErrorKind::Limit(LimitError::DecompressedBytes(65))
+	));
+}
+#[test]
+fn read_limits_7() {
+	use crate::error::ErrorKind;
+	use crate::limits::{LimitError, ReadLimits};
+	let bytes = read("meshes/CSGPHS_7.data").unwrap();
+	let mesh = super::readonly::<CSGPHS7>(bytes.clone()).unwrap();
+	let alloc_bytes: Vec<u64> = mesh
+		.meshes
+		.iter()
+		.map(|mesh| 12 * (mesh.positions.len() + mesh.faces.len()) as u64)
+		.collect();
+	let total: u64 = alloc_bytes.iter().sum();
+	assert!(alloc_bytes.iter().all(|&bytes| bytes < total));
+
+	// max_alloc_bytes is shared by every mesh in the file
+	let limits = ReadLimits {
+		max_alloc_bytes: total - 1,
+		..ReadLimits::DEFAULT
+	};
+	let error =
+		crate::read_union_physics_versioned_with_limits(std::io::Cursor::new(&bytes), limits)
+			.unwrap_err();
+	assert!(matches!(
+		error.kind,
+		ErrorKind::Limit(LimitError::AllocBytes(_))
+	));
+	let limits = ReadLimits {
+		max_alloc_bytes: total,
+		..ReadLimits::DEFAULT
+	};
+	crate::read_union_physics_versioned_with_limits(std::io::Cursor::new(&bytes), limits).unwrap();
+}
@@ -0,0 +1,755 @@
+//! A version agnostic view of the geometry stored in every mesh and union format.
+//! The versioned structs reflect the on-disk format, while TriangleMesh is a
+//! plain indexed triangle list which tools can be built on.
+
+use core::ops::Range;
+
+#[cfg(feature = "mesh")]
+use crate::mesh::{Face2, Lod3, Vertex2, Vertices2};
+
+const TANGENT_SCALE: f32 = 127.0;
+
+/// Convert a `[i8; 4]` tangent into the -1.0..=1.0 range.
+pub fn tangent_from_i8(tangent: [i8; 4]) -> [f32; 4] {
+	tangent.map(|value| value as f32 / TANGENT_SCALE)
+}
+/// Convert a tangent in the -1.0..=1.0 range into the `[i8; 4]` layout.
+pub fn tangent_to_i8(tangent: [f32; 4]) -> [i8; 4] {
+	tangent.map(|value| {
+		(value * TANGENT_SCALE)
+			.round()
+			.clamp(i8::MIN as f32, i8::MAX as f32) as i8
+	})
+}
+
+/// An indexed triangle mesh.
+///
+/// Every attribute other than positions is either empty, when the source
+/// format does not store it, or has one entry per position.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TriangleMesh {
+	pub positions: Vec<[f32; 3]>,
+	pub normals: Vec<[f32; 3]>,
+	pub uvs: Vec<[f32; 2]>,
+	/// RGBA
+	pub colors: Vec<[u8; 4]>,
+	/// Tangent xyz and bitangent sign w, in the -1.0..=1.0 range.
+	/// Unions store no bitangent sign, so w is 1.0 for them.
+	pub tangents: Vec<[f32; 4]>,
+	/// 0 based indices into positions
+	pub indices: Vec<[u32; 3]>,
+	/// Ranges of indices which the source format keeps apart, in order and without gaps:
+	/// one per level of detail for meshes, one per physics mesh or convex hull for unions.
+	pub parts: Vec<Range<usize>>,
+	/// The number of leading parts which are high quality levels of detail,
+	/// when the source format stores it.
+	pub lod_hq_count: Option<u8>,
+}
+
+impl TriangleMesh {
+	pub fn vertex_count(&self) -> usize {
+		self.positions.len()
+	}
+	pub fn triangle_count(&self) -> usize {
+		self.indices.len()
+	}
+	/// Iterate the triangles of each part.
+	pub fn iter_parts(&self) -> impl ExactSizeIterator<Item = &[[u32; 3]]> {
+		self.parts.iter().map(|range| &self.indices[range.clone()])
+	}
+	/// Append positions and triangles with their own 0 based indices as a new part.
+	/// Other attributes are not extended.
+	pub fn push_part(&mut self, positions: &[[f32; 3]], faces: impl Iterator<Item = [u32; 3]>) {
+		let vertex_offset = self.positions.len() as u32;
+		let start = self.indices.len();
+		self.positions.extend_from_slice(positions);
+		self.indices
+			.extend(faces.map(|face| face.map(|index| index + vertex_offset)));
+		self.parts.push(start..self.indices.len());
+	}
+	#[cfg(any(feature = "mesh-v1", feature = "union-graphics"))]
+	fn whole_part(&mut self) {
+		if !self.indices.is_empty() {
+			self.parts.push(0..self.indices.len());
+		}
+	}
+}
+
+/// Data which a conversion between a TriangleMesh and a versioned format cannot carry.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DroppedAttribute {
+	/// Mesh2 has no levels of detail, every part is kept as one list of faces.
+	Parts,
+	/// Morph targets cannot be turned back into FACS data.
+	MorphTargets,
+	/// The third texture coordinate of Mesh1, which is not zero.
+	TexW,
+	/// Lod offsets which are out of range, do not increase or do not cover every face.
+	/// The faces are kept, but the parts no longer match the lods.
+	Lods,
+	/// Bones, envelopes and subsets.  Glb keeps them as a skin.
+	Skinning,
+	/// FACS data.  Glb keeps it as morph targets.
+	Facs,
+	/// Mesh6 and Mesh7 chunks which hold no geometry, such as HSRAVIS and unknown chunks.
+	Chunks,
+	/// The CSGMDL4 list and the CSGMDL5 index lists after the primary one, whose meaning is unknown.
+	Unknown,
+	/// Trailing indices which do not make up a whole triangle.
+	PartialFace,
+	/// The target stores normals but the TriangleMesh has none, so zeroes are written.
+	DefaultNormals,
+	/// The target stores uvs but the TriangleMesh has none, so zeroes are written.
+	DefaultUvs,
+	/// The target stores tangents but the TriangleMesh has none, so Roblox's default tangent is written.
+	DefaultTangents,
+	/// The target stores colors but the TriangleMesh has none, so white is written.
+	DefaultColors,
+}
+
+/// The result of a conversion, and what it could not carry.
+#[derive(Debug, Clone, Default)]
+pub struct Converted<M> {
+	pub mesh: M,
+	pub dropped: Vec<DroppedAttribute>,
+}
+impl<M> Converted<M> {
+	fn new(mesh: M) -> Self {
+		Self {
+			mesh,
+			dropped: Vec::new(),
+		}
+	}
+	#[cfg(any(feature = "mesh", feature = "union-graphics"))]
+	fn drop_if(mut self, condition: bool, attribute: DroppedAttribute) -> Self {
+		if condition {
+			self.dropped.push(attribute);
+		}
+		self
+	}
+}
+
+/// Convert into a TriangleMesh, reporting what it cannot carry.
+/// `TriangleMesh::from` does the same conversion without the report.
+pub trait ToTriangleMesh {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh>;
+}
+impl<T: ToTriangleMesh> From<&T> for TriangleMesh {
+	fn from(mesh: &T) -> Self {
+		mesh.to_triangle_mesh().mesh
+	}
+}
+
+#[cfg(feature = "mesh")]
+fn faces2(faces: &[Face2]) -> Vec<[u32; 3]> {
+	faces
+		.iter()
+		.map(|Face2(face)| face.clone().map(|vertex_id| vertex_id.0))
+		.collect()
+}
+
+/// Lods are face offsets, each pair of which is a part.
+/// Faces which are not covered by the lods are kept as their own parts.
+/// Also returns whether the parts differ from the lods.
+#[cfg(feature = "mesh")]
+fn lod_parts(lods: &[Lod3], face_count: usize) -> (Vec<Range<usize>>, bool) {
+	let mut bounds = vec![0];
+	let mut lossy = lods.first().is_some_and(|&Lod3(first)| first != 0);
+	for (i, &Lod3(offset)) in lods.iter().enumerate() {
+		let offset = offset as usize;
+		lossy |= face_count < offset;
+		let offset = offset.min(face_count);
+		if bounds.last().is_some_and(|&last| last < offset) {
+			bounds.push(offset);
+		} else if i != 0 {
+			lossy = true;
+		}
+	}
+	if bounds.last().is_some_and(|&last| last < face_count) {
+		lossy |= !lods.is_empty();
+		bounds.push(face_count);
+	}
+	let parts = bounds
+		.windows(2)
+		.map(|bounds| bounds[0]..bounds[1])
+		.collect();
+	(parts, lossy)
+}
+
+#[cfg(feature = "mesh")]
+fn from_vertex2(
+	vertices: &[Vertex2],
+	faces: &[Face2],
+	lods: &[Lod3],
+	lod_hq_count: Option<u8>,
+) -> Converted<TriangleMesh> {
+	let indices = faces2(faces);
+	let (parts, lossy_lods) = lod_parts(lods, indices.len());
+	Converted::new(TriangleMesh {
+		positions: vertices.iter().map(|vertex| vertex.pos).collect(),
+		normals: vertices.iter().map(|vertex| vertex.norm).collect(),
+		uvs: vertices.iter().map(|vertex| vertex.tex).collect(),
+		colors: vertices.iter().map(|vertex| vertex.color).collect(),
+		tangents: vertices
+			.iter()
+			.map(|vertex| tangent_from_i8(vertex.tangent))
+			.collect(),
+		indices,
+		parts,
+		lod_hq_count,
+	})
+	.drop_if(lossy_lods, DroppedAttribute::Lods)
+}
+
+#[cfg(feature = "mesh")]
+fn from_vertices2(vertices: &Vertices2, faces: &[Face2], lods: &[Lod3]) -> Converted<TriangleMesh> {
+	match vertices {
+		Vertices2::Full(vertices) => from_vertex2(vertices, faces, lods, None),
+		Vertices2::Truncated(vertices) => {
+			let indices = faces2(faces);
+			let (parts, lossy_lods) = lod_parts(lods, indices.len());
+			Converted::new(TriangleMesh {
+				positions: vertices.iter().map(|vertex| vertex.pos).collect(),
+				normals: vertices.iter().map(|vertex| vertex.norm).collect(),
+				uvs: vertices.iter().map(|vertex| vertex.tex).collect(),
+				colors: Vec::new(),
+				tangents: vertices
+					.iter()
+					.map(|vertex| tangent_from_i8(vertex.tangent))
+					.collect(),
+				indices,
+				parts,
+				lod_hq_count: None,
+			})
+			.drop_if(lossy_lods, DroppedAttribute::Lods)
+		}
+	}
+}
+
+#[cfg(feature = "mesh")]
+fn has_facs(facs: &crate::mesh::Facs5) -> bool {
+	!facs.face_control_names.is_empty()
+}
+
+/// Mesh1 is not indexed, so every vertex is its own position.
+/// The third texture coordinate is not kept.
+#[cfg(feature = "mesh-v1")]
+impl ToTriangleMesh for crate::mesh::Mesh1 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		let vertices = &self.vertices;
+		let mut triangle_mesh = TriangleMesh {
+			positions: vertices.iter().map(|vertex| vertex.pos).collect(),
+			normals: vertices.iter().map(|vertex| vertex.norm).collect(),
+			uvs: vertices
+				.iter()
+				.map(|vertex| [vertex.tex[0], vertex.tex[1]])
+				.collect(),
+			indices: (0..vertices.len() as u32 / 3)
+				.map(|face| [3 * face, 3 * face + 1, 3 * face + 2])
+				.collect(),
+			..Default::default()
+		};
+		triangle_mesh.whole_part();
+		Converted::new(triangle_mesh).drop_if(
+			vertices.iter().any(|vertex| vertex.tex[2] != 0.0),
+			DroppedAttribute::TexW,
+		)
+	}
+}
+#[cfg(feature = "mesh")]
+impl ToTriangleMesh for crate::mesh::Mesh2 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		from_vertices2(&self.vertices, &self.faces, &[])
+	}
+}
+#[cfg(feature = "mesh")]
+impl ToTriangleMesh for crate::mesh::Mesh3 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		from_vertices2(&self.vertices, &self.faces, &self.lods)
+	}
+}
+/// Bones, envelopes and subsets are not kept, Glb keeps them as a skin.
+#[cfg(feature = "mesh")]
+impl ToTriangleMesh for crate::mesh::Mesh4 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		from_vertex2(
+			&self.vertices,
+			&self.faces,
+			&self.lods,
+			Some(self.lod_hq_count),
+		)
+		.drop_if(!self.bones.is_empty(), DroppedAttribute::Skinning)
+	}
+}
+/// Bones, envelopes, subsets and FACS data are not kept, Glb keeps them as a skin and morph targets.
+#[cfg(feature = "mesh")]
+impl ToTriangleMesh for crate::mesh::Mesh5 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		from_vertex2(
+			&self.vertices,
+			&self.faces,
+			&self.lods,
+			Some(self.lod_hq_count),
+		)
+		.drop_if(!self.bones.is_empty(), DroppedAttribute::Skinning)
+		.drop_if(has_facs(&self.facs), DroppedAttribute::Facs)
+	}
+}
+/// Only the core mesh and lods chunks are kept.
+#[cfg(feature = "mesh")]
+impl ToTriangleMesh for crate::mesh::Mesh6 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		use crate::mesh::Chunk6;
+		let (lods, lod_hq_count) = self.lods().map_or((&[][..], None), |lods| {
+			(&lods.lods[..], Some(lods.lod_hq_count))
+		});
+		let converted = self
+			.core_mesh()
+			.map_or_else(Converted::default, |core_mesh| {
+				from_vertex2(&core_mesh.vertices, &core_mesh.faces, lods, lod_hq_count)
+			});
+		converted
+			.drop_if(
+				self.skinning()
+					.is_some_and(|skinning| !skinning.bones.is_empty()),
+				DroppedAttribute::Skinning,
+			)
+			.drop_if(self.facs().is_some_and(has_facs), DroppedAttribute::Facs)
+			.drop_if(
+				self.chunks
+					.iter()
+					.any(|chunk| matches!(chunk, Chunk6::HsrAvis(_) | Chunk6::Unknown(_))),
+				DroppedAttribute::Chunks,
+			)
+	}
+}
+/// Only the core mesh and lods chunks are kept.
+#[cfg(feature = "mesh")]
+impl ToTriangleMesh for crate::mesh::Mesh7 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		use crate::mesh::Chunk7;
+		let (lods, lod_hq_count) = self.lods().map_or((&[][..], None), |lods| {
+			(&lods.lods[..], Some(lods.lod_hq_count))
+		});
+		let converted = self
+			.core_mesh()
+			.map_or_else(Converted::default, |core_mesh| {
+				from_vertex2(&core_mesh.vertices, &core_mesh.faces, lods, lod_hq_count)
+			});
+		converted
+			.drop_if(
+				self.skinning()
+					.is_some_and(|skinning| !skinning.bones.is_empty()),
+				DroppedAttribute::Skinning,
+			)
+			.drop_if(self.facs().is_some_and(has_facs), DroppedAttribute::Facs)
+			.drop_if(
+				self.chunks
+					.iter()
+					.any(|chunk| matches!(chunk, Chunk7::HsrAvis(_) | Chunk7::Unknown(_))),
+				DroppedAttribute::Chunks,
+			)
+	}
+}
+#[cfg(feature = "mesh")]
+impl ToTriangleMesh for crate::mesh::Mesh {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		use crate::mesh::Mesh;
+		match self {
+			#[cfg(feature = "mesh-v1")]
+			Mesh::V1(mesh) => mesh.to_triangle_mesh(),
+			Mesh::V2(mesh) => mesh.to_triangle_mesh(),
+			Mesh::V3(mesh) => mesh.to_triangle_mesh(),
+			Mesh::V4(mesh) => mesh.to_triangle_mesh(),
+			Mesh::V5(mesh) => mesh.to_triangle_mesh(),
+			Mesh::V6(mesh) => mesh.to_triangle_mesh(),
+			Mesh::V7(mesh) => mesh.to_triangle_mesh(),
+		}
+	}
+}
+
+/// The NormalId of each vertex is not kept, it can be computed from the normal.
+/// Tangents have no bitangent sign, so w is 1.0.
+#[cfg(feature = "union-graphics")]
+impl ToTriangleMesh for crate::union_graphics::Mesh2 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		let vertices = &self.vertices;
+		let mut triangle_mesh = TriangleMesh {
+			positions: vertices.iter().map(|vertex| vertex.pos).collect(),
+			normals: vertices.iter().map(|vertex| vertex.norm).collect(),
+			uvs: vertices.iter().map(|vertex| vertex.tex).collect(),
+			colors: vertices.iter().map(|vertex| vertex.color).collect(),
+			tangents: vertices
+				.iter()
+				.map(|vertex| {
+					let [x, y, z] = vertex.tangent;
+					[x, y, z, 1.0]
+				})
+				.collect(),
+			indices: self
+				.faces
+				.iter()
+				.map(|face| face.clone().map(|vertex_id| vertex_id.0))
+				.collect(),
+			parts: Vec::new(),
+			lod_hq_count: None,
+		};
+		triangle_mesh.whole_part();
+		Converted::new(triangle_mesh)
+	}
+}
+/// The hash is not kept.
+#[cfg(feature = "union-graphics")]
+impl ToTriangleMesh for crate::union_graphics::CSGMDL2 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		self.mesh.to_triangle_mesh()
+	}
+}
+/// The hash is not kept.
+#[cfg(feature = "union-graphics")]
+impl ToTriangleMesh for crate::union_graphics::CSGMDL4 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		self.mesh
+			.to_triangle_mesh()
+			.drop_if(!self._unknown1_list.is_empty(), DroppedAttribute::Unknown)
+	}
+}
+/// The NormalId of each vertex is not kept, it can be computed from the normal.
+/// Tangents have no bitangent sign, so w is 1.0.
+/// Only the primary index list is kept, the meaning of the remaining lists is unknown.
+#[cfg(feature = "union-graphics")]
+impl ToTriangleMesh for crate::union_graphics::CSGMDL5 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		let (indices, remainder) = self.faces.indices.as_chunks();
+		let mut triangle_mesh = TriangleMesh {
+			positions: self.positions.clone(),
+			normals: self.normals.iter().map(|normal| normal.0).collect(),
+			uvs: self.tex.clone(),
+			colors: self.colors.clone(),
+			tangents: self
+				.tangents
+				.iter()
+				.map(|tangent| {
+					let [x, y, z] = tangent.0;
+					[x, y, z, 1.0]
+				})
+				.collect(),
+			indices: indices.to_vec(),
+			parts: Vec::new(),
+			lod_hq_count: None,
+		};
+		triangle_mesh.whole_part();
+		Converted::new(triangle_mesh)
+			.drop_if(!self.faces._unknown.is_empty(), DroppedAttribute::Unknown)
+			.drop_if(!remainder.is_empty(), DroppedAttribute::PartialFace)
+	}
+}
+#[cfg(feature = "union-graphics")]
+impl ToTriangleMesh for crate::union_graphics::UnionGraphics {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		use crate::union_graphics::UnionGraphics;
+		match self {
+			UnionGraphics::CSGK(_) => Converted::default(),
+			UnionGraphics::V2(mesh) => mesh.to_triangle_mesh(),
+			UnionGraphics::V4(mesh) => mesh.to_triangle_mesh(),
+			UnionGraphics::V5(mesh) => mesh.to_triangle_mesh(),
+		}
+	}
+}
+
+#[cfg(feature = "union-physics")]
+impl TriangleMesh {
+	fn from_physics_meshes<'a>(
+		meshes: impl IntoIterator<Item = &'a crate::union_physics::Mesh>,
+	) -> Converted<Self> {
+		let mut triangle_mesh = Self::default();
+		for mesh in meshes {
+			triangle_mesh.push_part(
+				&mesh.positions,
+				mesh.faces
+					.iter()
+					.map(|face| face.clone().map(|vertex_id| vertex_id.0)),
+			);
+		}
+		Converted::new(triangle_mesh)
+	}
+}
+#[cfg(feature = "union-physics")]
+impl ToTriangleMesh for crate::union_physics::CSGPHS3 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		TriangleMesh::from_physics_meshes(&self.meshes)
+	}
+}
+#[cfg(feature = "union-physics")]
+impl ToTriangleMesh for crate::union_physics::CSGPHS5 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		TriangleMesh::from_physics_meshes(&self.meshes)
+	}
+}
+/// The physics info is not kept, it describes the geometry.
+#[cfg(feature = "union-physics")]
+impl ToTriangleMesh for crate::union_physics::CSGPHS6 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		TriangleMesh::from_physics_meshes([&self.mesh])
+	}
+}
+/// The geom type and physics info are not kept, they describe the geometry.
+#[cfg(feature = "union-physics")]
+impl ToTriangleMesh for crate::union_physics::CSGPHS7 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		TriangleMesh::from_physics_meshes(&self.meshes)
+	}
+}
+#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+impl ToTriangleMesh for crate::union_physics::Hulls {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		let mut triangle_mesh = TriangleMesh::default();
+		for hull in self.iter_hulls() {
+			triangle_mesh.push_part(hull.positions, hull.faces.iter().copied());
+		}
+		Converted::new(triangle_mesh)
+	}
+}
+/// The geom type, physics info and bounding box are not kept, they describe the geometry.
+#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+impl ToTriangleMesh for crate::union_physics::CSGPHS8 {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		self.mesh.hulls.to_triangle_mesh()
+	}
+}
+#[cfg(feature = "union-physics")]
+impl ToTriangleMesh for crate::union_physics::UnionPhysics {
+	fn to_triangle_mesh(&self) -> Converted<TriangleMesh> {
+		use crate::union_physics::UnionPhysics;
+		match self {
+			UnionPhysics::CSGK(_) | UnionPhysics::Block(_) => Converted::default(),
+			UnionPhysics::V3(mesh) => mesh.to_triangle_mesh(),
+			UnionPhysics::V5(mesh) => mesh.to_triangle_mesh(),
+			UnionPhysics::V6(mesh) => mesh.to_triangle_mesh(),
+			UnionPhysics::V7(mesh) => mesh.to_triangle_mesh(),
+			#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+			UnionPhysics::V8(mesh) => mesh.to_triangle_mesh(),
+		}
+	}
+}
+
+#[cfg(feature = "mesh")]
+#[derive(Debug)]
+pub enum ConvertError {
+	/// The attribute does not have one entry per position.
+	AttributeLength(&'static str),
+	IndexOutOfRange(u32),
+	/// Parts must cover the indices in order and without gaps.
+	InvalidParts,
+	/// Bones are indexed by u16, with u16::MAX meaning no bone.
+	TooManyBones(usize),
+	JointOutOfRange(u16),
+}
+#[cfg(feature = "mesh")]
+impl std::fmt::Display for ConvertError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+#[cfg(feature = "mesh")]
+impl core::error::Error for ConvertError {}
+
+/// Roblox's tangent for vertices without one.
+#[cfg(feature = "mesh")]
+const DEFAULT_TANGENT: [i8; 4] = [0, 0, -128, 127];
+#[cfg(feature = "mesh")]
+const DEFAULT_COLOR: [u8; 4] = [u8::MAX; 4];
+
+#[cfg(feature = "mesh")]
+impl TriangleMesh {
+	fn check(&self) -> Result<(), ConvertError> {
+		let vertex_count = self.positions.len();
+		for (name, len) in [
+			("normals", self.normals.len()),
+			("uvs", self.uvs.len()),
+			("colors", self.colors.len()),
+			("tangents", self.tangents.len()),
+		] {
+			if len != 0 && len != vertex_count {
+				return Err(ConvertError::AttributeLength(name));
+			}
+		}
+		if let Some(&index) = self
+			.indices
+			.as_flattened()
+			.iter()
+			.find(|&&index| vertex_count <= index as usize)
+		{
+			return Err(ConvertError::IndexOutOfRange(index));
+		}
+		let mut end = 0;
+		for part in &self.parts {
+			if part.start != end || part.end < part.start {
+				return Err(ConvertError::InvalidParts);
+			}
+			end = part.end;
+		}
+		if !self.parts.is_empty() && end != self.indices.len() {
+			return Err(ConvertError::InvalidParts);
+		}
+		Ok(())
+	}
+	fn vertices2(&self) -> Vec<Vertex2> {
+		(0..self.positions.len())
+			.map(|i| Vertex2 {
+				pos: self.positions[i],
+				norm: self.normals.get(i).copied().unwrap_or_default(),
+				tex: self.uvs.get(i).copied().unwrap_or_default(),
+				tangent: self
+					.tangents
+					.get(i)
+					.copied()
+					.map_or(DEFAULT_TANGENT, tangent_to_i8),
+				color: self.colors.get(i).copied().unwrap_or(DEFAULT_COLOR),
+			})
+			.collect()
+	}
+	fn vertices2_sized(&self) -> Vertices2 {
+		let vertices = self.vertices2();
+		if self.colors.is_empty() {
+			Vertices2::Truncated(
+				vertices
+					.into_iter()
+					.map(|vertex| crate::mesh::Vertex2Truncated {
+						pos: vertex.pos,
+						norm: vertex.norm,
+						tex: vertex.tex,
+						tangent: vertex.tangent,
+					})
+					.collect(),
+			)
+		} else {
+			Vertices2::Full(vertices)
+		}
+	}
+	fn faces2(&self) -> Vec<Face2> {
+		use crate::mesh::VertexId2;
+		self.indices
+			.iter()
+			.map(|face| Face2(face.map(VertexId2)))
+			.collect()
+	}
+	/// One lod per part, or a single lod covering every face.
+	fn lods3(&self) -> Vec<Lod3> {
+		let face_count = self.indices.len() as u32;
+		let mut lods = vec![Lod3(0)];
+		lods.extend(
+			self.parts
+				.iter()
+				.map(|part| Lod3(part.end as u32))
+				.filter(|&Lod3(end)| end != 0),
+		);
+		if lods.len() == 1 {
+			lods.push(Lod3(face_count));
+		}
+		lods
+	}
+	pub(crate) fn lod_type4(lods: &[Lod3]) -> crate::mesh::LodType4 {
+		use crate::mesh::LodType4;
+		// the generator of the lods is unknown
+		if lods.len() <= 2 {
+			LodType4::None
+		} else {
+			LodType4::Unknown
+		}
+	}
+	/// Report the attributes which vertices2 fills with defaults, other than colors.
+	fn defaulted<M>(&self, converted: Converted<M>) -> Converted<M> {
+		let has_vertices = !self.positions.is_empty();
+		converted
+			.drop_if(
+				has_vertices && self.normals.is_empty(),
+				DroppedAttribute::DefaultNormals,
+			)
+			.drop_if(
+				has_vertices && self.uvs.is_empty(),
+				DroppedAttribute::DefaultUvs,
+			)
+			.drop_if(
+				has_vertices && self.tangents.is_empty(),
+				DroppedAttribute::DefaultTangents,
+			)
+	}
+	/// Report colors filled with white by the targets which always store them.
+	fn defaulted_colors<M>(&self, converted: Converted<M>) -> Converted<M> {
+		let has_vertices = !self.positions.is_empty();
+		self.defaulted(converted).drop_if(
+			has_vertices && self.colors.is_empty(),
+			DroppedAttribute::DefaultColors,
+		)
+	}
+	/// The carried lod_hq_count, or only the first level like Mesh3.
+	fn lod_hq_count4(&self) -> u8 {
+		self.lod_hq_count.unwrap_or(1)
+	}
+	/// Colors are stored when present, choosing between full and truncated vertices.
+	pub fn to_mesh2(&self) -> Result<Converted<crate::mesh::Mesh2>, ConvertError> {
+		use crate::mesh::{Mesh2, Revision2};
+		self.check()?;
+		let converted = Converted::new(Mesh2 {
+			revision: Revision2::Version200,
+			vertices: self.vertices2_sized(),
+			faces: self.faces2(),
+		});
+		Ok(self
+			.defaulted(converted)
+			.drop_if(1 < self.parts.len(), DroppedAttribute::Parts))
+	}
+	/// Parts become lods.
+	pub fn to_mesh3(&self) -> Result<Converted<crate::mesh::Mesh3>, ConvertError> {
+		use crate::mesh::{Mesh3, Revision3};
+		self.check()?;
+		Ok(self.defaulted(Converted::new(Mesh3 {
+			revision: Revision3::Version301,
+			vertices: self.vertices2_sized(),
+			faces: self.faces2(),
+			lods: self.lods3(),
+		})))
+	}
+	/// Parts become lods.  The mesh has no bones, so envelopes and subsets are empty.
+	pub fn to_mesh4(&self) -> Result<Converted<crate::mesh::Mesh4>, ConvertError> {
+		use crate::mesh::{Mesh4, Revision4};
+		self.check()?;
+		let lods = self.lods3();
+		Ok(self.defaulted_colors(Converted::new(Mesh4 {
+			revision: Revision4::Version401,
+			lod_type: Self::lod_type4(&lods),
+			lod_hq_count: self.lod_hq_count4(),
+			_padding: 0,
+			vertices: self.vertices2(),
+			envelopes: Vec::new(),
+			faces: self.faces2(),
+			lods,
+			bones: Vec::new(),
+			bone_names: Vec::new(),
+			subsets: Vec::new(),
+		})))
+	}
+	/// Parts become lods.  The mesh has no bones or FACS data.
+	pub fn to_mesh5(&self) -> Result<Converted<crate::mesh::Mesh5>, ConvertError> {
+		use crate::mesh::{Facs5, FacsFormat5, Mesh5, Revision5};
+		self.check()?;
+		let lods = self.lods3();
+		let facs = Facs5::default();
+		Ok(self.defaulted_colors(Converted::new(Mesh5 {
+			revision: Revision5::Version500,
+			lod_type: Self::lod_type4(&lods),
+			lod_hq_count: self.lod_hq_count4(),
+			facs_format: FacsFormat5::Format1,
+			sizeof_facs: facs.encoded_len() as u32,
+			vertices: self.vertices2(),
+			envelopes: Vec::new(),
+			faces: self.faces2(),
+			lods,
+			bones: Vec::new(),
+			bone_names: Vec::new(),
+			subsets: Vec::new(),
+			facs,
+		})))
+	}
+}
@@ -9,13 +9,28 @@ mod obfuscate;
 
 pub use super::csgk::CSGK;
 
-use binrw::BinReaderExt;
+use crate::limits::ReadLimits;
+use binrw::{BinReaderExt, BinWriterExt};
 
 pub type Error = binrw::Error;
 
 #[inline]
-pub fn read_versioned<R: BinReaderExt>(mut read: R) -> Result<UnionGraphics, Error> {
-	read.read_le()
+pub fn read_versioned<R: BinReaderExt>(read: R) -> Result<UnionGraphics, crate::Error> {
+	crate::error::read_with(read, |read| read.read_le())
+}
+
+/// Read with limits on the counts in the file, for untrusted input.
+#[inline]
+pub fn read_versioned_with_limits<R: BinReaderExt>(
+	read: R,
+	limits: ReadLimits,
+) -> Result<UnionGraphics, crate::Error> {
+	crate::error::read_with(read, |read| read.read_le_args((limits,)))
+}
+
+#[inline]
+pub fn write_versioned<W: BinWriterExt>(mut write: W, mesh: &UnionGraphics) -> Result<(), Error> {
+	write.write_le(mesh)
 }
 
 #[derive(Debug)]
@@ -38,12 +53,13 @@ pub enum NormalId {
 	Front = 6,
 }
 
-#[binrw::binread]
-#[br(little)]
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub enum UnionGraphics {
 	CSGK(CSGK),
-	V2(CSGMDL2),
-	V4(CSGMDL4),
-	V5(CSGMDL5),
+	V2(#[br(args(limits))] CSGMDL2),
+	V4(#[br(args(limits))] CSGMDL4),
+	V5(#[br(args(limits))] CSGMDL5),
 }
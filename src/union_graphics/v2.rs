@@ -1,5 +1,6 @@
 use super::obfuscate::Obfuscator;
 use super::{NormalIDError, NormalId};
+use crate::limits::ReadLimits;
 
 #[binrw::binrw]
 #[brw(little)]
@@ -64,11 +65,19 @@ pub struct VertexId(pub u32);
 
 #[binrw::binrw]
 #[brw(little)]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub struct Mesh2 {
 	#[br(temp)]
 	#[bw(try_calc=vertices.len().try_into())]
 	pub vertex_count: u32,
+	#[br(temp, try_calc = limits
+		.charge()
+		.vertices(vertex_count)
+		.alloc::<Vertex>(vertex_count)
+		.finish())]
+	#[bw(ignore)]
+	vertex_limits: ReadLimits,
 	// vertex data length
 	#[brw(magic = 84u32)]
 	#[br(count=vertex_count)]
@@ -76,6 +85,13 @@ pub struct Mesh2 {
 	#[br(temp)]
 	#[bw(try_calc=(faces.len()*3).try_into())]
 	pub face_count: u32,
+	#[br(temp, try_calc = vertex_limits
+		.charge()
+		.faces(face_count / 3)
+		.alloc::<[VertexId; 3]>(face_count / 3)
+		.finish())]
+	#[bw(ignore)]
+	_face_limits: ReadLimits,
 	#[br(count=face_count/3)]
 	pub faces: Vec<[VertexId; 3]>,
 }
@@ -87,8 +103,10 @@ pub struct Mesh2 {
 // Magic does not have obfuscator applied
 // reversible_obfuscate(0, concat_bytes!(b"CSGMDL", 2u32))
 #[brw(magic = b"\x15\x7d\x29\x15\x75\x6c\x32\x04\x34\x69")]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub struct CSGMDL2 {
 	pub hash: Hash,
+	#[br(args(limits))]
 	pub mesh: Mesh2,
 }
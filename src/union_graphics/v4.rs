@@ -1,5 +1,6 @@
 use super::obfuscate::Obfuscator;
 use super::v2::{Hash, Mesh2};
+use crate::limits::ReadLimits;
 
 #[binrw::binrw]
 #[brw(little)]
@@ -8,13 +9,18 @@ use super::v2::{Hash, Mesh2};
 // Magic does not have obfuscator applied
 // reversible_obfuscate(0, concat_bytes!(b"CSGMDL", 4u32))
 #[brw(magic = b"\x15\x7d\x29\x15\x75\x6c\x34\x04\x34\x69")]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub struct CSGMDL4 {
 	pub hash: Hash,
+	#[br(args(limits))]
 	pub mesh: Mesh2,
 	#[br(temp)]
 	#[bw(try_calc=_unknown1_list.len().try_into())]
 	pub _unknown1_count: u32,
+	#[br(temp, try_calc = limits.charge().alloc::<u32>(_unknown1_count).finish())]
+	#[bw(ignore)]
+	_limits: ReadLimits,
 	#[br(count=_unknown1_count)]
 	pub _unknown1_list: Vec<u32>,
 }
@@ -1,6 +1,7 @@
-use binrw::BinReaderExt;
+use binrw::{BinReaderExt, BinWriterExt};
 
 use super::{Error, NormalIDError, NormalId};
+use crate::limits::ReadLimits;
 
 #[binrw::binrw]
 #[brw(little,repr=u8)]
@@ -32,6 +33,8 @@ impl TryFrom<u8> for NormalId5 {
 pub enum FacesStateMachineError {
 	UnexpectedEOF,
 	UnusedData,
+	/// Indices are limited to 23 bits.
+	IndexOutOfRange(u32),
 }
 impl std::fmt::Display for FacesStateMachineError {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -40,6 +43,53 @@ impl std::fmt::Display for FacesStateMachineError {
 }
 impl core::error::Error for FacesStateMachineError {}
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RangeMarkerError {
+	/// The marker at this index is past the end of the indices
+	OutOfRange { marker: usize, value: u32 },
+	/// The marker at this index is less than the previous marker
+	Decreasing {
+		marker: usize,
+		value: u32,
+		previous: u32,
+	},
+	/// At least two markers are required, this is how many there were
+	NotEnough(usize),
+}
+impl std::fmt::Display for RangeMarkerError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+impl core::error::Error for RangeMarkerError {}
+
+// complete faces data
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
+struct Faces5Inner {
+	vertex_count: u32,
+	#[br(temp)]
+	#[bw(try_calc=vertex_data.len().try_into())]
+	vertex_data_len: u32,
+	// vertex_count is the number of indices decoded from vertex_data
+	#[br(temp, try_calc = limits
+		.charge()
+		.faces(vertex_count / 3)
+		.alloc::<u32>(vertex_count)
+		.alloc::<u8>(vertex_data_len)
+		.finish())]
+	#[bw(ignore)]
+	_limits: ReadLimits,
+	#[br(count=vertex_data_len)]
+	vertex_data: Vec<u8>,
+	#[br(temp)]
+	#[bw(try_calc=range_markers.len().try_into())]
+	range_marker_count: u8,
+	#[br(count=range_marker_count)]
+	range_markers: Vec<u32>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Faces5 {
 	pub indices: Vec<u32>,
@@ -47,25 +97,12 @@ pub struct Faces5 {
 	pub _unknown: Vec<Vec<u32>>,
 }
 impl binrw::BinRead for Faces5 {
-	type Args<'a> = ();
+	type Args<'a> = (ReadLimits,);
 	fn read_options<R: BinReaderExt>(
 		reader: &mut R,
 		_endian: binrw::Endian,
-		_args: Self::Args<'_>,
+		args: Self::Args<'_>,
 	) -> binrw::BinResult<Self> {
-		// complete faces data
-		#[binrw::binrw]
-		#[brw(little)]
-		struct Faces5Inner {
-			vertex_count: u32,
-			vertex_data_len: u32,
-			#[br(count=vertex_data_len)]
-			vertex_data: Vec<u8>,
-			range_marker_count: u8,
-			#[br(count=range_marker_count)]
-			range_markers: Vec<u32>,
-		}
-
 		// use the stream position at the beginning of the Faces data
 		let pos = reader.stream_position()?;
 
@@ -101,7 +138,7 @@ impl binrw::BinRead for Faces5 {
 		}
 
 		// read complete data
-		let faces_inner: Faces5Inner = reader.read_le()?;
+		let faces_inner: Faces5Inner = reader.read_le_args(args)?;
 
 		// accumulate vertex indices using state machine
 		let mut indices = read_state_machine(faces_inner.vertex_data, faces_inner.vertex_count)
@@ -112,29 +149,31 @@ impl binrw::BinRead for Faces5 {
 
 		// Validate markers
 		{
+			let marker_error = |err: RangeMarkerError| Error::Custom {
+				pos,
+				err: Box::new(err),
+			};
 			let mut it = faces_inner.range_markers.iter().copied().enumerate();
 			if let Some((i, mut last_marker)) = it.next() {
 				if indices.len() < (last_marker as usize) {
-					return Err(Error::Custom {
-						pos,
-						err: Box::new(format!("Marker {i} (value {last_marker}) out of range")),
-					});
+					return Err(marker_error(RangeMarkerError::OutOfRange {
+						marker: i,
+						value: last_marker,
+					}));
 				}
 				for (i, marker) in it {
 					if marker < last_marker {
-						return Err(Error::Custom {
-							pos,
-							err: Box::new(format!(
-								"Marker {i} (value {marker}) is less than marker {} (value {last_marker})",
-								i - 1
-							)),
-						});
+						return Err(marker_error(RangeMarkerError::Decreasing {
+							marker: i,
+							value: marker,
+							previous: last_marker,
+						}));
 					}
 					if indices.len() < (marker as usize) {
-						return Err(Error::Custom {
-							pos,
-							err: Box::new(format!("Marker {i} (value {marker}) out of range")),
-						});
+						return Err(marker_error(RangeMarkerError::OutOfRange {
+							marker: i,
+							value: marker,
+						}));
 					}
 					last_marker = marker;
 				}
@@ -146,7 +185,7 @@ impl binrw::BinRead for Faces5 {
 		let Some(marker0) = it.next() else {
 			return Err(Error::Custom {
 				pos,
-				err: Box::new("Not enough range markers: 0"),
+				err: Box::new(RangeMarkerError::NotEnough(0)),
 			});
 		};
 		let mut remaining_start_index = marker0;
@@ -157,7 +196,7 @@ impl binrw::BinRead for Faces5 {
 		let Some(marker1) = it.next() else {
 			return Err(Error::Custom {
 				pos,
-				err: Box::new("Not enough range markers: 1"),
+				err: Box::new(RangeMarkerError::NotEnough(1)),
 			});
 		};
 		let Some(mut marker2) = it.next() else {
@@ -193,9 +232,86 @@ impl binrw::BinRead for Faces5 {
 	}
 }
 
-#[binrw::binread]
-#[br(little)]
+impl binrw::BinWrite for Faces5 {
+	type Args<'a> = ();
+	fn write_options<W: BinWriterExt>(
+		&self,
+		writer: &mut W,
+		_endian: binrw::Endian,
+		_args: Self::Args<'_>,
+	) -> binrw::BinResult<()> {
+		// use the stream position at the beginning of the Faces data
+		let pos = writer.stream_position()?;
+
+		// inverse of read_state_machine
+		fn write_state_machine<'a>(
+			indices: impl Iterator<Item = &'a u32>,
+		) -> Result<Vec<u8>, FacesStateMachineError> {
+			let mut data = Vec::new();
+			let mut last_index: u32 = 0;
+			for &index in indices {
+				if 0x007FFFFF < index {
+					return Err(FacesStateMachineError::IndexOutOfRange(index));
+				}
+				let offset = index as i32 - last_index as i32;
+				match offset {
+					0..64 => data.push(offset as u8),
+					// -64..0 is mapped to 64..128
+					-64..0 => data.push((offset + 128) as u8),
+					_ => {
+						// wrap negative offsets around the 23 bit index space
+						let [v2, v1, v0, _] = (offset as u32 & 0x007FFFFF).to_le_bytes();
+						data.extend_from_slice(&[v0 + 128, v1, v2]);
+					}
+				}
+				last_index = index;
+			}
+			Ok(data)
+		}
+
+		let all_indices = || self.indices.iter().chain(self._unknown.iter().flatten());
+
+		let vertex_data = write_state_machine(all_indices()).map_err(|e| Error::Custom {
+			pos,
+			err: Box::new(e),
+		})?;
+
+		// one marker at the start of each list, plus a marker at the end
+		let mut range_markers = Vec::with_capacity(2 + self._unknown.len());
+		let mut marker = 0;
+		range_markers.push(marker);
+		for list in core::iter::once(&self.indices).chain(&self._unknown) {
+			marker += list.len();
+			range_markers.push(marker);
+		}
+		let range_markers = range_markers
+			.into_iter()
+			.map(u32::try_from)
+			.collect::<Result<Vec<u32>, _>>()
+			.map_err(|e| Error::Custom {
+				pos,
+				err: Box::new(e),
+			})?;
+
+		let faces_inner = Faces5Inner {
+			vertex_count: all_indices()
+				.count()
+				.try_into()
+				.map_err(|e| Error::Custom {
+					pos,
+					err: Box::new(e),
+				})?,
+			vertex_data,
+			range_markers,
+		};
+		writer.write_le(&faces_inner)
+	}
+}
+
+#[binrw::binrw]
+#[brw(little)]
 #[br(map=Self::read)]
+#[bw(map=Self::write)]
 #[derive(Debug, Clone)]
 pub struct QuantizedF32x3(pub [f32; 3]);
 impl QuantizedF32x3 {
@@ -207,17 +323,30 @@ impl QuantizedF32x3 {
 			(z.wrapping_sub(0x7FFF) as f32) * SCALE,
 		])
 	}
+	fn write(&self) -> [i16; 3] {
+		const SCALE: f32 = 32_767.0;
+		self.0
+			.map(|v| ((v * SCALE).round() as i16).wrapping_add(0x7FFF))
+	}
 }
 
-#[binrw::binread]
-#[br(little)]
+#[binrw::binrw]
+#[brw(little)]
 // reversible_obfuscate(0, concat_bytes!(b"CSGMDL", 5u32))
-#[br(magic = b"\x15\x7d\x29\x15\x75\x6c\x35\x04\x34\x69")]
+#[brw(magic = b"\x15\x7d\x29\x15\x75\x6c\x35\x04\x34\x69")]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub struct CSGMDL5 {
 	#[br(temp)]
 	#[bw(try_calc=positions.len().try_into())]
 	pub pos_count: u16,
+	#[br(temp, try_calc = limits
+		.charge()
+		.vertices(pos_count)
+		.alloc::<[f32; 3]>(pos_count)
+		.finish())]
+	#[bw(ignore)]
+	limits: ReadLimits,
 	#[br(count=pos_count)]
 	pub positions: Vec<[f32; 3]>,
 
@@ -225,7 +354,7 @@ pub struct CSGMDL5 {
 	#[bw(try_calc=normals.len().try_into())]
 	pub normals_count: u16,
 	#[br(temp)]
-	#[bw(try_calc=(normals.len()*size_of::<QuantizedF32x3>()).try_into())]
+	#[bw(try_calc=(normals.len()*size_of::<[i16; 3]>()).try_into())]
 	pub normals_len: u32,
 	#[br(count=normals_count)]
 	pub normals: Vec<QuantizedF32x3>,
@@ -252,11 +381,12 @@ pub struct CSGMDL5 {
 	#[bw(try_calc=tangents.len().try_into())]
 	pub tangents_count: u16,
 	#[br(temp)]
-	#[bw(try_calc=(tangents.len()*size_of::<QuantizedF32x3>()).try_into())]
+	#[bw(try_calc=(tangents.len()*size_of::<[i16; 3]>()).try_into())]
 	pub tangents_len: u32,
 	#[br(count=tangents_count)]
 	pub tangents: Vec<QuantizedF32x3>,
 
 	// delta encoded vertex indices
+	#[br(args(limits))]
 	pub faces: Faces5,
 }
@@ -15,36 +15,59 @@ pub use v8::*;
 
 pub use super::csgk::CSGK;
 
+use binrw::BinReaderExt;
+
+use crate::limits::ReadLimits;
+
 pub type Error = binrw::Error;
 
 #[inline]
-pub fn read_versioned<R: binrw::BinReaderExt>(mut read: R) -> Result<UnionPhysics, Error> {
-	read.read_le()
+pub fn read_versioned<R: BinReaderExt>(read: R) -> Result<UnionPhysics, crate::Error> {
+	crate::error::read_with(read, |read| read.read_le())
+}
+
+/// Read with limits on the counts in the file, for untrusted input.
+#[inline]
+pub fn read_versioned_with_limits<R: BinReaderExt>(
+	read: R,
+	limits: ReadLimits,
+) -> Result<UnionPhysics, crate::Error> {
+	crate::error::read_with(read, |read| read.read_le_args((limits,)))
+}
+
+#[inline]
+pub fn write_versioned<W: binrw::BinWriterExt>(
+	mut write: W,
+	mesh: &UnionPhysics,
+) -> Result<(), Error> {
+	write.write_le(mesh)
 }
 
 #[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
-#[binrw::binread]
-#[br(little)]
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub enum UnionPhysics {
 	CSGK(CSGK),
 	Block(Block),
-	V3(CSGPHS3),
-	V5(CSGPHS5),
-	V6(CSGPHS6),
-	V7(CSGPHS7),
-	V8(CSGPHS8),
+	V3(#[br(args(limits))] CSGPHS3),
+	V5(#[br(args(limits))] CSGPHS5),
+	V6(#[br(args(limits))] CSGPHS6),
+	V7(#[br(args(limits))] CSGPHS7),
+	V8(#[br(args(limits))] CSGPHS8),
 }
 
 #[cfg(not(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd")))]
 #[binrw::binrw]
 #[brw(little)]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub enum UnionPhysics {
 	CSGK(CSGK),
 	Block(Block),
-	V3(CSGPHS3),
-	V5(CSGPHS5),
-	V6(CSGPHS6),
-	V7(CSGPHS7),
+	V3(#[br(args(limits))] CSGPHS3),
+	V5(#[br(args(limits))] CSGPHS5),
+	V6(#[br(args(limits))] CSGPHS6),
+	V7(#[br(args(limits))] CSGPHS7),
 }
@@ -1,3 +1,7 @@
+use binrw::BinRead;
+
+use crate::limits::ReadLimits;
+
 #[binrw::binrw]
 #[brw(little)]
 #[derive(Debug, Clone)]
@@ -15,6 +19,7 @@ pub struct VertexId(pub u32);
 
 #[binrw::binrw]
 #[brw(little)]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub struct Mesh {
 	// concat_bytes!(16u32,0u128,16u32,0x3F800000000000000000000000000000u128)
@@ -24,6 +29,13 @@ pub struct Mesh {
 	#[br(temp)]
 	#[bw(try_calc=(positions.len()*3).try_into())]
 	pub pos_count: u32,
+	#[br(temp, try_calc = limits
+		.charge()
+		.vertices(pos_count / 3)
+		.alloc::<[f32; 3]>(pos_count / 3)
+		.finish())]
+	#[bw(ignore)]
+	vertex_limits: ReadLimits,
 	// vertex_width
 	#[brw(magic = 4u32)]
 	#[br(count=pos_count/3)]
@@ -31,15 +43,49 @@ pub struct Mesh {
 	#[br(temp)]
 	#[bw(try_calc=(faces.len()*3).try_into())]
 	pub face_count: u32,
+	#[br(temp, try_calc = vertex_limits
+		.charge()
+		.faces(face_count / 3)
+		.alloc::<[VertexId; 3]>(face_count / 3)
+		.finish())]
+	#[bw(ignore)]
+	_face_limits: ReadLimits,
 	#[br(count=face_count/3)]
 	pub faces: Vec<[VertexId; 3]>,
 }
 
+/// Read meshes until the end of the file like until_eof.  Each mesh is checked against the limits
+/// left over by the meshes before it, so max_alloc_bytes bounds all of them together.
+#[binrw::parser(reader, endian)]
+pub(super) fn read_meshes(limits: ReadLimits) -> binrw::BinResult<Vec<Mesh>> {
+	let mut limits = limits;
+	let mut meshes = Vec::new();
+	loop {
+		let pos = reader.stream_position()?;
+		let mesh = match Mesh::read_options(reader, endian, (limits,)) {
+			Ok(mesh) => mesh,
+			Err(err) if err.is_eof() => return Ok(meshes),
+			Err(err) => return Err(err),
+		};
+		limits = limits
+			.charge()
+			.alloc::<[f32; 3]>(mesh.positions.len() as u64)
+			.alloc::<[VertexId; 3]>(mesh.faces.len() as u64)
+			.finish()
+			.map_err(|err| binrw::Error::Custom {
+				pos,
+				err: Box::new(err),
+			})?;
+		meshes.push(mesh);
+	}
+}
+
 #[binrw::binrw]
 #[brw(little)]
 #[brw(magic = b"CSGPHS\x03\0\0\0")]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub struct CSGPHS3 {
-	#[br(parse_with=binrw::helpers::until_eof)]
+	#[br(parse_with = read_meshes, args(limits))]
 	pub meshes: Vec<Mesh>,
 }
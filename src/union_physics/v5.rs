@@ -1,11 +1,13 @@
-use super::v3::Mesh;
+use super::v3::{Mesh, read_meshes};
+use crate::limits::ReadLimits;
 
 // v3 and v5 are identical
 #[binrw::binrw]
 #[brw(little)]
 #[brw(magic = b"CSGPHS\x05\0\0\0")]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub struct CSGPHS5 {
-	#[br(parse_with=binrw::helpers::until_eof)]
+	#[br(parse_with = read_meshes, args(limits))]
 	pub meshes: Vec<Mesh>,
 }
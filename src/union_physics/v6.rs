@@ -1,10 +1,13 @@
 use super::v3::{Mesh, PhysicsInfo};
+use crate::limits::ReadLimits;
 
 #[binrw::binrw]
 #[brw(little)]
 #[brw(magic = b"CSGPHS\x06\0\0\0")]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub struct CSGPHS6 {
 	pub physics_info: PhysicsInfo,
+	#[br(args(limits))]
 	pub mesh: Mesh,
 }
@@ -1,4 +1,5 @@
-use super::v3::{Mesh, PhysicsInfo};
+use super::v3::{Mesh, PhysicsInfo, read_meshes};
+use crate::limits::ReadLimits;
 
 #[binrw::binrw]
 #[brw(little)]
@@ -17,10 +18,11 @@ pub enum GeomType7 {
 #[binrw::binrw]
 #[brw(little)]
 #[brw(magic = b"CSGPHS\x07\0\0\0")]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub struct CSGPHS7 {
 	pub geom_type: GeomType7,
 	pub physics_info: PhysicsInfo,
-	#[br(parse_with=binrw::helpers::until_eof)]
+	#[br(parse_with = read_meshes, args(limits))]
 	pub meshes: Vec<Mesh>,
 }
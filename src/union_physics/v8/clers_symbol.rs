@@ -1,6 +1,7 @@
 use super::roblox_bit_reader::{BitCounterError, RobloxBitReader};
+use super::roblox_bit_writer::RobloxBitWriter;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Symbol {
 	// 1 bit
 	Continue, // 0b0
@@ -36,6 +37,30 @@ impl<'a> SymbolReader<'a> {
 	}
 }
 
+pub struct SymbolWriter {
+	bit_writer: RobloxBitWriter,
+}
+impl SymbolWriter {
+	pub const fn new() -> Self {
+		Self {
+			bit_writer: RobloxBitWriter::new(),
+		}
+	}
+	pub fn write(&mut self, symbol: Symbol) {
+		match symbol {
+			Symbol::Continue => self.bit_writer.write(1, 0b0),
+			Symbol::Split => self.bit_writer.write(3, 0b1_00),
+			Symbol::Left => self.bit_writer.write(3, 0b1_01),
+			Symbol::Right => self.bit_writer.write(3, 0b1_10),
+			Symbol::End => self.bit_writer.write(3, 0b1_11),
+		}
+	}
+	/// Returns the bytes and the number of bits written.
+	pub fn finish(self) -> (Vec<u8>, u32) {
+		self.bit_writer.finish()
+	}
+}
+
 #[test]
 fn read_symbols() {
 	// 2 C_C_R_C_S_L_E_C
@@ -104,3 +129,19 @@ fn empirical() {
 	assert_eq!(r.read(), Ok(Symbol::End));
 	assert_eq!(r.read(), Err(BitCounterError::NotEnoughBits));
 }
+#[test]
+fn write_symbols() {
+	use Symbol::*;
+	// same stream as read_symbols
+	const BYTES: [u8; 8] = [0x5E, 0x32, 0x5E, 0x32, 0x5E, 0x32, 0x5E, 0x32];
+	const SYMBOLS: [Symbol; 8] = [
+		Continue, Continue, Right, Continue, Split, Left, End, Continue,
+	];
+	let mut w = SymbolWriter::new();
+	for symbols in [&SYMBOLS[..], &SYMBOLS[..], &SYMBOLS[2..], &SYMBOLS[..]] {
+		for &symbol in symbols {
+			w.write(symbol);
+		}
+	}
+	assert_eq!(w.finish(), (BYTES.to_vec(), 62));
+}
@@ -1,4 +1,6 @@
-use super::clers_symbol::{Symbol, SymbolReader};
+use std::collections::HashMap;
+
+use super::clers_symbol::{Symbol, SymbolReader, SymbolWriter};
 use super::roblox_bit_reader::BitCounterError;
 
 #[derive(Debug, Clone)]
@@ -71,14 +73,44 @@ impl From<EdgeId> for Edge {
 	}
 }
 
+/// Malformed CLERS symbols or topology found by HullDecoder.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum HullDecoderError {
+	BitCounter(BitCounterError),
+	/// The symbols describe more faces than the header's face_count
+	TooManyFaces,
+	/// The walks around the boundary took more steps than the decoder's budget, so the adjacency has a cycle
+	Cycle,
+}
+impl From<BitCounterError> for HullDecoderError {
+	fn from(value: BitCounterError) -> Self {
+		Self::BitCounter(value)
+	}
+}
+impl core::fmt::Display for HullDecoderError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+impl core::error::Error for HullDecoderError {}
+
+/// The steps which the walks in zip_boundary may take per edge of capacity,
+/// shared by every hull of the decoder.  Valid hulls take far fewer.
+const STEPS_PER_EDGE: u32 = 16;
+
 pub struct HullDecoder<'a> {
 	symbol_reader: SymbolReader<'a>,
 	// adjacency[edge] = twin edge index, or one of SENTINEL_*
+	// faces are only emitted after checking that they fit, so every edge id
+	// stored here or derived with next and prev is in bounds
 	adjacency: Box<[Edge]>,
 	// indices[edge] = vertex id at this face corner
 	indices: Box<[u32]>,
 	current_face: u32,
 	vertex_offset: u32,
+	// edge steps taken by every walk so far, bounded by max_steps
+	steps: u32,
+	max_steps: u32,
 }
 
 impl<'a> HullDecoder<'a> {
@@ -89,6 +121,10 @@ impl<'a> HullDecoder<'a> {
 			indices: vec![0; capacity].into_boxed_slice(),
 			current_face: 0,
 			vertex_offset: 0,
+			steps: 0,
+			max_steps: u32::try_from(capacity)
+				.unwrap_or(u32::MAX)
+				.saturating_mul(STEPS_PER_EDGE),
 		}
 	}
 	pub const fn remaining_bits(&self) -> u32 {
@@ -103,16 +139,38 @@ impl<'a> HullDecoder<'a> {
 	pub fn into_indices(self) -> Box<[u32]> {
 		self.indices
 	}
-	fn zip_boundary(&mut self, mut current_edge: EdgeId) -> EdgeId {
+	/// The edge steps taken by the walks so far.
+	#[cfg(test)]
+	pub const fn steps(&self) -> u32 {
+		self.steps
+	}
+	fn step(&mut self) -> Result<(), HullDecoderError> {
+		if self.steps == self.max_steps {
+			return Err(HullDecoderError::Cycle);
+		}
+		self.steps += 1;
+		Ok(())
+	}
+	/// Claim the next face, returning its first edge.
+	fn emit_face(&mut self) -> Result<EdgeId, HullDecoderError> {
+		let edge = 3 * self.current_face;
+		if self.adjacency.len() < edge as usize + 3 {
+			return Err(HullDecoderError::TooManyFaces);
+		}
+		self.current_face += 1;
+		Ok(EdgeId(edge))
+	}
+	fn zip_boundary(&mut self, mut current_edge: EdgeId) -> Result<EdgeId, HullDecoderError> {
 		// loop while a SENTINEL_PROCESSING edge still needs to be paired
-		// inf loop if bad format
+		// every pass and walk takes steps from the decoder's budget, so a cycle ends in an error
 		while self.adjacency[current_edge.idx()].ty() == EdgeType::Processing {
+			self.step()?;
 			let mut candidate_edge = current_edge.next();
 
 			// walk the fan via twin+next until we reach a boundary edge
-			// inf loop if bad format
 			while let EdgeType::Adjacency(opposite_edge) = self.adjacency[candidate_edge.idx()].ty()
 			{
+				self.step()?;
 				candidate_edge = opposite_edge.next();
 			}
 
@@ -134,44 +192,43 @@ impl<'a> HullDecoder<'a> {
 
 			// propagate that vertex id around the rest of the merged fan
 			let mut connected_edge = self.adjacency[current_edge.idx()];
-			// inf loop if bad format
 			while let EdgeType::Adjacency(connected_edge_id) = connected_edge.ty()
 				&& candidate_edge != prev_edge
 			{
+				self.step()?;
 				prev_edge = connected_edge_id.prev();
 				self.indices[prev_edge.prev().idx()] = self.indices[candidate_prev_edge.idx()];
 				connected_edge = self.adjacency[prev_edge.idx()];
 			}
 
 			// hop along the connected fan to the next still-unzipped edge
-			// inf loop if bad format
 			while let EdgeType::Adjacency(linked_edge) = self.adjacency[current_edge.idx()].ty()
 				&& current_edge != candidate_edge
 			{
+				self.step()?;
 				current_edge = linked_edge.prev();
 			}
 		}
 
-		current_edge
+		Ok(current_edge)
 	}
-	// recursive function that matches symbols S and E like parentheses
-	fn decode_recursive(
+	// matches symbols S and E like parentheses, with a stack of the gates
+	// to resume from once each split branch ends
+	fn decode_faces(
 		&mut self,
 		vertex_count: &mut u32,
 		mut cursor: EdgeId,
-	) -> Result<(), BitCounterError> {
+	) -> Result<(), HullDecoderError> {
+		let mut splits = Vec::new();
 		loop {
-			// inf loop / stack overflow if bad format
 			// emit a new face and glue its edge 0 to cursor_edge as twins;
 			// edges 1 and 2 inherit the corner vertices from the gate edge
-			let current_face = self.current_face;
-			let current_edge_0 = EdgeId(3 * current_face);
-			let current_edge_1 = EdgeId(3 * current_face + 1);
-			let current_edge_2 = EdgeId(3 * current_face + 2);
+			let current_edge_0 = self.emit_face()?;
+			let current_edge_1 = current_edge_0.next();
+			let current_edge_2 = current_edge_1.next();
 			self.adjacency[current_edge_0.idx()] = cursor.into();
 			self.adjacency[current_edge_1.idx()] = Edge::UNINIT;
 			self.adjacency[current_edge_2.idx()] = Edge::UNINIT;
-			self.current_face += 1;
 
 			self.adjacency[cursor.idx()] = current_edge_0.into();
 
@@ -190,8 +247,8 @@ impl<'a> HullDecoder<'a> {
 					*vertex_count += 1;
 				}
 				Symbol::Split => {
-					self.decode_recursive(vertex_count, cursor)?;
-					cursor = cursor.next();
+					// decode the branch on cursor first, then resume from cursor.next()
+					splits.push(cursor);
 				}
 				Symbol::Left => {
 					self.adjacency[cursor.idx()] = Edge::PROCESSING;
@@ -200,36 +257,305 @@ impl<'a> HullDecoder<'a> {
 				Symbol::Right => {
 					let next_edge = cursor.next();
 					self.adjacency[next_edge.idx()] = Edge::PROCESSING;
-					self.zip_boundary(next_edge);
+					self.zip_boundary(next_edge)?;
 				}
 				Symbol::End => {
 					self.adjacency[cursor.idx()] = Edge::PROCESSING;
 					let next_edge = cursor.next();
 					self.adjacency[next_edge.idx()] = Edge::PROCESSING;
-					self.zip_boundary(next_edge);
-					return Ok(());
+					self.zip_boundary(next_edge)?;
+					match splits.pop() {
+						Some(split) => cursor = split.next(),
+						None => return Ok(()),
+					}
 				}
 			}
 		}
 	}
-	pub fn decode_hull(&mut self) -> Result<(), BitCounterError> {
+	pub fn decode_hull(&mut self) -> Result<(), HullDecoderError> {
 		// Create the starting face
-		let edge = 3 * self.current_face as usize;
-		self.adjacency[edge..edge + 3].copy_from_slice(&[
+		let edge = self.emit_face()?;
+		let edges = edge.idx()..edge.idx() + 3;
+		self.adjacency[edges.clone()].copy_from_slice(&[
 			Edge::BOUNDARY,
 			Edge::UNINIT,
 			Edge::BOUNDARY,
 		]);
-		self.current_face += 1;
 
 		// Create the starting face's vertex indices
-		self.indices[edge..edge + 3].copy_from_slice(&[0, 1, 2]);
+		self.indices[edges].copy_from_slice(&[0, 1, 2]);
 		let mut vertex_count = 3;
 
-		self.decode_recursive(&mut vertex_count, EdgeId(edge as u32 + 1))?;
+		self.decode_faces(&mut vertex_count, edge.next())?;
 
 		self.vertex_offset += vertex_count;
 
 		Ok(())
 	}
 }
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum HullEncoderError {
+	EmptyHull,
+	VertexOutOfRange(u32),
+	DegenerateFace(u32),
+	/// The directed edge is used by more than one face.
+	NonManifoldEdge([u32; 2]),
+	/// The edge has no twin, the hull is not closed.
+	OpenEdge([u32; 2]),
+	/// The position is not referenced by any face.
+	UnusedVertex(u32),
+	/// Edgebreaker without handles can only encode a single connected genus 0 hull.
+	NotGenusZero,
+}
+impl core::fmt::Display for HullEncoderError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+impl core::error::Error for HullEncoderError {}
+
+/// The inverse of HullDecoder.  Traverses each hull in the same order
+/// that HullDecoder reconstructs it and emits the CLERS symbols.
+pub struct HullEncoder {
+	symbol_writer: SymbolWriter,
+	current_face: u32,
+}
+
+impl HullEncoder {
+	pub const fn new() -> Self {
+		Self {
+			symbol_writer: SymbolWriter::new(),
+			current_face: 0,
+		}
+	}
+	pub const fn current_face(&self) -> u32 {
+		self.current_face
+	}
+	/// Returns the bytes and the number of bits written.
+	pub fn finish(self) -> (Vec<u8>, u32) {
+		self.symbol_writer.finish()
+	}
+	// twins[edge] = twin edge, where edge k of a face is opposite corner k
+	fn build_twins(hull: &Hull) -> Result<Vec<EdgeId>, HullEncoderError> {
+		let vertex_count = hull.positions.len() as u32;
+		let mut directed_edges = HashMap::with_capacity(hull.faces.len() * 3);
+		for (face_id, face) in hull.faces.iter().enumerate() {
+			if let Some(&vertex) = face.iter().find(|&&vertex| vertex_count <= vertex) {
+				return Err(HullEncoderError::VertexOutOfRange(vertex));
+			}
+			let [v0, v1, v2] = *face;
+			if v0 == v1 || v1 == v2 || v2 == v0 {
+				return Err(HullEncoderError::DegenerateFace(face_id as u32));
+			}
+			for corner in 0..3 {
+				let edge = EdgeId(face_id as u32 * 3 + corner);
+				let from = face[edge.next().idx() % 3];
+				let to = face[edge.prev().idx() % 3];
+				if directed_edges.insert([from, to], edge).is_some() {
+					return Err(HullEncoderError::NonManifoldEdge([from, to]));
+				}
+			}
+		}
+		let mut twins = Vec::with_capacity(hull.faces.len() * 3);
+		for (face_id, face) in hull.faces.iter().enumerate() {
+			for corner in 0..3 {
+				let edge = EdgeId(face_id as u32 * 3 + corner);
+				let from = face[edge.next().idx() % 3];
+				let to = face[edge.prev().idx() % 3];
+				match directed_edges.get(&[to, from]) {
+					Some(&twin) => twins.push(twin),
+					None => return Err(HullEncoderError::OpenEdge([from, to])),
+				}
+			}
+		}
+		Ok(twins)
+	}
+	/// Encode the connectivity of a closed manifold hull.
+	/// Returns the hull's vertex ids in the order that the decoder assigns them,
+	/// which is the order that the positions must be written in.
+	pub fn encode_hull(&mut self, hull: &Hull) -> Result<Vec<u32>, HullEncoderError> {
+		let vertex_count = hull.positions.len();
+		let face_count = hull.faces.len();
+		if face_count == 0 {
+			return Err(HullEncoderError::EmptyHull);
+		}
+		let twins = Self::build_twins(hull)?;
+
+		let mut used_vertices = vec![false; vertex_count];
+		for &vertex in hull.faces.as_flattened() {
+			used_vertices[vertex as usize] = true;
+		}
+		if let Some(vertex) = used_vertices.iter().position(|&used| !used) {
+			return Err(HullEncoderError::UnusedVertex(vertex as u32));
+		}
+
+		// Euler characteristic V - E + F = 2
+		let edge_count = face_count * 3 / 2;
+		if vertex_count + face_count != edge_count + 2 {
+			return Err(HullEncoderError::NotGenusZero);
+		}
+
+		let corner = |edge: EdgeId| hull.faces[edge.idx() / 3][edge.idx() % 3];
+		let face_of = |edge: EdgeId| edge.idx() / 3;
+
+		let mut vertex_order = Vec::with_capacity(vertex_count);
+		let mut visited_vertices = vec![false; vertex_count];
+		let mut visited_faces = vec![false; face_count];
+
+		// The starting face is implicit, its vertices are 0, 1, 2
+		visited_faces[0] = true;
+		for &vertex in &hull.faces[0] {
+			visited_vertices[vertex as usize] = true;
+			vertex_order.push(vertex);
+		}
+		// symbols are only written once the whole hull is known to be valid
+		let mut symbols = Vec::with_capacity(face_count - 1);
+
+		// Split symbols defer the second branch until the first branch ends
+		let mut pending = vec![twins[EdgeId(1).idx()]];
+		while let Some(mut edge) = pending.pop() {
+			loop {
+				// edge is edge 0 of the face being entered, so its corner is the apex
+				if core::mem::replace(&mut visited_faces[face_of(edge)], true) {
+					return Err(HullEncoderError::NotGenusZero);
+				}
+				let edge_1 = edge.next();
+				let edge_2 = edge.prev();
+				let apex = corner(edge);
+
+				if !core::mem::replace(&mut visited_vertices[apex as usize], true) {
+					vertex_order.push(apex);
+					symbols.push(Symbol::Continue);
+					edge = twins[edge_1.idx()];
+					continue;
+				}
+
+				let open_1 = !visited_faces[face_of(twins[edge_1.idx()])];
+				let open_2 = !visited_faces[face_of(twins[edge_2.idx()])];
+				match (open_1, open_2) {
+					(true, true) => {
+						symbols.push(Symbol::Split);
+						pending.push(twins[edge_2.idx()]);
+						edge = twins[edge_1.idx()];
+					}
+					(false, true) => {
+						symbols.push(Symbol::Left);
+						edge = twins[edge_2.idx()];
+					}
+					(true, false) => {
+						symbols.push(Symbol::Right);
+						edge = twins[edge_1.idx()];
+					}
+					(false, false) => {
+						symbols.push(Symbol::End);
+						break;
+					}
+				}
+			}
+		}
+
+		// every face is entered exactly once by a symbol
+		if symbols.len() + 1 != face_count || vertex_order.len() != vertex_count {
+			return Err(HullEncoderError::NotGenusZero);
+		}
+		for symbol in symbols {
+			self.symbol_writer.write(symbol);
+		}
+
+		self.current_face += face_count as u32;
+
+		Ok(vertex_order)
+	}
+}
+
+#[test]
+fn encode_tetrahedron() {
+	const POSITIONS: [[f32; 3]; 4] = [[0.0; 3], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+	const FACES: [[u32; 3]; 4] = [[0, 2, 1], [0, 1, 3], [1, 2, 3], [0, 3, 2]];
+	let hull = Hull {
+		positions: &POSITIONS,
+		faces: &FACES,
+	};
+	let mut encoder = HullEncoder::new();
+	assert_eq!(encoder.encode_hull(&hull), Ok(vec![0, 2, 1, 3]));
+	let (bytes, bits) = encoder.finish();
+
+	let symbol_reader = SymbolReader::new(&bytes, bits).unwrap();
+	let mut decoder = HullDecoder::new(symbol_reader, FACES.len() * 3);
+	decoder.decode_hull().unwrap();
+	assert_eq!(decoder.remaining_bits(), 0);
+	assert_eq!(decoder.vertex_offset(), 4);
+
+	// the open hull is rejected
+	let open_hull = Hull {
+		positions: &POSITIONS,
+		faces: &FACES[..3],
+	};
+	assert!(matches!(
+		HullEncoder::new().encode_hull(&open_hull),
+		Err(HullEncoderError::OpenEdge(_))
+	));
+}
+
+#[test]
+fn decode_malformed() {
+	let decode = |symbols: &[Symbol], capacity: usize| {
+		let mut writer = SymbolWriter::new();
+		for &symbol in symbols {
+			writer.write(symbol);
+		}
+		let (bytes, bits) = writer.finish();
+		let symbol_reader = SymbolReader::new(&bytes, bits).unwrap();
+		let mut decoder = HullDecoder::new(symbol_reader, capacity);
+		while decoder.remaining_bits() != 0 {
+			decoder.decode_hull()?;
+		}
+		Ok(())
+	};
+
+	// splits nest without recursing and stop at the face count
+	let splits = vec![Symbol::Split; 100_000];
+	assert_eq!(
+		decode(&splits, 3 * 1000),
+		Err(HullDecoderError::TooManyFaces)
+	);
+	assert_eq!(
+		decode(&[Symbol::Continue], 3 * 8),
+		Err(HullDecoderError::BitCounter(BitCounterError::NotEnoughBits))
+	);
+
+	// arbitrary symbols fail or decode, but never panic or hang
+	let mut state = 0x2545_F491u32;
+	for _ in 0..1000 {
+		let symbols: Vec<Symbol> = (0..64)
+			.map(|_| {
+				state ^= state << 13;
+				state ^= state >> 17;
+				state ^= state << 5;
+				[
+					Symbol::Continue,
+					Symbol::Split,
+					Symbol::Left,
+					Symbol::Right,
+					Symbol::End,
+				][state as usize % 5]
+			})
+			.collect();
+		let _ = decode(&symbols, 3 * 48);
+	}
+}
+
+#[test]
+fn decode_step_budget() {
+	// a fan whose twin leads back into itself, which would walk forever without a budget
+	let symbol_reader = SymbolReader::new(&[], 0).unwrap();
+	let capacity = 3 * 2;
+	let mut decoder = HullDecoder::new(symbol_reader, capacity);
+	let edge = decoder.emit_face().unwrap();
+	decoder.adjacency[edge.idx()] = Edge::PROCESSING;
+	decoder.adjacency[edge.next().idx()] = edge.into();
+	assert_eq!(decoder.zip_boundary(edge), Err(HullDecoderError::Cycle));
+	// every walk shares the budget of the decoder
+	assert_eq!(decoder.steps(), STEPS_PER_EDGE * capacity as u32);
+}
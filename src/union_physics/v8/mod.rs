@@ -3,18 +3,21 @@ mod clers_symbol;
 mod edgebreaker;
 mod raw_hulls;
 mod roblox_bit_reader;
+mod roblox_bit_writer;
 
-use binrw::{BinRead, BinReaderExt};
+use binrw::{BinRead, BinReaderExt, BinWrite, BinWriterExt};
 
 use super::v7::GeomType7;
-pub use edgebreaker::Hull;
+use crate::limits::{LimitError, ReadLimits};
+pub use edgebreaker::{Hull, HullDecoderError, HullEncoderError};
 pub use raw_hulls::Hulls;
 pub use roblox_bit_reader::BitCounterError;
 
 /// Hull information is accessed via mesh.hulls.iter_hulls()
-#[binrw::binread]
-#[br(little)]
-#[br(magic = b"CSGPHS\x08\0\0\0")]
+#[binrw::binrw]
+#[brw(little)]
+#[brw(magic = b"CSGPHS\x08\0\0\0")]
+#[br(import(limits: ReadLimits))]
 #[derive(Debug, Clone)]
 pub struct CSGPHS8 {
 	pub geom_type: GeomType7,
@@ -22,42 +25,57 @@ pub struct CSGPHS8 {
 	#[bw(ignore)]
 	#[brw(magic = 0u8)]
 	_padding: (),
-	#[br(parse_with = read_mesh)]
+	#[br(parse_with = read_mesh, args(limits))]
+	#[bw(write_with = write_mesh)]
 	pub mesh: Mesh8,
 }
 
-#[binrw::binread]
-#[br(little)]
+#[binrw::binrw]
+#[brw(little)]
 #[derive(Debug, Clone)]
 pub struct Aabb {
 	pub min: [f32; 3],
 	pub max: [f32; 3],
 }
 
-#[binrw::binread]
-#[br(little)]
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
 struct RawMesh8 {
 	hull_count: u32,
 	#[br(temp)]
+	#[bw(try_calc = (positions.len() / 3).try_into())]
 	position_count: u32,
 	face_count: u32,
-	#[br(temp)]
-	first_hull_pos_count: u32,
-	#[br(temp)]
-	first_hull_face_count: u32,
-	#[br(temp)]
+	max_hull_pos_count: u32,
+	max_hull_face_count: u32,
 	raw_hulls_len: u32,
 	clers_bit_count: u32,
 	#[br(temp)]
+	#[bw(try_calc = clers_buffer.len().try_into())]
 	clers_buffer_len: u32,
 	#[br(temp)]
+	#[bw(try_calc = size_of_val(positions.as_slice()).try_into())]
 	positions_len: u32,
+	// the edgebreaker hulls are decoded into the ranges and indices after reading
+	#[br(temp, try_calc = limits
+		.charge()
+		.faces(face_count)
+		.vertices(position_count)
+		.alloc::<u32>(hull_count as u64 * 2)
+		.alloc::<u32>(face_count as u64 * 3)
+		.alloc::<u8>(clers_buffer_len)
+		.alloc::<f32>(position_count as u64 * 3)
+		.finish())]
+	#[bw(ignore)]
+	limits: ReadLimits,
 	aabb: Aabb,
-	#[br(if(raw_hulls_len != 0))]
+	#[br(if(raw_hulls_len != 0), args(limits))]
+	#[bw(if(*raw_hulls_len != 0))]
 	raw_hulls: Hulls,
 	#[br(count = clers_buffer_len)]
 	clers_buffer: Vec<u8>,
-	#[br(count = position_count * 3)]
+	#[br(count = position_count as u64 * 3)]
 	positions: Vec<f32>,
 }
 
@@ -75,7 +93,7 @@ pub struct Mesh8 {
 fn read_mesh<R: BinReaderExt>(
 	reader: &mut R,
 	endian: binrw::Endian,
-	args: (),
+	(limits,): (ReadLimits,),
 ) -> binrw::BinResult<Mesh8> {
 	// decode zstd
 	let pos = reader.stream_position()?;
@@ -91,11 +109,26 @@ fn read_mesh<R: BinReaderExt>(
 		pos,
 		err: Box::new(e),
 	})?;
-	std::io::copy(&mut decoder, &mut decoded)?;
+	// read one byte past the limit to tell whether it was exceeded
+	let max = limits.max_decompressed_bytes;
+	std::io::copy(
+		&mut std::io::Read::take(&mut decoder, max.saturating_add(1)),
+		&mut decoded,
+	)?;
+	let decoded_len = decoded.len() as u64;
+	let limits = if max < decoded_len {
+		Err(LimitError::DecompressedBytes(decoded_len))
+	} else {
+		limits.charge().bytes(decoded_len).finish()
+	}
+	.map_err(|e| binrw::Error::Custom {
+		pos,
+		err: Box::new(e),
+	})?;
 
 	let mut reader = std::io::Cursor::new(decoded);
 	let pos = reader.position();
-	let mesh = RawMesh8::read_options(&mut reader, endian, args)?;
+	let mesh = RawMesh8::read_options(&mut reader, endian, (limits,))?;
 
 	let symbol_reader = clers_symbol::SymbolReader::new(&mesh.clers_buffer, mesh.clers_bit_count)
 		.map_err(|e| binrw::Error::Custom {
@@ -164,3 +197,81 @@ fn read_mesh<R: BinReaderExt>(
 		},
 	})
 }
+
+fn write_mesh<W: BinWriterExt>(
+	mesh: &Mesh8,
+	writer: &mut W,
+	endian: binrw::Endian,
+	args: (),
+) -> binrw::BinResult<()> {
+	let pos = writer.stream_position()?;
+	let mut hulls = mesh.hulls.iter_hulls();
+
+	// the raw hulls are written as they are
+	let mut raw_hulls = Hulls::default();
+	for hull in hulls.by_ref().take(mesh.raw_hull_count as usize) {
+		if raw_hulls.face_ranges.is_empty() {
+			raw_hulls.face_ranges.push(0);
+			raw_hulls.pos_ranges.push(0);
+		}
+		raw_hulls.faces.extend_from_slice(hull.faces.as_flattened());
+		raw_hulls
+			.positions
+			.extend_from_slice(hull.positions.as_flattened());
+		raw_hulls.face_ranges.push(raw_hulls.faces.len() as u32);
+		raw_hulls.pos_ranges.push(raw_hulls.positions.len() as u32);
+	}
+
+	// encode the remaining hulls with edgebreaker, reordering the positions to match
+	let mut hull_encoder = edgebreaker::HullEncoder::new();
+	let mut positions = Vec::new();
+	let mut max_hull_pos_count = 0;
+	let mut max_hull_face_count = 0;
+	let mut hull_count = 0u32;
+	for hull in hulls {
+		let vertex_order = hull_encoder
+			.encode_hull(&hull)
+			.map_err(|e| binrw::Error::Custom {
+				pos,
+				err: Box::new(e),
+			})?;
+		positions.extend(
+			vertex_order
+				.iter()
+				.flat_map(|&vertex| hull.positions[vertex as usize]),
+		);
+		max_hull_pos_count = max_hull_pos_count.max(vertex_order.len() as u32);
+		max_hull_face_count = max_hull_face_count.max(hull.faces.len() as u32);
+		hull_count += 1;
+	}
+	let face_count = hull_encoder.current_face();
+	let (clers_buffer, clers_bit_count) = hull_encoder.finish();
+
+	let raw_mesh = RawMesh8 {
+		hull_count,
+		face_count,
+		max_hull_pos_count,
+		max_hull_face_count,
+		raw_hulls_len: raw_hulls.serialized_len() as u32,
+		clers_bit_count,
+		aabb: mesh.aabb.clone(),
+		raw_hulls,
+		clers_buffer,
+		positions,
+	};
+	let mut raw = std::io::Cursor::new(Vec::new());
+	raw_mesh.write_options(&mut raw, endian, args)?;
+	let raw = raw.into_inner();
+
+	// encode zstd
+	#[cfg(all(feature = "csgphs-v8-ruzstd", not(feature = "csgphs-v8-zstd")))]
+	let encoded = ruzstd::encoding::compress_to_vec(
+		raw.as_slice(),
+		ruzstd::encoding::CompressionLevel::Fastest,
+	);
+	#[cfg(feature = "csgphs-v8-zstd")]
+	let encoded = zstd::stream::encode_all(raw.as_slice(), 0)?;
+	writer.write_all(&encoded)?;
+
+	Ok(())
+}
@@ -1,24 +1,60 @@
 use super::Hull;
+use crate::limits::ReadLimits;
 
-#[binrw::binread]
-#[br(little)]
+#[binrw::binrw]
+#[brw(little)]
+#[br(import(limits: ReadLimits))]
 #[derive(Clone, Debug, Default)]
 pub struct Hulls {
 	#[br(temp)]
+	#[bw(try_calc = face_ranges.len().try_into())]
 	face_range_count: u32,
-	#[br(count = face_range_count)]
+	#[br(temp, try_calc = limits.charge().alloc::<u32>(face_range_count).finish())]
+	#[bw(ignore)]
+	face_range_limits: ReadLimits,
+	// iter_hulls slices faces with each pair of ranges
+	#[br(count = face_range_count, assert(face_ranges.is_sorted(), "face_ranges decrease"))]
 	pub face_ranges: Vec<u32>,
+	#[br(temp, try_calc = face_range_limits
+		.charge()
+		.faces(face_ranges.last().copied().unwrap_or(0) / 3)
+		.alloc::<u32>(face_ranges.last().copied().unwrap_or(0))
+		.finish())]
+	#[bw(ignore)]
+	face_limits: ReadLimits,
 	// the last hull_range value gives the total index count
 	#[br(count = face_ranges.last().copied().unwrap_or(0))]
 	pub faces: Vec<u32>,
 	#[br(temp)]
+	#[bw(try_calc = pos_ranges.len().try_into())]
 	pos_range_count: u32,
-	#[br(count = pos_range_count)]
+	#[br(temp, try_calc = face_limits.charge().alloc::<u32>(pos_range_count).finish())]
+	#[bw(ignore)]
+	pos_range_limits: ReadLimits,
+	#[br(count = pos_range_count, assert(pos_ranges.is_sorted(), "pos_ranges decrease"))]
 	pub pos_ranges: Vec<u32>,
+	#[br(temp, try_calc = pos_range_limits
+		.charge()
+		.vertices(pos_ranges.last().copied().unwrap_or(0) / 3)
+		.alloc::<f32>(pos_ranges.last().copied().unwrap_or(0))
+		.finish())]
+	#[bw(ignore)]
+	_pos_limits: ReadLimits,
 	#[br(count = pos_ranges.last().copied().unwrap_or(0))]
 	pub positions: Vec<f32>,
 }
 impl Hulls {
+	/// Number of bytes written, or 0 if there are no hulls.
+	pub(crate) fn serialized_len(&self) -> usize {
+		if self.face_ranges.is_empty() {
+			return 0;
+		}
+		size_of::<u32>() * 2
+			+ size_of_val(self.face_ranges.as_slice())
+			+ size_of_val(self.faces.as_slice())
+			+ size_of_val(self.pos_ranges.as_slice())
+			+ size_of_val(self.positions.as_slice())
+	}
 	pub fn iter_hulls(&self) -> impl ExactSizeIterator<Item = Hull<'_>> {
 		self.face_ranges
 			.array_windows()
@@ -24,7 +24,7 @@ pub struct RobloxBitReader<'a> {
 }
 impl<'a> RobloxBitReader<'a> {
 	pub fn new(bytes: &'a [u8], bit_count_limit: u32) -> Result<Self, BitCounterError> {
-		if (bytes.len() as u32 * u8::BITS) < bit_count_limit {
+		if (bytes.len() as u64 * u8::BITS as u64) < bit_count_limit as u64 {
 			return Err(BitCounterError::NotEnoughBytes);
 		}
 
@@ -51,7 +51,9 @@ impl<'a> RobloxBitReader<'a> {
 			self.bit_count -= draw_bits;
 			// bits are lsb-aligned
 			let new_cache_bits = self.chunks.next().copied().map_or(0, Cache::from_le_bytes);
-			let new_cache = BitBuffer::new(new_cache_bits, draw_bits);
+			// the padding above the last bits is not necessarily zero
+			let mask = (1 as Cache).unbounded_shl(draw_bits).wrapping_sub(1);
+			let new_cache = BitBuffer::new(new_cache_bits & mask, draw_bits);
 			core::mem::replace(&mut self.cache, new_cache)
 		} else {
 			BitBuffer::empty()
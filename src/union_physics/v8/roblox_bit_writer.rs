@@ -0,0 +1,74 @@
+use super::bit_buffer::{BitBuffer, Cache};
+
+/// Write bits in the same inconsistent manner as Roblox.
+/// Bits are packed msb-first into little endian words,
+/// and the bits of the final partial word are lsb-aligned.
+#[derive(Debug, Clone)]
+pub struct RobloxBitWriter {
+	bytes: Vec<u8>,
+	cache: BitBuffer,
+	bit_count: u32,
+}
+impl RobloxBitWriter {
+	pub const fn new() -> Self {
+		Self {
+			bytes: Vec::new(),
+			cache: BitBuffer::empty(),
+			bit_count: 0,
+		}
+	}
+	fn flush(&mut self) {
+		let cache = core::mem::replace(&mut self.cache, BitBuffer::empty());
+		self.bytes.extend_from_slice(&cache.value().to_le_bytes());
+	}
+	/// Write the low `bits` bits of value.
+	pub fn write(&mut self, bits: u32, value: Cache) {
+		debug_assert!(bits <= Cache::BITS);
+
+		let mask = |bits: u32| (1 as Cache).unbounded_shl(bits).wrapping_sub(1);
+		let value = value & mask(bits);
+		self.bit_count += bits;
+
+		// split the value across words if the cache would overflow
+		let room = BitBuffer::CAPACITY - self.cache.bits();
+		let remaining_bits = if room < bits {
+			let remaining_bits = bits - room;
+			self.cache
+				.push_lsb(room, value.unbounded_shr(remaining_bits));
+			self.flush();
+			remaining_bits
+		} else {
+			bits
+		};
+
+		self.cache
+			.push_lsb(remaining_bits, value & mask(remaining_bits));
+		if self.cache.bits() == BitBuffer::CAPACITY {
+			self.flush();
+		}
+	}
+	/// Returns the bytes and the number of bits written.
+	pub fn finish(mut self) -> (Vec<u8>, u32) {
+		if self.cache.bits() != 0 {
+			self.flush();
+		}
+		(self.bytes, self.bit_count)
+	}
+}
+
+#[test]
+fn roundtrip_bits() {
+	use super::roblox_bit_reader::RobloxBitReader;
+	let mut w = RobloxBitWriter::new();
+	for i in 0..40 {
+		w.write(i % 7 + 1, i);
+	}
+	let (bytes, bit_count) = w.finish();
+	assert_eq!(bytes.len() % 4, 0);
+	let mut r = RobloxBitReader::new(&bytes, bit_count).unwrap();
+	for i in 0..40 {
+		let bits = i % 7 + 1;
+		assert_eq!(r.read(bits), Ok(i & ((1 << bits) - 1)));
+	}
+	assert_eq!(r.remaining_bits(), 0);
+}
@@ -0,0 +1,726 @@
+//! Structural checks for data which parses, but is not safe to use as is.
+//!
+//! Every mesh and union format has a `validate` method which returns a list of
+//! diagnostics.  Anything with `Severity::Error` can make indexing code panic.
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum Severity {
+	/// Unusual, but safe to use
+	Warning,
+	/// Indices or ranges point outside of the data they refer to
+	Error,
+}
+
+/// What a diagnostic refers to.  Indices are into the list of the same name.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Location {
+	Mesh,
+	Vertex(usize),
+	Face(usize),
+	Lod(usize),
+	Envelope(usize),
+	Bone(usize),
+	Subset(usize),
+	Facs,
+	/// A list in CSGMDL5 `Faces5::_unknown`
+	UnknownFaces(usize),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DiagnosticKind {
+	/// A position, normal or texture coordinate is NaN or infinite
+	NonFinite,
+	/// A face references a vertex which does not exist
+	FaceIndexOutOfRange(u32),
+	/// A face references the same vertex more than once
+	DegenerateFace,
+	/// The index count is not a multiple of three
+	IncompleteFace,
+	/// An attribute does not have one entry per vertex
+	AttributeLength(&'static str),
+	LodOutOfRange(u32),
+	/// The lod starts before the previous one
+	LodNotMonotonic,
+	/// There are envelopes, but not one per vertex
+	EnvelopeCount(usize),
+	/// Envelope weights should sum to 255
+	EnvelopeWeightSum(u32),
+	/// A weighted envelope bone is past the bone_count of its subset
+	EnvelopeBoneOutOfRange(u8),
+	/// A skinned vertex which is not in any subset
+	VertexWithoutSubset,
+	BoneNameOutOfRange(u32),
+	BoneParentOutOfRange(u16),
+	/// Following the parents of the bone leads back to itself
+	BoneCycle,
+	SubsetFacesOutOfRange,
+	SubsetVerticesOutOfRange,
+	/// The faces or vertices of the subset overlap with the subset at this index
+	SubsetOverlap(usize),
+	/// A subset has more than 26 bones
+	SubsetBoneCount(u32),
+	SubsetBoneOutOfRange(u16),
+	/// A face of the subset uses a vertex outside of the subset
+	SubsetFaceOutsideVertices(u32),
+	#[cfg(feature = "mesh")]
+	Facs(crate::mesh::FacsError),
+	/// A mesh without a core mesh chunk has no geometry
+	MissingCoreMesh,
+	/// The face or position ranges of the hulls are out of order or out of bounds
+	#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+	HullRanges,
+}
+impl DiagnosticKind {
+	pub fn severity(&self) -> Severity {
+		match self {
+			DiagnosticKind::DegenerateFace
+			| DiagnosticKind::VertexWithoutSubset
+			| DiagnosticKind::EnvelopeWeightSum(_)
+			| DiagnosticKind::MissingCoreMesh => Severity::Warning,
+			_ => Severity::Error,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+	pub kind: DiagnosticKind,
+	pub location: Location,
+	/// The index of the mesh or convex hull, for union physics formats with several
+	pub mesh: Option<usize>,
+}
+impl Diagnostic {
+	pub fn severity(&self) -> Severity {
+		self.kind.severity()
+	}
+}
+impl core::fmt::Display for Diagnostic {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+
+/// True if any diagnostic is an error.
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+	diagnostics
+		.iter()
+		.any(|diagnostic| diagnostic.severity() == Severity::Error)
+}
+
+#[derive(Default)]
+struct Diagnostics {
+	list: Vec<Diagnostic>,
+	mesh: Option<usize>,
+}
+impl Diagnostics {
+	fn push(&mut self, location: Location, kind: DiagnosticKind) {
+		self.list.push(Diagnostic {
+			kind,
+			location,
+			mesh: self.mesh,
+		});
+	}
+	fn finite<V: IntoIterator<Item = f32>>(&mut self, vertices: impl IntoIterator<Item = V>) {
+		for (vertex, values) in vertices.into_iter().enumerate() {
+			if !values.into_iter().all(f32::is_finite) {
+				self.push(Location::Vertex(vertex), DiagnosticKind::NonFinite);
+			}
+		}
+	}
+	fn faces(&mut self, faces: impl IntoIterator<Item = [u32; 3]>, vertex_count: usize) {
+		for (face, vertices) in faces.into_iter().enumerate() {
+			if let Some(&vertex) = vertices
+				.iter()
+				.find(|&&vertex| vertex_count <= vertex as usize)
+			{
+				self.push(
+					Location::Face(face),
+					DiagnosticKind::FaceIndexOutOfRange(vertex),
+				);
+			} else if let [a, b, c] = vertices
+				&& (a == b || b == c || c == a)
+			{
+				self.push(Location::Face(face), DiagnosticKind::DegenerateFace);
+			}
+		}
+	}
+}
+
+#[cfg(feature = "mesh")]
+mod mesh {
+	use super::{Diagnostic, DiagnosticKind, Diagnostics, Location};
+	use crate::mesh::{
+		Bone4, Envelope4, Face2, Facs5, Lod3, Mesh, Mesh2, Mesh3, Mesh4, Mesh5, Mesh6, Mesh7,
+		Subset4, Vertex2, Vertices2,
+	};
+
+	/// Roblox limits each subset to this many bones.
+	const SUBSET_BONE_COUNT: u32 = 26;
+
+	fn face_indices(faces: &[Face2]) -> impl Iterator<Item = [u32; 3]> + '_ {
+		faces
+			.iter()
+			.map(|Face2(face)| [face[0].0, face[1].0, face[2].0])
+	}
+
+	impl Diagnostics {
+		fn vertex2(&mut self, vertices: &[Vertex2]) {
+			self.finite(
+				vertices
+					.iter()
+					.map(|vertex| vertex.pos.into_iter().chain(vertex.norm).chain(vertex.tex)),
+			);
+		}
+		fn vertices2(&mut self, vertices: &Vertices2) {
+			match vertices {
+				Vertices2::Full(vertices) => self.vertex2(vertices),
+				Vertices2::Truncated(vertices) => self.finite(
+					vertices
+						.iter()
+						.map(|vertex| vertex.pos.into_iter().chain(vertex.norm).chain(vertex.tex)),
+				),
+			}
+		}
+		fn lods(&mut self, lods: &[Lod3], face_count: usize) {
+			let mut previous = 0;
+			for (i, &Lod3(lod)) in lods.iter().enumerate() {
+				if face_count < lod as usize {
+					self.push(Location::Lod(i), DiagnosticKind::LodOutOfRange(lod));
+				} else if lod < previous {
+					self.push(Location::Lod(i), DiagnosticKind::LodNotMonotonic);
+				}
+				previous = previous.max(lod);
+			}
+		}
+		fn bones(&mut self, bones: &[Bone4], bone_names: &[u8]) {
+			let parent = |bone: usize| {
+				bones[bone]
+					.parent
+					.get()
+					.map(usize::from)
+					.filter(|&parent| parent < bones.len())
+			};
+			// one pass with three state marking finds every bone which is on a cycle
+			#[derive(Clone, Copy, Eq, PartialEq)]
+			enum Mark {
+				Unvisited,
+				InProgress,
+				Done,
+			}
+			let mut marks = vec![Mark::Unvisited; bones.len()];
+			let mut in_cycle = vec![false; bones.len()];
+			let mut path = Vec::new();
+			for start in 0..bones.len() {
+				let mut bone = Some(start);
+				while let Some(i) = bone
+					&& marks[i] == Mark::Unvisited
+				{
+					marks[i] = Mark::InProgress;
+					path.push(i);
+					bone = parent(i);
+				}
+				if let Some(i) = bone
+					&& marks[i] == Mark::InProgress
+					&& let Some(cycle_start) = path.iter().position(|&j| j == i)
+				{
+					for &j in &path[cycle_start..] {
+						in_cycle[j] = true;
+					}
+				}
+				for i in path.drain(..) {
+					marks[i] = Mark::Done;
+				}
+			}
+			for (i, bone) in bones.iter().enumerate() {
+				if bone_names.len() <= bone.bone_name_pos as usize {
+					self.push(
+						Location::Bone(i),
+						DiagnosticKind::BoneNameOutOfRange(bone.bone_name_pos),
+					);
+				}
+				if let Some(parent) = bone.parent.get()
+					&& bones.len() <= parent as usize
+				{
+					self.push(
+						Location::Bone(i),
+						DiagnosticKind::BoneParentOutOfRange(parent),
+					);
+				} else if in_cycle[i] {
+					self.push(Location::Bone(i), DiagnosticKind::BoneCycle);
+				}
+			}
+		}
+		fn subsets(
+			&mut self,
+			subsets: &[Subset4],
+			faces: &[Face2],
+			vertex_count: usize,
+			bone_count: usize,
+		) {
+			let faces_range = |subset: &Subset4| {
+				subset.faces_offset as usize
+					..subset.faces_offset as usize + subset.faces_len as usize
+			};
+			let vertices_range = |subset: &Subset4| {
+				subset.vertices_offset as usize
+					..subset.vertices_offset as usize + subset.vertices_len as usize
+			};
+			// sort the ranges by start and sweep, so each subset which overlaps
+			// a subset before it is reported once, with the subset it overlaps
+			let mut overlaps = vec![None; subsets.len()];
+			for range in [faces_range, vertices_range] {
+				let mut order: Vec<_> = subsets
+					.iter()
+					.map(range)
+					.enumerate()
+					.filter(|(_, range)| !range.is_empty())
+					.collect();
+				order.sort_by_key(|(i, range)| (range.start, *i));
+				let mut furthest: Option<(usize, usize)> = None;
+				for (i, range) in order {
+					if let Some((j, end)) = furthest {
+						if range.start < end {
+							overlaps[i].get_or_insert(j);
+						}
+						if end < range.end {
+							furthest = Some((i, range.end));
+						}
+					} else {
+						furthest = Some((i, range.end));
+					}
+				}
+			}
+			for (i, subset) in subsets.iter().enumerate() {
+				let subset_faces = faces_range(subset);
+				let subset_vertices = vertices_range(subset);
+				if faces.len() < subset_faces.end {
+					self.push(Location::Subset(i), DiagnosticKind::SubsetFacesOutOfRange);
+				}
+				if vertex_count < subset_vertices.end {
+					self.push(
+						Location::Subset(i),
+						DiagnosticKind::SubsetVerticesOutOfRange,
+					);
+				}
+				if let Some(j) = overlaps[i] {
+					self.push(Location::Subset(i), DiagnosticKind::SubsetOverlap(j));
+				}
+				if SUBSET_BONE_COUNT < subset.bone_count {
+					self.push(
+						Location::Subset(i),
+						DiagnosticKind::SubsetBoneCount(subset.bone_count),
+					);
+				}
+				for bone in subset.bones.iter().take(subset.bone_count as usize) {
+					// a missing bone is stored as u16::MAX
+					let bone = bone.get().unwrap_or(u16::MAX);
+					if bone_count <= bone as usize {
+						self.push(
+							Location::Subset(i),
+							DiagnosticKind::SubsetBoneOutOfRange(bone),
+						);
+					}
+				}
+				let Some(subset_face_list) = faces.get(subset_faces.clone()) else {
+					continue;
+				};
+				for (face, vertices) in subset_faces.zip(face_indices(subset_face_list)) {
+					if let Some(&vertex) = vertices
+						.iter()
+						.find(|&&vertex| !subset_vertices.contains(&(vertex as usize)))
+					{
+						self.push(
+							Location::Face(face),
+							DiagnosticKind::SubsetFaceOutsideVertices(vertex),
+						);
+					}
+				}
+			}
+		}
+		fn envelopes(&mut self, envelopes: &[Envelope4], subsets: &[Subset4], vertex_count: usize) {
+			if envelopes.is_empty() {
+				return;
+			}
+			if envelopes.len() != vertex_count {
+				self.push(
+					Location::Mesh,
+					DiagnosticKind::EnvelopeCount(envelopes.len()),
+				);
+			}
+			let mut subset_of = vec![None; envelopes.len()];
+			for subset in subsets {
+				let start = (subset.vertices_offset as usize).min(envelopes.len());
+				let end = (start + subset.vertices_len as usize).min(envelopes.len());
+				for vertex_subset in &mut subset_of[start..end] {
+					vertex_subset.get_or_insert(subset);
+				}
+			}
+			for (i, (envelope, subset)) in envelopes.iter().zip(subset_of).enumerate() {
+				let sum: u32 = envelope.weights.iter().map(|&weight| weight as u32).sum();
+				if sum != u8::MAX as u32 {
+					self.push(
+						Location::Envelope(i),
+						DiagnosticKind::EnvelopeWeightSum(sum),
+					);
+				}
+				let Some(subset) = subset else {
+					self.push(Location::Vertex(i), DiagnosticKind::VertexWithoutSubset);
+					continue;
+				};
+				for (&bone, &weight) in envelope.bones.iter().zip(&envelope.weights) {
+					if weight != 0 && subset.bone_count <= bone as u32 {
+						self.push(
+							Location::Envelope(i),
+							DiagnosticKind::EnvelopeBoneOutOfRange(bone),
+						);
+					}
+				}
+			}
+		}
+		fn facs(&mut self, facs: &Facs5) {
+			if let Err(error) = facs.decode() {
+				self.push(Location::Facs, DiagnosticKind::Facs(error));
+			}
+		}
+		#[expect(clippy::too_many_arguments)]
+		fn skinned(
+			&mut self,
+			vertices: &[Vertex2],
+			faces: &[Face2],
+			lods: &[Lod3],
+			envelopes: &[Envelope4],
+			bones: &[Bone4],
+			bone_names: &[u8],
+			subsets: &[Subset4],
+			facs: Option<&Facs5>,
+		) {
+			self.vertex2(vertices);
+			self.faces(face_indices(faces), vertices.len());
+			self.lods(lods, faces.len());
+			self.bones(bones, bone_names);
+			self.subsets(subsets, faces, vertices.len(), bones.len());
+			self.envelopes(envelopes, subsets, vertices.len());
+			if let Some(facs) = facs {
+				self.facs(facs);
+			}
+		}
+	}
+
+	#[cfg(feature = "mesh-v1")]
+	impl crate::mesh::Mesh1 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			let mut diagnostics = Diagnostics::default();
+			diagnostics.finite(
+				self.vertices
+					.iter()
+					.map(|vertex| vertex.pos.into_iter().chain(vertex.norm).chain(vertex.tex)),
+			);
+			if !self.vertices.len().is_multiple_of(3) {
+				diagnostics.push(Location::Mesh, DiagnosticKind::IncompleteFace);
+			}
+			diagnostics.list
+		}
+	}
+	impl Mesh2 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			let mut diagnostics = Diagnostics::default();
+			diagnostics.vertices2(&self.vertices);
+			diagnostics.faces(face_indices(&self.faces), self.vertices.len());
+			diagnostics.list
+		}
+	}
+	impl Mesh3 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			let mut diagnostics = Diagnostics::default();
+			diagnostics.vertices2(&self.vertices);
+			diagnostics.faces(face_indices(&self.faces), self.vertices.len());
+			diagnostics.lods(&self.lods, self.faces.len());
+			diagnostics.list
+		}
+	}
+	impl Mesh4 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			let mut diagnostics = Diagnostics::default();
+			diagnostics.skinned(
+				&self.vertices,
+				&self.faces,
+				&self.lods,
+				&self.envelopes,
+				&self.bones,
+				&self.bone_names,
+				&self.subsets,
+				None,
+			);
+			diagnostics.list
+		}
+	}
+	impl Mesh5 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			let mut diagnostics = Diagnostics::default();
+			diagnostics.skinned(
+				&self.vertices,
+				&self.faces,
+				&self.lods,
+				&self.envelopes,
+				&self.bones,
+				&self.bone_names,
+				&self.subsets,
+				Some(&self.facs),
+			);
+			diagnostics.list
+		}
+	}
+	fn validate_chunks(
+		core_mesh: Option<(&[Vertex2], &[Face2])>,
+		lods: Option<&[Lod3]>,
+		skinning: Option<&crate::mesh::Skinning6>,
+		facs: Option<&Facs5>,
+	) -> Vec<Diagnostic> {
+		let mut diagnostics = Diagnostics::default();
+		let Some((vertices, faces)) = core_mesh else {
+			diagnostics.push(Location::Mesh, DiagnosticKind::MissingCoreMesh);
+			return diagnostics.list;
+		};
+		let (envelopes, bones, bone_names, subsets) = match skinning {
+			Some(skinning) => (
+				&skinning.envelopes[..],
+				&skinning.bones[..],
+				&skinning.bone_names[..],
+				&skinning.subsets[..],
+			),
+			None => (&[][..], &[][..], &[][..], &[][..]),
+		};
+		diagnostics.skinned(
+			vertices,
+			faces,
+			lods.unwrap_or_default(),
+			envelopes,
+			bones,
+			bone_names,
+			subsets,
+			facs,
+		);
+		diagnostics.list
+	}
+	impl Mesh6 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			validate_chunks(
+				self.core_mesh()
+					.map(|core_mesh| (&core_mesh.vertices[..], &core_mesh.faces[..])),
+				self.lods().map(|lods| &lods.lods[..]),
+				self.skinning(),
+				self.facs(),
+			)
+		}
+	}
+	impl Mesh7 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			validate_chunks(
+				self.core_mesh()
+					.map(|core_mesh| (&core_mesh.vertices[..], &core_mesh.faces[..])),
+				self.lods().map(|lods| &lods.lods[..]),
+				self.skinning(),
+				self.facs(),
+			)
+		}
+	}
+	impl Mesh {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			match self {
+				#[cfg(feature = "mesh-v1")]
+				Mesh::V1(mesh) => mesh.validate(),
+				Mesh::V2(mesh) => mesh.validate(),
+				Mesh::V3(mesh) => mesh.validate(),
+				Mesh::V4(mesh) => mesh.validate(),
+				Mesh::V5(mesh) => mesh.validate(),
+				Mesh::V6(mesh) => mesh.validate(),
+				Mesh::V7(mesh) => mesh.validate(),
+			}
+		}
+	}
+}
+
+#[cfg(feature = "union-graphics")]
+mod union_graphics {
+	use super::{Diagnostic, DiagnosticKind, Diagnostics, Location};
+	use crate::union_graphics::{CSGMDL2, CSGMDL4, CSGMDL5, Mesh2, UnionGraphics};
+
+	impl Diagnostics {
+		fn attribute_length(&mut self, name: &'static str, len: usize, vertex_count: usize) {
+			if len != 0 && len != vertex_count {
+				self.push(Location::Mesh, DiagnosticKind::AttributeLength(name));
+			}
+		}
+	}
+
+	impl Mesh2 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			let mut diagnostics = Diagnostics::default();
+			diagnostics.finite(self.vertices.iter().map(|vertex| {
+				vertex
+					.pos
+					.into_iter()
+					.chain(vertex.norm)
+					.chain(vertex.tex)
+					.chain(vertex.tangent)
+			}));
+			diagnostics.faces(
+				self.faces
+					.iter()
+					.map(|face| [face[0].0, face[1].0, face[2].0]),
+				self.vertices.len(),
+			);
+			diagnostics.list
+		}
+	}
+	impl CSGMDL2 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			self.mesh.validate()
+		}
+	}
+	impl CSGMDL4 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			self.mesh.validate()
+		}
+	}
+	impl CSGMDL5 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			let mut diagnostics = Diagnostics::default();
+			diagnostics.finite(self.positions.iter().copied());
+			let vertex_count = self.positions.len();
+			diagnostics.attribute_length("normals", self.normals.len(), vertex_count);
+			diagnostics.attribute_length("colors", self.colors.len(), vertex_count);
+			diagnostics.attribute_length("normal_ids", self.normal_ids.len(), vertex_count);
+			diagnostics.attribute_length("tex", self.tex.len(), vertex_count);
+			diagnostics.attribute_length("tangents", self.tangents.len(), vertex_count);
+			let (faces, remainder) = self.faces.indices.as_chunks();
+			if !remainder.is_empty() {
+				diagnostics.push(Location::Mesh, DiagnosticKind::IncompleteFace);
+			}
+			diagnostics.faces(faces.iter().copied(), vertex_count);
+			// assumed to be more indices, like the main list
+			for (i, indices) in self.faces._unknown.iter().enumerate() {
+				if !indices.len().is_multiple_of(3) {
+					diagnostics.push(Location::UnknownFaces(i), DiagnosticKind::IncompleteFace);
+				}
+				if let Some(&vertex) = indices
+					.iter()
+					.find(|&&vertex| vertex_count <= vertex as usize)
+				{
+					diagnostics.push(
+						Location::UnknownFaces(i),
+						DiagnosticKind::FaceIndexOutOfRange(vertex),
+					);
+				}
+			}
+			diagnostics.list
+		}
+	}
+	impl UnionGraphics {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			match self {
+				UnionGraphics::CSGK(_) => Vec::new(),
+				UnionGraphics::V2(mesh) => mesh.validate(),
+				UnionGraphics::V4(mesh) => mesh.validate(),
+				UnionGraphics::V5(mesh) => mesh.validate(),
+			}
+		}
+	}
+}
+
+#[cfg(feature = "union-physics")]
+mod union_physics {
+	use super::{Diagnostic, Diagnostics};
+	use crate::union_physics::{CSGPHS3, CSGPHS5, CSGPHS6, CSGPHS7, Mesh, UnionPhysics};
+
+	impl Diagnostics {
+		fn physics_meshes<'a>(&mut self, meshes: impl IntoIterator<Item = &'a Mesh>) {
+			for (i, mesh) in meshes.into_iter().enumerate() {
+				self.mesh = Some(i);
+				self.finite(mesh.positions.iter().copied());
+				self.faces(
+					mesh.faces
+						.iter()
+						.map(|face| [face[0].0, face[1].0, face[2].0]),
+					mesh.positions.len(),
+				);
+			}
+			self.mesh = None;
+		}
+	}
+
+	impl CSGPHS3 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			let mut diagnostics = Diagnostics::default();
+			diagnostics.physics_meshes(&self.meshes);
+			diagnostics.list
+		}
+	}
+	impl CSGPHS5 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			let mut diagnostics = Diagnostics::default();
+			diagnostics.physics_meshes(&self.meshes);
+			diagnostics.list
+		}
+	}
+	impl CSGPHS6 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			let mut diagnostics = Diagnostics::default();
+			diagnostics.physics_meshes([&self.mesh]);
+			diagnostics.list
+		}
+	}
+	impl CSGPHS7 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			let mut diagnostics = Diagnostics::default();
+			diagnostics.physics_meshes(&self.meshes);
+			diagnostics.list
+		}
+	}
+	#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+	impl crate::union_physics::Hulls {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			use super::{DiagnosticKind, Location};
+			let mut diagnostics = Diagnostics::default();
+			let valid_ranges = |ranges: &[u32], len: usize| {
+				ranges.first().is_none_or(|&start| start == 0)
+					&& ranges.is_sorted()
+					&& ranges
+						.iter()
+						.all(|&end| end as usize <= len && end.is_multiple_of(3))
+			};
+			if self.face_ranges.len() != self.pos_ranges.len()
+				|| !valid_ranges(&self.face_ranges, self.faces.len())
+				|| !valid_ranges(&self.pos_ranges, self.positions.len())
+			{
+				diagnostics.push(Location::Mesh, DiagnosticKind::HullRanges);
+				return diagnostics.list;
+			}
+			for (i, hull) in self.iter_hulls().enumerate() {
+				diagnostics.mesh = Some(i);
+				diagnostics.finite(hull.positions.iter().copied());
+				diagnostics.faces(hull.faces.iter().copied(), hull.positions.len());
+			}
+			diagnostics.list
+		}
+	}
+	#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+	impl crate::union_physics::CSGPHS8 {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			self.mesh.hulls.validate()
+		}
+	}
+	impl UnionPhysics {
+		pub fn validate(&self) -> Vec<Diagnostic> {
+			match self {
+				UnionPhysics::CSGK(_) | UnionPhysics::Block(_) => Vec::new(),
+				UnionPhysics::V3(mesh) => mesh.validate(),
+				UnionPhysics::V5(mesh) => mesh.validate(),
+				UnionPhysics::V6(mesh) => mesh.validate(),
+				UnionPhysics::V7(mesh) => mesh.validate(),
+				#[cfg(any(feature = "csgphs-v8-zstd", feature = "csgphs-v8-ruzstd"))]
+				UnionPhysics::V8(mesh) => mesh.validate(),
+			}
+		}
+	}
+}